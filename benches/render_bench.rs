@@ -0,0 +1,77 @@
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use std::io::Cursor;
+use termbox::{
+    calculate_cursor_position, calculate_required_lines, draw_prompt_line_to_buffer,
+    render_text_lines,
+};
+
+const SIZES: [usize; 4] = [10, 100, 1_000, 10_000];
+const WIDTHS: [usize; 3] = [40, 80, 120];
+
+/// `len` characters of words, with a newline after every 17th word
+fn text(len: usize) -> String {
+    let words = ["render", "frame", "é", "terminal", "wrap", "input"];
+    words
+        .iter()
+        .cycle()
+        .enumerate()
+        .flat_map(|(i, word)| {
+            let separator = if i % 17 == 16 { '\n' } else { ' ' };
+            word.chars().chain([separator])
+        })
+        .take(len)
+        .collect()
+}
+
+/// Runs `f` with the text, the terminal width and the frame height for
+/// every combination of input size and width
+fn bench_sizes(c: &mut Criterion, name: &str, f: impl Fn(&str, usize, usize)) {
+    let mut group = c.benchmark_group(name);
+    for len in SIZES {
+        let text = text(len);
+        for cols in WIDTHS {
+            let rows = calculate_required_lines(&text, cols);
+            group.bench_with_input(
+                BenchmarkId::new(format!("{cols}_cols"), len),
+                &text,
+                |b, text| b.iter(|| f(black_box(text), black_box(cols), black_box(rows))),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn required_lines(c: &mut Criterion) {
+    bench_sizes(c, "calculate_required_lines", |text, cols, _| {
+        calculate_required_lines(text, cols);
+    });
+}
+
+fn render_lines(c: &mut Criterion) {
+    bench_sizes(c, "render_text_lines", |text, cols, _| {
+        render_text_lines(text, cols);
+    });
+}
+
+fn draw_prompt(c: &mut Criterion) {
+    // In a terminal just tall enough for the frame
+    bench_sizes(c, "draw_prompt_line_to_buffer", |text, cols, rows| {
+        let mut out = Cursor::new(Vec::new());
+        draw_prompt_line_to_buffer(&mut out, text, (cols, rows), rows).unwrap();
+    });
+}
+
+fn cursor_position(c: &mut Criterion) {
+    bench_sizes(c, "calculate_cursor_position", |text, cols, rows| {
+        calculate_cursor_position(text, cols, rows, rows);
+    });
+}
+
+criterion_group!(
+    benches,
+    required_lines,
+    render_lines,
+    draw_prompt,
+    cursor_position
+);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use termbox::{WrapCache, calculate_required_lines};
+
+/// 10 000 characters of words, with a newline after every 17th word
+fn long_text() -> String {
+    let words = ["wrap", "cache", "é", "terminal", "frame", "input"];
+    words
+        .iter()
+        .cycle()
+        .enumerate()
+        .flat_map(|(i, word)| {
+            let separator = if i % 17 == 16 { '\n' } else { ' ' };
+            word.chars().chain([separator])
+        })
+        .take(10_000)
+        .collect()
+}
+
+fn wrap_cache(c: &mut Criterion) {
+    let text = long_text();
+    let cache = WrapCache::new(&text, 80);
+
+    let mut group = c.benchmark_group("required_lines_10k");
+    group.bench_function("calculate", |b| {
+        b.iter(|| calculate_required_lines(black_box(&text), black_box(80)))
+    });
+    group.bench_function("cached", |b| {
+        b.iter(|| cache.get(black_box(&text), black_box(80)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, wrap_cache);
+criterion_main!(benches);
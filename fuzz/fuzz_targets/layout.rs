@@ -0,0 +1,34 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use termbox::{
+    FRAME_CHARS, calculate_cursor_position, calculate_required_lines, render_text_lines,
+};
+
+// Terminal sizes come from crossterm as u16, so larger widths never occur
+#[derive(Debug, Arbitrary)]
+struct Input {
+    text: String,
+    cols: u16,
+    rows: u16,
+    required_lines: u16,
+}
+
+fuzz_target!(|input: Input| {
+    let Input {
+        text,
+        cols,
+        rows,
+        required_lines,
+    } = input;
+    let (cols, rows, required_lines) = (cols as usize, rows as usize, required_lines as usize);
+
+    let lines = calculate_required_lines(&text, cols);
+    assert!(lines >= 3, "{lines} lines for {cols} columns");
+    if cols > FRAME_CHARS {
+        assert_eq!(lines, render_text_lines(&text, cols).len());
+    }
+
+    calculate_cursor_position(&text, cols, rows, required_lines);
+});
@@ -0,0 +1,455 @@
+use crossterm::{cursor::MoveTo, queue, style::Print};
+use std::future::Future;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, sleep, timeout_at};
+
+use crate::{InputState, ScrollEvent, ui};
+
+/// Rows taken by a bordered animation box: the frame line and its border
+pub const BOX_ANIMATION_HEIGHT: usize = 3;
+
+/// Former name of [`BOX_ANIMATION_HEIGHT`], from when only `/tiktok` drew a box
+pub const TIKTOK_ANIMATION_HEIGHT: usize = BOX_ANIMATION_HEIGHT;
+
+/// Rows taken by a spinner, which has no border
+pub const SPINNER_ANIMATION_HEIGHT: usize = 1;
+
+/// How long a box animation may run before it jumps to its last frame,
+/// unless `Config::default_animation_timeout` says otherwise
+pub const DEFAULT_ANIMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Handle to a running background animation
+#[derive(Debug)]
+pub struct AnimationHandle {
+    cancel_tx: oneshot::Sender<()>,
+    /// Set by the task when the animation stops on an error
+    error: Arc<Mutex<Option<anyhow::Error>>>,
+}
+
+impl AnimationHandle {
+    /// Create a handle together with the receiver the animation task listens on
+    pub fn new() -> (Self, oneshot::Receiver<()>) {
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let error = Arc::default();
+        (Self { cancel_tx, error }, cancel_rx)
+    }
+
+    /// Whether the animation has ended, by running out of frames, failing or
+    /// being cancelled
+    pub fn is_finished(&self) -> bool {
+        // The task drops its receiver and its reporter when it returns
+        self.cancel_tx.is_closed() && Arc::strong_count(&self.error) == 1
+    }
+
+    /// The error the animation stopped on, if any. Returns it only once.
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.error.lock().unwrap().take()
+    }
+
+    /// Called by the animation task with its result, to keep an error for
+    /// [`take_error`](Self::take_error) instead of printing over the UI
+    pub fn error_reporter(&self) -> impl FnOnce(anyhow::Result<()>) + Send + 'static {
+        let error = self.error.clone();
+        move |result| {
+            if let Err(e) = result {
+                *error.lock().unwrap() = Some(e);
+            }
+        }
+    }
+
+    /// Stop the animation before its next step. Dropping the handle instead
+    /// lets the animation run to completion.
+    pub fn cancel(self) {
+        // The task may already have finished and dropped its receiver
+        let _ = self.cancel_tx.send(());
+    }
+}
+
+/// Background animation tasks that can be counted and stopped together.
+///
+/// Clones share the same tasks. The built-in commands spawn their animations
+/// on [`AnimationRegistry::global`].
+#[derive(Debug, Clone, Default)]
+pub struct AnimationRegistry {
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl AnimationRegistry {
+    /// An empty registry, separate from the global one
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The registry shared by the built-in animations and `CommandDispatcher`
+    pub fn global() -> &'static AnimationRegistry {
+        static GLOBAL: OnceLock<AnimationRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(AnimationRegistry::new)
+    }
+
+    /// Run `animation` as a Tokio task tracked by this registry. Must be
+    /// called from within a Tokio runtime.
+    pub fn spawn(&self, animation: impl Future<Output = ()> + Send + 'static) {
+        let task = tokio::spawn(animation);
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// Number of animations still running
+    pub fn active_count(&self) -> usize {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.len()
+    }
+
+    /// Abort every animation. They stop at their next frame without drawing
+    /// anything more, so this is safe to call while restoring the terminal.
+    pub fn cancel_all(&self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Plays `frames` in a box `width` columns wide at the bottom of the scroll
+/// region, one frame per `interval`, on a task of the global registry.
+///
+/// Rows for the box are reserved by scrolling the content up first. The
+/// animation stops early when the returned handle is cancelled, and shows
+/// its last frame once `timeout` has passed.
+pub fn spawn_box_animation(
+    state: &mut InputState,
+    out: Arc<Mutex<std::io::Stdout>>,
+    width: usize,
+    frames: Vec<String>,
+    (interval, timeout): (Duration, Duration),
+) -> anyhow::Result<AnimationHandle> {
+    let scroll_rx = state.setup_scroll_broadcast();
+    let scroll_region_bottom = state.scroll_region_rows();
+
+    // Reserve the rows of the box by scrolling the content above it up
+    {
+        let mut out_guard = out.lock().unwrap();
+        let blank_rows = "\n".repeat(BOX_ANIMATION_HEIGHT - 1);
+        ui::print_to_scroll_region(&mut *out_guard, &blank_rows, state)?;
+    }
+
+    let (handle, mut cancel_rx) = AnimationHandle::new();
+    let report = handle.error_reporter();
+    let rows = state.rows;
+    // The box ends on the bottom row of the scroll region
+    let box_top = scroll_region_bottom.saturating_sub(BOX_ANIMATION_HEIGHT - 1);
+
+    AnimationRegistry::global().spawn(async move {
+        let animation = run_timed_animation(
+            out,
+            (width, rows),
+            box_top,
+            frames,
+            (interval, timeout),
+            scroll_rx,
+        );
+        let result = tokio::select! {
+            result = animation => result,
+            Ok(()) = &mut cancel_rx => Ok(()),
+        };
+        report(result);
+    });
+
+    Ok(handle)
+}
+
+/// Applies all pending scroll events to `lines_scrolled`, the net number of
+/// lines content has moved up since an animation box was drawn.
+///
+/// If the receiver lagged, each skipped event is assumed to have scrolled
+/// content up by one line. Returns `false` once the channel is closed.
+pub fn drain_scroll_events(
+    scroll_rx: &mut broadcast::Receiver<ScrollEvent>,
+    lines_scrolled: &mut usize,
+) -> bool {
+    for_each_pending(scroll_rx, |event| {
+        *lines_scrolled = match event {
+            ScrollEvent::ScrolledUp(lines) => lines_scrolled.saturating_add(lines),
+            ScrollEvent::ScrolledDown(lines) => lines_scrolled.saturating_sub(lines),
+        };
+    })
+}
+
+/// Calls `apply` with every pending event, passing a lag of N skipped events
+/// as `ScrolledUp(N)`. Returns `false` once the channel is closed.
+fn for_each_pending(
+    scroll_rx: &mut broadcast::Receiver<ScrollEvent>,
+    mut apply: impl FnMut(ScrollEvent),
+) -> bool {
+    loop {
+        match scroll_rx.try_recv() {
+            Ok(event) => apply(event),
+            Err(broadcast::error::TryRecvError::Empty) => return true,
+            Err(broadcast::error::TryRecvError::Closed) => return false,
+            Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                apply(ScrollEvent::ScrolledUp(skipped as usize));
+            }
+        }
+    }
+}
+
+/// Follows the scroll events an animation receives, to find where the rows
+/// it drew have moved
+#[derive(Debug)]
+pub struct ScrollTracker {
+    pub receiver: broadcast::Receiver<ScrollEvent>,
+    pub total_scrolled_up: usize,
+    pub total_scrolled_down: usize,
+    /// Net lines scrolled up, never below zero; see [`drain_scroll_events`]
+    lines_scrolled: usize,
+}
+
+impl ScrollTracker {
+    /// Track the events arriving on `receiver` from now on
+    pub fn new(receiver: broadcast::Receiver<ScrollEvent>) -> Self {
+        Self {
+            receiver,
+            total_scrolled_up: 0,
+            total_scrolled_down: 0,
+            lines_scrolled: 0,
+        }
+    }
+
+    /// Apply all pending events. Returns `false` once the channel is closed.
+    pub fn drain(&mut self) -> bool {
+        let Self {
+            receiver,
+            total_scrolled_up,
+            total_scrolled_down,
+            lines_scrolled,
+        } = self;
+        for_each_pending(receiver, |event| match event {
+            ScrollEvent::ScrolledUp(lines) => {
+                *total_scrolled_up = total_scrolled_up.saturating_add(lines);
+                *lines_scrolled = lines_scrolled.saturating_add(lines);
+            }
+            ScrollEvent::ScrolledDown(lines) => {
+                *total_scrolled_down = total_scrolled_down.saturating_add(lines);
+                *lines_scrolled = lines_scrolled.saturating_sub(lines);
+            }
+        })
+    }
+
+    /// Where a row drawn at `original_row` is now, clamped to the top of the
+    /// terminal. Scrolling down never moves it below `original_row`.
+    pub fn adjust_row(&self, original_row: usize) -> usize {
+        box_top_after_scroll(original_row, self.lines_scrolled)
+    }
+}
+
+/// Row of an animation box drawn at `box_top` after content scrolled up by
+/// `lines_scrolled`, clamped to the top of the terminal
+pub fn box_top_after_scroll(box_top: usize, lines_scrolled: usize) -> usize {
+    box_top.saturating_sub(lines_scrolled)
+}
+
+/// Plays `frames` inside a 3-line box, one frame per `interval`.
+///
+/// The box is drawn at `box_top` with the first frame, then only its middle
+/// line is redrawn for each following frame. Scroll events move the box up
+/// with the surrounding content; frames that would land below the terminal
+/// are skipped.
+///
+/// Frames are written at most once per `rate_limit`, so a slow terminal is
+/// not flooded with updates and the output lock is not fought over while
+/// the user types. A frame due sooner than that after the last write is
+/// skipped in favor of the ones after it, but the last frame is always
+/// written. `Duration::ZERO` writes every frame.
+///
+/// # Arguments
+///
+/// * `out` - Shared output handle, normally stdout
+/// * `cols` - Terminal width in columns
+/// * `rows` - Terminal height in rows
+/// * `box_top` - The row where the top of the animation box should be drawn
+/// * `frames` - Text shown on the middle line of the box, in order
+/// * `interval` - Delay between frames
+/// * `rate_limit` - Shortest time between two writes to `out`
+/// * `scroll_rx` - Receiver for scroll events
+///
+/// # Returns
+///
+/// Returns `Ok(())` once all frames are shown or the scroll channel closes.
+pub async fn run_animation<W: Write>(
+    out: Arc<Mutex<W>>,
+    cols: usize,
+    rows: usize,
+    box_top: usize,
+    frames: impl IntoIterator<Item = String>,
+    (interval, rate_limit): (Duration, Duration),
+    scroll_rx: broadcast::Receiver<ScrollEvent>,
+) -> anyhow::Result<()> {
+    play_frames(
+        out,
+        (cols, rows),
+        (box_top, BOX_ANIMATION_HEIGHT),
+        frames.into_iter(),
+        (interval, rate_limit, None),
+        scroll_rx,
+    )
+    .await
+}
+
+/// Like [`run_animation`], but once `timeout` has passed the box skips
+/// straight to the last of `frames` and the animation ends, so a long
+/// animation cannot run forever.
+pub async fn run_timed_animation<W: Write>(
+    out: Arc<Mutex<W>>,
+    (cols, rows): (usize, usize),
+    box_top: usize,
+    frames: impl IntoIterator<Item = String>,
+    (interval, timeout): (Duration, Duration),
+    scroll_rx: broadcast::Receiver<ScrollEvent>,
+) -> anyhow::Result<()> {
+    play_frames(
+        out,
+        (cols, rows),
+        (box_top, BOX_ANIMATION_HEIGHT),
+        frames.into_iter(),
+        (
+            interval,
+            Duration::ZERO,
+            Instant::now().checked_add(timeout),
+        ),
+        scroll_rx,
+    )
+    .await
+}
+
+/// Plays `frames` on a single line without a border, one frame per
+/// `interval` and at most one write per `rate_limit`. Behaves like
+/// [`run_animation`] otherwise; pass an endless iterator such as
+/// `frames.into_iter().cycle()` to animate until cancelled.
+pub async fn run_line_animation<W: Write>(
+    out: Arc<Mutex<W>>,
+    cols: usize,
+    rows: usize,
+    line: usize,
+    frames: impl IntoIterator<Item = String>,
+    (interval, rate_limit): (Duration, Duration),
+    scroll_rx: broadcast::Receiver<ScrollEvent>,
+) -> anyhow::Result<()> {
+    play_frames(
+        out,
+        (cols, rows),
+        (line, SPINNER_ANIMATION_HEIGHT),
+        frames.into_iter(),
+        (interval, rate_limit, None),
+        scroll_rx,
+    )
+    .await
+}
+
+/// Shared frame loop for animations `height` lines tall. Heights above one
+/// get a border, with frames drawn on the middle line. Frames other than the
+/// first and last are written at most once per `rate_limit`, see
+/// [`run_animation`]. At `deadline` the rest of `frames` is skipped to its
+/// last one, which must therefore be finite when a deadline is given.
+async fn play_frames<W: Write>(
+    out: Arc<Mutex<W>>,
+    (cols, rows): (usize, usize),
+    (box_top, height): (usize, usize),
+    frames: impl Iterator<Item = String>,
+    (interval, rate_limit, deadline): (Duration, Duration, Option<Instant>),
+    mut scroll_rx: broadcast::Receiver<ScrollEvent>,
+) -> anyhow::Result<()> {
+    let mut frames = frames.peekable();
+    let Some(first_frame) = frames.next() else {
+        return Ok(());
+    };
+    let bordered = height > 1;
+    let horizontal_line = "─".repeat(cols.saturating_sub(2));
+    let frame_line = |frame: &str| {
+        if bordered {
+            format!("│ {:<width$} │", frame, width = cols.saturating_sub(4))
+        } else {
+            format!("{:<width$}", frame, width = cols)
+        }
+    };
+
+    // Clear any pending scroll events that occurred before we started
+    while scroll_rx.try_recv().is_ok() {
+        // Discard events that happened before animation started
+    }
+    let mut scroll = ScrollTracker::new(scroll_rx);
+
+    // Draw the complete box at the specified position - no additional scrolling
+    {
+        let mut out_guard = out.lock().unwrap();
+        if bordered {
+            queue!(
+                out_guard,
+                MoveTo(0, box_top as u16),
+                Print(format!("╭{}╮", horizontal_line))
+            )?;
+        }
+        queue!(
+            out_guard,
+            MoveTo(0, (box_top + height / 2) as u16),
+            Print(frame_line(&first_frame))
+        )?;
+        if bordered {
+            queue!(
+                out_guard,
+                MoveTo(0, (box_top + height - 1) as u16),
+                Print(format!("╰{}╯", horizontal_line))
+            )?;
+        }
+        out_guard.flush()?;
+    }
+    let mut last_write = Instant::now();
+
+    while let Some(mut frame) = frames.next() {
+        // Sleep first to allow time for the previous frame to be visible
+        let timed_out = match deadline {
+            Some(deadline) => timeout_at(deadline, sleep(interval)).await.is_err(),
+            None => {
+                sleep(interval).await;
+                false
+            }
+        };
+        if timed_out {
+            // Out of time: show the animation as complete right away
+            if let Some(last) = frames.by_ref().last() {
+                frame = last;
+            }
+        }
+
+        // Check for any scroll events that occurred during sleep
+        if !scroll.drain() {
+            return Ok(());
+        }
+
+        // Too soon after the last write: a later frame replaces this one
+        let is_last = frames.peek().is_none();
+        if !is_last && last_write.elapsed() < rate_limit {
+            continue;
+        }
+        last_write = Instant::now();
+
+        // The box has moved up by the number of lines scrolled
+        let frame_line_position = scroll.adjust_row(box_top) + height / 2;
+
+        // Only update if the frame line is still visible on screen
+        if frame_line_position < rows {
+            let mut out_guard = out.lock().unwrap();
+            queue!(
+                out_guard,
+                MoveTo(0, frame_line_position as u16),
+                Print(frame_line(&frame))
+            )?;
+            out_guard.flush()?;
+        }
+    }
+
+    Ok(())
+}
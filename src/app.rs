@@ -0,0 +1,693 @@
+use crossterm::{
+    cursor::MoveTo,
+    event::{EnableBracketedPaste, EnableMouseCapture, Event, EventStream},
+    execute, queue,
+    style::Print,
+    terminal::{self, enable_raw_mode},
+};
+use futures::{Stream, StreamExt};
+use std::io::{Stdout, Write, stdout};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::sync::mpsc;
+#[cfg(feature = "serde")]
+use {
+    crate::InputStateSnapshot, crate::error::TermboxError, std::path::PathBuf, std::time::Duration,
+    tokio::sync::watch, tokio::task::JoinHandle, tokio::time::MissedTickBehavior,
+};
+
+use crate::animation::AnimationRegistry;
+use crate::{
+    Config, FramePosition, InputMode, InputState, KeyAction, ResizeAction, ScrollEvent,
+    calculate_required_lines, commands, guard, signals, ui,
+};
+
+/// Runs the interactive input box on a terminal until the user exits.
+///
+/// Submitted text is run as a command or printed to the scroll region; see
+/// `TermboxApp::run_loop`.
+///
+/// # Arguments
+///
+/// * `config` - Settings applied to the input state
+///
+/// # Returns
+///
+/// Returns `Ok(())` on successful completion or an error if terminal operations fail.
+pub async fn run_tty(config: Config) -> anyhow::Result<()> {
+    TermboxApp::new(config)?.run_loop(|_| {}).await
+}
+
+/// Where `TermboxApp` reads terminal events from
+type EventSource = Pin<Box<dyn Stream<Item = std::io::Result<Event>> + Send>>;
+
+/// Runs the input box on `events` instead of the terminal's, until an exit
+/// key or the end of `events`, and returns everything that was submitted.
+///
+/// The box is drawn to stdout for an 80x24 terminal, but raw mode is never
+/// enabled, so this works without a terminal, e.g. in tests.
+///
+/// # Returns
+///
+/// Returns the submissions in order, or an error if drawing fails.
+pub async fn run_with_events(events: Vec<Event>, config: Config) -> anyhow::Result<Vec<String>> {
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    for event in events {
+        event_tx.send(Ok(event))?;
+    }
+    // The stream ends once the events are used up
+    drop(event_tx);
+    let events = futures::stream::poll_fn(move |cx| event_rx.poll_recv(cx));
+
+    let mut app = TermboxApp::with_event_source(config, (80, 24), Box::pin(events), None)?;
+    let mut submitted = Vec::new();
+    while let Some(text) = app.next_submission().await? {
+        submitted.push(text);
+    }
+    Ok(submitted)
+}
+
+/// An input box fixed to the bottom of the terminal.
+///
+/// Sets up a terminal-based input interface with the following features:
+/// - Raw mode terminal input handling
+/// - Dynamic frame sizing based on input text length
+/// - Scroll region management to keep the input box at the bottom
+/// - Multi-line text input with word wrapping
+/// - Proper cleanup when dropped, including the frame and cursor position
+///
+/// The rest of the terminal keeps scrolling normally above the box.
+pub struct TermboxApp {
+    pub state: InputState,
+    pub out: Arc<Mutex<Stdout>>,
+    pub config: Config,
+    pub command_dispatcher: commands::CommandDispatcher,
+    event_stream: EventSource,
+    resize_rx: mpsc::Receiver<()>,
+    terminal_guard: Option<guard::TermboxGuard>,
+    /// Feeds the auto-save task started for `Config::auto_save`
+    #[cfg(feature = "serde")]
+    auto_save: Option<(watch::Sender<InputStateSnapshot>, AutoSaveTask)>,
+}
+
+impl TermboxApp {
+    /// Takes over the terminal and draws the empty input box.
+    ///
+    /// Enables raw mode, bracketed paste and mouse capture, pushes existing
+    /// content up to make room for the box and sets the scroll region above
+    /// it. With `Config::auto_save` set, the saved session is restored if
+    /// its file exists and saving starts in the background.
+    ///
+    /// # Returns
+    ///
+    /// Returns the app or an error if the terminal cannot be set up.
+    pub fn new(config: Config) -> anyhow::Result<Self> {
+        let (cols, rows) = terminal::size()?;
+        let (cols, rows) = (cols as usize, rows as usize);
+        enable_raw_mode()?;
+        // Restores the terminal even when a panic skips the guard's drop
+        guard::set_panic_hook();
+        // Restores the terminal however the app exits, including on panic
+        let mut terminal_guard = guard::TermboxGuard::new(cols, rows);
+        // The dispatcher's commands spawn their animations on the global registry
+        terminal_guard.cancel_animations_on_drop(AnimationRegistry::global().clone());
+        // Deliver pastes as a single event instead of one key event per character
+        execute!(stdout(), EnableBracketedPaste, EnableMouseCapture)?;
+
+        let mut app = Self::with_event_source(
+            config,
+            (cols, rows),
+            Box::pin(EventStream::new()),
+            Some(terminal_guard),
+        )?;
+        // SIGWINCH catches resizes that never show up as Event::Resize
+        app.resize_rx = signals::window_change_channel()?;
+        Ok(app)
+    }
+
+    /// Draws the empty input box for a `cols` x `rows` terminal and reads
+    /// events from `event_stream`. Raw mode and the rest of the terminal
+    /// setup are left to the caller, and resizes only arrive as events.
+    fn with_event_source(
+        config: Config,
+        (cols, rows): (usize, usize),
+        event_stream: EventSource,
+        terminal_guard: Option<guard::TermboxGuard>,
+    ) -> anyhow::Result<Self> {
+        let out = Arc::new(Mutex::new(stdout()));
+        let command_dispatcher =
+            commands::CommandDispatcher::with_animation_timeout(config.default_animation_timeout);
+
+        // ── 1. reserve the bottom lines ──────────────────────────────────
+        let mut state = InputState::with_config(cols, rows, config.clone());
+        #[cfg(feature = "serde")]
+        {
+            state = restore_session(state, &config)?;
+        }
+        {
+            let mut out_guard = out.lock().unwrap();
+            // Push existing terminal content up to make space for the input frame
+            // A frame at the top is drawn over what was there instead
+            if state.frame_position != FramePosition::Top {
+                let pushed = rows - state.frame_start_row();
+                ui::push_content_up(&mut *out_guard, rows, pushed)?;
+            }
+            ui::set_scroll_region_for(&mut *out_guard, &state)?;
+        }
+
+        // ── 2. draw the static box once ──────────────────────────────────
+        {
+            let mut out_guard = out.lock().unwrap();
+            redraw_box(&mut out_guard, &mut state)?;
+        }
+
+        Ok(Self {
+            out,
+            command_dispatcher,
+            event_stream,
+            // Closed until `new` replaces it, so it never yields anything
+            resize_rx: mpsc::channel(1).1,
+            terminal_guard,
+            #[cfg(feature = "serde")]
+            auto_save: config.auto_save.clone().map(|(path, period)| {
+                let (snapshot_tx, snapshot_rx) = watch::channel(state.snapshot());
+                (snapshot_tx, spawn_auto_save(path, period, snapshot_rx))
+            }),
+            state,
+            config,
+        })
+    }
+
+    /// Waits until the user submits one input and returns it.
+    ///
+    /// The submission is handled like in `run_loop`: commands run and other
+    /// text is printed to the scroll region. In password mode nothing runs as
+    /// a command; wipe the returned password with `termbox::zeroize` once done
+    /// with it.
+    ///
+    /// # Returns
+    ///
+    /// Returns the submitted text, or an error if the user exits (Esc, Ctrl+C
+    /// or Ctrl+D), an event cannot be read or terminal operations fail.
+    pub async fn run_once(&mut self) -> anyhow::Result<String> {
+        match self.next_submission().await? {
+            Some(text) => Ok(text),
+            None => anyhow::bail!("input closed before anything was submitted"),
+        }
+    }
+
+    /// Calls `callback` with each submitted input until the user exits, then
+    /// restores the terminal.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on successful completion or an error if an event
+    /// cannot be read or terminal operations fail.
+    pub async fn run_loop<F: Fn(String)>(mut self, callback: F) -> anyhow::Result<()> {
+        while let Some(text) = self.next_submission().await? {
+            callback(text);
+        }
+        self.cleanup()
+    }
+
+    /// Handles events until a submission, or `None` once the user exits. An
+    /// error reading the next event ends the loop with that error.
+    async fn next_submission(&mut self) -> anyhow::Result<Option<String>> {
+        // ── 3. main loop ─────────────────────────────────────────────────
+        loop {
+            self.publish_snapshot();
+            let current_size = (self.state.cols, self.state.rows);
+            if let Some(terminal_guard) = &mut self.terminal_guard {
+                terminal_guard.set_size(self.state.cols, self.state.rows);
+            }
+            let indicator_deadline = self.state.max_lines_indicator_deadline();
+            let event = tokio::select! {
+                event = self.event_stream.next() => event,
+                _ = tokio::time::sleep_until(indicator_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                    if indicator_deadline.is_some() =>
+                {
+                    self.state.max_lines_reached = None;
+                    ui::draw_input(&mut self.out.lock().unwrap(), &mut self.state)?;
+                    continue;
+                }
+                Some((new_cols, new_rows)) =
+                    signals::next_window_size(&mut self.resize_rx, current_size, terminal::size) =>
+                {
+                    handle_resize(new_cols, new_rows, &mut self.state, self.out.clone())?;
+                    continue;
+                }
+            };
+
+            match event {
+                Some(Ok(Event::Key(key))) => {
+                    match handle_key_event(
+                        key,
+                        &mut self.state,
+                        self.out.clone(),
+                        &self.command_dispatcher,
+                    )
+                    .await?
+                    {
+                        KeyAction::Exit => return Ok(None),
+                        KeyAction::Submit(text) => return Ok(Some(text)),
+                        KeyAction::Suspend => self.suspend()?,
+                        KeyAction::Continue | KeyAction::ClearScreen | KeyAction::ShowHelp => {}
+                    }
+                }
+
+                Some(Ok(Event::Paste(text))) => {
+                    let old_required_lines = self.state.required_lines;
+                    self.state.handle_paste(&text);
+                    update_frame_if_needed(&mut self.state, self.out.clone(), old_required_lines)?;
+                }
+
+                Some(Ok(Event::Mouse(mouse))) => {
+                    if self.state.handle_mouse(mouse) {
+                        let mut out_guard = self.out.lock().unwrap();
+                        ui::draw_input(&mut out_guard, &mut self.state)?;
+                    }
+                }
+
+                // Fallback for platforms without SIGWINCH
+                Some(Ok(Event::Resize(new_cols, new_rows))) => {
+                    handle_resize(
+                        new_cols as usize,
+                        new_rows as usize,
+                        &mut self.state,
+                        self.out.clone(),
+                    )?;
+                }
+
+                Some(Ok(_)) => {} // Other events
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Hands the current state to the auto-save task, if there is one
+    fn publish_snapshot(&self) {
+        #[cfg(feature = "serde")]
+        if let Some((snapshot_tx, _)) = &self.auto_save {
+            let snapshot = self.state.snapshot();
+            snapshot_tx.send_if_modified(|saved| {
+                let changed = *saved != snapshot;
+                *saved = snapshot;
+                changed
+            });
+        }
+    }
+
+    /// Gives the terminal back, stops the process until the shell continues
+    /// it (Ctrl+Z) and then takes the terminal over again and redraws
+    fn suspend(&mut self) -> anyhow::Result<()> {
+        let Some(terminal_guard) = &mut self.terminal_guard else {
+            return Ok(());
+        };
+        terminal_guard.suspend()?;
+        signals::stop_process()?;
+        terminal_guard.resume()?;
+
+        // The shell printed over the frame and reset the scroll region
+        let mut out_guard = self.out.lock().unwrap();
+        ui::set_scroll_region_for(&mut *out_guard, &self.state)?;
+        redraw_box(&mut out_guard, &mut self.state)
+    }
+
+    /// Clears the frame, restores the terminal and leaves the cursor where
+    /// the input cursor was. Does nothing the second time.
+    fn cleanup(&mut self) -> anyhow::Result<()> {
+        // ── 4. clean-up ──────────────────────────────────────────────────
+        let Some(terminal_guard) = self.terminal_guard.take() else {
+            return Ok(());
+        };
+
+        // Stop periodic saving and save the final state. A failed save is
+        // reported once the terminal is restored, not before.
+        #[cfg(feature = "serde")]
+        let mut saved = Ok(());
+        #[cfg(feature = "serde")]
+        if let Some((_, task)) = self.auto_save.take() {
+            task.abort();
+            if let Some((path, _)) = &self.config.auto_save {
+                saved = self.state.save_to_file(path);
+            }
+        }
+        let state = &self.state;
+        let mut out_guard = self.out.lock().unwrap();
+        let clear_line = " ".repeat(state.cols);
+        // Clear all lines used by the frame
+        for i in 0..=state.required_lines {
+            queue!(
+                out_guard,
+                MoveTo(0, (state.frame_start_row().saturating_sub(1) + i) as u16),
+                Print(&clear_line)
+            )?;
+        }
+        out_guard.flush()?;
+        // Leave raw mode and give terminal its full screen back
+        drop(terminal_guard);
+
+        // Position cursor exactly where the input cursor was
+        // Do this AFTER clearing scroll region to prevent cursor position restoration
+        let (cursor_col, cursor_row) = state.cursor_position();
+        queue!(out_guard, MoveTo(cursor_col as u16, cursor_row as u16))?;
+        out_guard.flush()?;
+        #[cfg(feature = "serde")]
+        saved?;
+        Ok(())
+    }
+}
+
+impl Drop for TermboxApp {
+    fn drop(&mut self) {
+        // Nothing useful can be done with an error while dropping
+        let _ = self.cleanup();
+        self.state.wipe_password();
+    }
+}
+
+/// Restores the session saved at `Config::auto_save`, if the file exists
+#[cfg(feature = "serde")]
+fn restore_session(mut state: InputState, config: &Config) -> anyhow::Result<InputState> {
+    if let Some((path, _)) = &config.auto_save
+        && path.exists()
+    {
+        state.restore_snapshot(InputState::load_from_file(path)?);
+    }
+    Ok(state)
+}
+
+/// Handle of the task started by [`spawn_auto_save`]
+#[cfg(feature = "serde")]
+pub type AutoSaveTask = JoinHandle<Result<(), TermboxError>>;
+
+/// Saves the latest snapshot from `snapshots` to `path` every `period`.
+///
+/// Nothing is written while the snapshot is unchanged. The task ends once
+/// the sender is dropped. A failed save is retried at the next change
+/// rather than printed, which would draw over the input box.
+///
+/// # Arguments
+///
+/// * `path` - File passed to `InputStateSnapshot::save_to_file`
+/// * `period` - Time between saves
+/// * `snapshots` - Receives the current state of the input
+///
+/// # Returns
+///
+/// Returns the handle of the spawned task, which yields the error of the
+/// last save if it failed.
+#[cfg(feature = "serde")]
+pub fn spawn_auto_save(
+    path: PathBuf,
+    period: Duration,
+    mut snapshots: watch::Receiver<InputStateSnapshot>,
+) -> AutoSaveTask {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut saved = Ok(());
+        loop {
+            interval.tick().await;
+            match snapshots.has_changed() {
+                Ok(true) => {
+                    let snapshot = snapshots.borrow_and_update().clone();
+                    saved = snapshot.save_to_file(&path);
+                }
+                Ok(false) => {}
+                // The app was dropped and saved the final state itself
+                Err(_) => return saved,
+            }
+        }
+    })
+}
+
+/// Handle keyboard events and return the action to take
+async fn handle_key_event(
+    key: crossterm::event::KeyEvent,
+    state: &mut InputState,
+    out: Arc<Mutex<std::io::Stdout>>,
+    command_dispatcher: &commands::CommandDispatcher,
+) -> anyhow::Result<KeyAction> {
+    for error in state.clear_finished_animations() {
+        let mut out_guard = out.lock().unwrap();
+        ui::print_to_scroll_region(&mut *out_guard, &format!("error: {error}"), state)?;
+    }
+    let old_required_lines = state.required_lines;
+    let action = state.handle_key_async(key.code, key.modifiers).await;
+
+    match &action {
+        KeyAction::Submit(text) => {
+            handle_enter_key(
+                text,
+                old_required_lines,
+                state,
+                out.clone(),
+                command_dispatcher,
+            )
+            .await?;
+        }
+        KeyAction::ClearScreen => clear_screen(state, out.clone())?,
+        // The terminal is redrawn after resuming
+        KeyAction::Suspend => {}
+        KeyAction::ShowHelp => {
+            // The dispatcher prints the help above the frame itself
+            command_dispatcher
+                .handle_command("help", state, out.clone())
+                .await?;
+            update_frame_if_needed(state, out.clone(), old_required_lines)?;
+        }
+        // Redrawing is deferred until the paste ends
+        _ if state.paste_mode => {}
+        _ => update_frame_if_needed(state, out.clone(), old_required_lines)?,
+    }
+
+    Ok(action)
+}
+
+/// Handle submitted input: run it as a command or print it to the scroll region
+async fn handle_enter_key(
+    submitted_text: &str,
+    old_required_lines: usize,
+    state: &mut InputState,
+    out: Arc<Mutex<std::io::Stdout>>,
+    command_dispatcher: &commands::CommandDispatcher,
+) -> anyhow::Result<()> {
+    // Any submission stops a running spinner
+    if let Some(spinner) = state.spinner.take() {
+        spinner.cancel();
+    }
+
+    // Only the masked form of a password is ever echoed to the scroll region
+    let echoed_text = state.mask_text(submitted_text).into_owned();
+    let new_required_lines = calculate_required_lines("", state.cols);
+
+    // Clear the old frame area if it was larger
+    {
+        let mut out_guard = out.lock().unwrap();
+        clear_vacated_frame_rows(
+            &mut out_guard,
+            state,
+            old_required_lines,
+            new_required_lines,
+        )?;
+    }
+
+    if new_required_lines > old_required_lines {
+        state.notify_scroll(ScrollEvent::ScrolledDown(
+            new_required_lines - old_required_lines,
+        ));
+    }
+
+    // Update the scroll region for the new frame size
+    if new_required_lines != old_required_lines {
+        state.required_lines = new_required_lines;
+        let mut out_guard = out.lock().unwrap();
+        ui::set_scroll_region_for(&mut *out_guard, state)?;
+    }
+
+    // Check for commands first; a password is never run as a command
+    let result = if state.input_mode == InputMode::Password {
+        commands::CommandResult::NotRecognized
+    } else {
+        command_dispatcher
+            .handle_command(submitted_text, state, out.clone())
+            .await?
+    };
+    match result {
+        commands::CommandResult::Handled { output_height } => {
+            state.last_command_height = output_height;
+            // Redraw the now empty frame right away; animations keep running behind it
+            let mut out_guard = out.lock().unwrap();
+            redraw_box(&mut out_guard, state)?;
+            return Ok(());
+        }
+        // The dispatcher prints command output itself and reports it as handled
+        commands::CommandResult::PrintOutput { .. }
+        | commands::CommandResult::PrintRule(_)
+        | commands::CommandResult::Error(_) => {
+            return Ok(());
+        }
+        commands::CommandResult::NotRecognized => {
+            // Continue with normal text processing
+        }
+    }
+
+    // Commands with vertical output get an extra blank line after them
+    let output_text = if state.last_command_height > 1 {
+        format!("\n{}", echoed_text)
+    } else {
+        echoed_text
+    };
+    {
+        let mut out_guard = out.lock().unwrap();
+        ui::print_to_scroll_region(&mut *out_guard, &output_text, state)?;
+    }
+
+    // Reset the command height after handling the spacing
+    state.last_command_height = 0;
+
+    Ok(())
+}
+
+/// Update frame if needed based on text changes
+fn update_frame_if_needed(
+    state: &mut InputState,
+    out: Arc<Mutex<std::io::Stdout>>,
+    old_required_lines: usize,
+) -> anyhow::Result<()> {
+    // A taller frame pushes the bottom of the scroll region down over content
+    if state.required_lines > old_required_lines {
+        state.notify_scroll(ScrollEvent::ScrolledDown(
+            state.required_lines - old_required_lines,
+        ));
+    }
+
+    if state.too_narrow() {
+        return Ok(());
+    }
+    let mut out_guard = out.lock().unwrap();
+    if state.required_lines != old_required_lines {
+        clear_vacated_frame_rows(
+            &mut out_guard,
+            state,
+            old_required_lines,
+            state.required_lines,
+        )?;
+        ui::set_scroll_region_for(&mut *out_guard, state)?;
+        state.prev_grid = None;
+        ui::draw_frame(
+            &mut out_guard,
+            (state.cols, state.frame_end_row()),
+            state.required_lines,
+            &state.theme,
+            state.effective_layout(),
+            state.frame_style,
+        )?;
+    }
+    ui::draw_input(&mut out_guard, state)?;
+    Ok(())
+}
+
+/// Blank the rows a taller frame occupied that are not covered by the new frame
+fn clear_vacated_frame_rows(
+    out: &mut std::io::Stdout,
+    state: &InputState,
+    old_required_lines: usize,
+    new_required_lines: usize,
+) -> anyhow::Result<()> {
+    if old_required_lines <= new_required_lines {
+        return Ok(());
+    }
+    let clear_line = " ".repeat(state.cols);
+    let old_frame_start = state.frame_start_row_for(old_required_lines);
+    let new_frame_start = state.frame_start_row_for(new_required_lines);
+    let new_frame = new_frame_start..new_frame_start + new_required_lines;
+    for row in old_frame_start..old_frame_start + old_required_lines {
+        if !new_frame.contains(&row) {
+            queue!(out, MoveTo(0, row as u16), Print(&clear_line))?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Blank the scroll region, forget its content and redraw the frame (Ctrl+L)
+fn clear_screen(state: &mut InputState, out: Arc<Mutex<std::io::Stdout>>) -> anyhow::Result<()> {
+    let mut out_guard = out.lock().unwrap();
+    commands::clear::clear_scroll_region(&mut *out_guard, state)?;
+    state.content.clear();
+    ui::reset_scroll_region(&mut *out_guard)?; // clear any old region
+    ui::set_scroll_region_for(&mut *out_guard, state)?;
+    redraw_box(&mut out_guard, state)
+}
+
+/// Handle terminal resize event
+fn handle_resize(
+    new_cols: usize,
+    new_rows: usize,
+    state: &mut InputState,
+    out: Arc<Mutex<std::io::Stdout>>,
+) -> anyhow::Result<()> {
+    let action = state.handle_resize(new_cols, new_rows);
+    if action == ResizeAction::NoChange {
+        return Ok(());
+    }
+    let mut out_guard = out.lock().unwrap();
+    if action == ResizeAction::FullRedraw {
+        ui::reset_scroll_region(&mut *out_guard)?; // clear any old region
+        ui::set_scroll_region_for(&mut *out_guard, state)?;
+        ui::redraw_scroll_region(&mut *out_guard, state)?;
+    } else {
+        // Terminals keep the bottom rows in place when only the height
+        // changes, so the output above the frame is still where it was
+        ui::set_scroll_region_for(&mut *out_guard, state)?;
+    }
+    redraw_box(&mut out_guard, state)
+}
+
+/// Draw the frame and input from scratch. On a terminal too narrow for the
+/// frame nothing is drawn until a resize makes room.
+fn redraw_box(out: &mut Stdout, state: &mut InputState) -> anyhow::Result<()> {
+    if state.too_narrow() {
+        return Ok(());
+    }
+    state.prev_grid = None;
+    ui::draw_frame(
+        out,
+        (state.cols, state.frame_end_row()),
+        state.required_lines,
+        &state.theme,
+        state.effective_layout(),
+        state.frame_style,
+    )?;
+    Ok(ui::draw_input(out, state)?)
+}
+
+/// Copies input to output line by line, for when stdout is not a terminal.
+///
+/// No frame is drawn and raw mode is never enabled, so termbox can sit in a
+/// pipeline, e.g. `printf 'a\nb' | termbox > out.txt`.
+///
+/// # Arguments
+///
+/// * `input` - Source of lines, normally stdin
+/// * `out` - Destination for each line, normally stdout
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the input is exhausted or an error if reading or writing fails.
+pub async fn run_non_tty<R, W>(input: R, out: &mut W) -> anyhow::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: Write,
+{
+    let mut lines = input.lines();
+    while let Some(line) = lines.next_line().await? {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    Ok(())
+}
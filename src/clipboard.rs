@@ -0,0 +1,28 @@
+//! System clipboard access, available with the `clipboard` feature.
+
+/// Source and destination for clipboard text.
+///
+/// `InputState` talks to the clipboard through this trait so tests and
+/// embedders can substitute their own implementation.
+pub trait ClipboardProvider {
+    /// Read the current clipboard contents as text
+    fn get_text(&self) -> anyhow::Result<String>;
+
+    /// Replace the clipboard contents with `text`
+    fn set_text(&self, text: &str) -> anyhow::Result<()>;
+}
+
+/// The operating system clipboard, accessed through `arboard`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClipboard;
+
+impl ClipboardProvider for SystemClipboard {
+    fn get_text(&self) -> anyhow::Result<String> {
+        Ok(arboard::Clipboard::new()?.get_text()?)
+    }
+
+    fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        arboard::Clipboard::new()?.set_text(text)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,17 @@
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use crate::InputState;
+use async_trait::async_trait;
 
+use crate::animation::{AnimationRegistry, DEFAULT_ANIMATION_TIMEOUT};
+use crate::{InputState, ui};
+
+pub mod clear;
+pub mod echo;
+pub mod help;
+pub mod progress;
+pub mod spinner;
+pub mod table;
 pub mod tiktok;
 
 /// Represents the result of a command execution
@@ -9,58 +19,161 @@ pub mod tiktok;
 pub enum CommandResult {
     /// Command was handled successfully with optional output height
     Handled { output_height: usize },
+    /// Command produced text for the dispatcher to print to the scroll region.
+    /// `CommandDispatcher::handle_command` prints it and reports `Handled`.
+    PrintOutput { text: String },
+    /// Command asked for a full-width rule below its output. Printed like
+    /// `PrintOutput` and reported as `Handled`.
+    PrintRule(ui::RuleStyle),
+    /// Command failed, e.g. on invalid arguments. The dispatcher prints the
+    /// message and reports `Handled`.
+    Error(String),
     /// Command was not recognized
     NotRecognized,
 }
 
-/// All available commands
+/// A command that can be registered with [`CommandDispatcher::register`]
+#[async_trait]
+pub trait CommandHandler {
+    /// Keyword that invokes the command
+    fn name(&self) -> &str;
+
+    /// One-line summary of what the command does
+    fn description(&self) -> &str;
+
+    /// Run the command with the words that followed its name
+    async fn handle(
+        &self,
+        args: &[&str],
+        state: &mut InputState,
+        out: Arc<Mutex<std::io::Stdout>>,
+    ) -> anyhow::Result<CommandResult>;
+}
+
+/// Built-in commands that are not registered handlers
 #[derive(Debug)]
 pub enum Command {
-    TikTok,
+    Spinner,
+    Stop,
+    Help,
     // Future commands can be added here
 }
 
 impl Command {
+    /// Every built-in command, in the order they are listed
+    pub const ALL: [Command; 3] = [Command::Spinner, Command::Stop, Command::Help];
+
     /// Parse a command from input string
     pub fn from_input(input: &str) -> Option<Self> {
-        match input.trim() {
-            "tiktok" => Some(Command::TikTok),
+        let (name, _) = split_command(input);
+        match name {
+            "spinner" => Some(Command::Spinner),
+            "stop" => Some(Command::Stop),
+            "help" => Some(Command::Help),
             _ => None,
         }
     }
 
     /// Get the command names this enum variant responds to
-    pub fn command_names(&self) -> &[&str] {
+    pub fn command_names(&self) -> &'static [&'static str] {
         match self {
-            Command::TikTok => &["tiktok"],
+            Command::Spinner => &["spinner"],
+            Command::Stop => &["stop"],
+            Command::Help => &["help"],
+        }
+    }
+
+    /// One-line summary of what the command does
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::Spinner => "Show a spinner with an optional label until the next Enter",
+            Command::Stop => "Stop running animations",
+            Command::Help => "List commands and key bindings",
         }
     }
 
     /// Handle the command execution
     pub async fn handle(
         &self,
-        _input: &str,
+        args: &[&str],
+        dispatcher: &CommandDispatcher,
         state: &mut InputState,
         out: Arc<Mutex<std::io::Stdout>>,
     ) -> anyhow::Result<CommandResult> {
         match self {
-            Command::TikTok => {
-                tiktok::handle_tiktok_command(state, out).await?;
+            Command::Spinner => {
+                let label = args.join(" ");
+                let label = (!label.is_empty()).then_some(label.as_str());
+                // Enter already cancelled any running spinner
+                let handle = spinner::handle_spinner_command(state, out, label).await?;
+                state.spinner = Some(handle);
                 Ok(CommandResult::Handled {
-                    output_height: tiktok::TIKTOK_ANIMATION_HEIGHT,
+                    output_height: crate::animation::SPINNER_ANIMATION_HEIGHT,
                 })
             }
+            Command::Stop => {
+                if let Some(spinner) = state.spinner.take() {
+                    spinner.cancel();
+                }
+                if let Some(animation) = state.active_animation.take() {
+                    animation.cancel();
+                }
+                Ok(CommandResult::Handled { output_height: 0 })
+            }
+            Command::Help => Ok(CommandResult::PrintOutput {
+                text: help::help_lines(dispatcher, state.cols).join("\n"),
+            }),
         }
     }
 }
 
 /// Central command dispatcher that manages all available commands
-pub struct CommandDispatcher;
+pub struct CommandDispatcher {
+    handlers: Vec<Arc<dyn CommandHandler + Send + Sync>>,
+    animations: AnimationRegistry,
+}
 
 impl CommandDispatcher {
-    /// Create a new command dispatcher
+    /// Create a new command dispatcher with the built-in handlers registered
     pub fn new() -> Self {
-        Self
+        Self::with_animation_timeout(DEFAULT_ANIMATION_TIMEOUT)
+    }
+
+    /// Like [`new`](Self::new), with the built-in animations ending after
+    /// `timeout`
+    pub fn with_animation_timeout(timeout: Duration) -> Self {
+        let mut dispatcher = Self {
+            handlers: Vec::new(),
+            animations: AnimationRegistry::global().clone(),
+        };
+        let tiktok_config = tiktok::TikTokConfig {
+            timeout,
+            ..Default::default()
+        };
+        dispatcher.register(Arc::new(tiktok::TikTokCommand {
+            config: tiktok_config,
+        }));
+        dispatcher.register(Arc::new(echo::EchoCommand));
+        dispatcher.register(Arc::new(clear::ClearCommand));
+        dispatcher.register(Arc::new(progress::ProgressCommand { timeout }));
+        dispatcher.register(Arc::new(table::TableCommand));
+        dispatcher
+    }
+
+    /// Add a command handler. When several handlers share a name the one
+    /// registered first wins.
+    pub fn register(&mut self, handler: Arc<dyn CommandHandler + Send + Sync>) {
+        self.handlers.push(handler);
+    }
+
+    /// The animations started by commands, shared with the global registry
+    pub fn animations(&self) -> &AnimationRegistry {
+        &self.animations
+    }
+
+    /// Stop every running animation, e.g. before the terminal is restored
+    pub fn cancel_all_animations(&self) {
+        self.animations.cancel_all();
     }
 
     /// Process a command by checking all available commands
@@ -70,16 +183,124 @@ impl CommandDispatcher {
         state: &mut InputState,
         out: Arc<Mutex<std::io::Stdout>>,
     ) -> anyhow::Result<CommandResult> {
-        if let Some(command) = Command::from_input(input) {
-            command.handle(input, state, out).await
-        } else {
-            Ok(CommandResult::NotRecognized)
+        let (keyword, _) = split_command(input);
+        let handler = self
+            .handlers
+            .iter()
+            .find(|handler| handler.name() == keyword);
+        let command = Command::from_input(input);
+        if handler.is_none() && command.is_none() {
+            return Ok(CommandResult::NotRecognized);
         }
+
+        // Input with unbalanced quotes is left to be echoed as plain text
+        let Ok((_, args)) = parse_command_args(input) else {
+            return Ok(CommandResult::NotRecognized);
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let result = match (handler, command) {
+            (Some(handler), _) => handler.handle(&args, state, out.clone()).await?,
+            (None, Some(command)) => command.handle(&args, self, state, out.clone()).await?,
+            (None, None) => CommandResult::NotRecognized,
+        };
+
+        let text = match result {
+            CommandResult::PrintOutput { text } => text,
+            CommandResult::PrintRule(style) => style.line(state.cols),
+            CommandResult::Error(message) => format!("error: {message}"),
+            result => return Ok(result),
+        };
+        let mut out_guard = out.lock().unwrap();
+        ui::print_to_scroll_region(&mut *out_guard, &text, state)?;
+        // Printed output needs no extra room below it, unlike animations
+        Ok(CommandResult::Handled { output_height: 0 })
     }
 
     /// Get a list of all available commands
     pub fn list_commands(&self) -> Vec<&str> {
-        vec!["tiktok"] // Can be generated from Command enum in the future
+        let mut commands: Vec<&str> = self.handlers.iter().map(|handler| handler.name()).collect();
+        commands.extend(
+            Command::ALL
+                .iter()
+                .flat_map(|command| command.command_names()),
+        );
+        commands
+    }
+
+    /// Get the description of a command by name
+    pub fn description(&self, name: &str) -> Option<&str> {
+        if let Some(handler) = self.handlers.iter().find(|handler| handler.name() == name) {
+            return Some(handler.description());
+        }
+        Command::ALL
+            .iter()
+            .find(|command| command.command_names().contains(&name))
+            .map(|command| command.description())
+    }
+}
+
+/// Split command input into the command name and its arguments.
+///
+/// Arguments are separated by whitespace. Text in single or double quotes
+/// is kept together as one argument with the quotes removed, and a
+/// backslash inside quotes escapes the next character.
+///
+/// # Errors
+///
+/// Returns an error if a quote is not closed.
+pub fn parse_command_args(input: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    // Distinguishes an empty quoted argument from no argument at all
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) if c == '\\' => {
+                if let Some(escaped) = chars.next() {
+                    token.push(escaped);
+                }
+            }
+            Some(_) => token.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            None => {
+                token.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        anyhow::bail!("unterminated {} quote", q);
+    }
+    if in_token {
+        tokens.push(token);
+    }
+
+    let mut tokens = tokens.into_iter();
+    let name = tokens.next().unwrap_or_default();
+    Ok((name, tokens.collect()))
+}
+
+/// Split input into the command keyword and the trimmed remainder
+fn split_command(input: &str) -> (&str, &str) {
+    let input = input.trim();
+    match input.split_once(char::is_whitespace) {
+        Some((name, rest)) => (name, rest.trim()),
+        None => (input, ""),
     }
 }
 
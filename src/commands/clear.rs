@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use crate::InputState;
+use crate::commands::{CommandHandler, CommandResult};
+
+/// The `clear` command: erases the output around the input box
+pub struct ClearCommand;
+
+#[async_trait]
+impl CommandHandler for ClearCommand {
+    fn name(&self) -> &str {
+        "clear"
+    }
+
+    fn description(&self) -> &str {
+        "Clear the screen above the input box"
+    }
+
+    async fn handle(
+        &self,
+        _args: &[&str],
+        state: &mut InputState,
+        out: Arc<Mutex<std::io::Stdout>>,
+    ) -> anyhow::Result<CommandResult> {
+        let mut out_guard = out.lock().unwrap();
+        clear_scroll_region(&mut *out_guard, state)?;
+        state.content.clear();
+        Ok(CommandResult::Handled { output_height: 0 })
+    }
+}
+
+/// Overwrites every row of the scroll region, `state.scroll_region()`,
+/// with spaces
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `state` - The input state, used for the terminal size and frame height
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if output operations fail.
+pub fn clear_scroll_region<W: Write>(out: &mut W, state: &InputState) -> anyhow::Result<()> {
+    Ok(state.content_area().clear(out)?)
+}
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+use crate::InputState;
+use crate::commands::{CommandHandler, CommandResult};
+
+/// The `echo` command: prints its arguments joined by spaces
+pub struct EchoCommand;
+
+#[async_trait]
+impl CommandHandler for EchoCommand {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn description(&self) -> &str {
+        "Print the given text"
+    }
+
+    async fn handle(
+        &self,
+        args: &[&str],
+        _state: &mut InputState,
+        _out: Arc<Mutex<std::io::Stdout>>,
+    ) -> anyhow::Result<CommandResult> {
+        Ok(CommandResult::PrintOutput {
+            text: args.join(" "),
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use crate::commands::CommandDispatcher;
+
+/// Key bindings listed below the commands in the help output
+pub const KEYBINDING_HINTS: [(&str, &str); 12] = [
+    ("Enter", "Submit input"),
+    ("Alt+Enter", "Insert a newline"),
+    ("Tab", "Complete the word before the cursor, or indent"),
+    ("Shift+Tab", "Dedent the current line"),
+    ("Ctrl+R", "Search input history"),
+    ("Ctrl+L", "Clear the screen above the input box"),
+    ("Shift+Arrows", "Select text"),
+    ("Insert", "Toggle overwrite mode"),
+    ("Ctrl+_", "Undo the last edit"),
+    ("F1", "Show this help"),
+    ("F5", "Clear the input"),
+    ("Esc", "Exit"),
+];
+
+/// Build the help output: every command of `dispatcher` with its
+/// description, then the key bindings, word-wrapped to `cols` columns
+pub fn help_lines(dispatcher: &CommandDispatcher, cols: usize) -> Vec<String> {
+    let commands: Vec<(&str, &str)> = dispatcher
+        .list_commands()
+        .into_iter()
+        .map(|name| (name, dispatcher.description(name).unwrap_or_default()))
+        .collect();
+
+    let mut lines = vec!["Commands:".to_string()];
+    lines.extend(two_column_lines(&commands, cols));
+    lines.push(String::new());
+    lines.push("Keys:".to_string());
+    lines.extend(two_column_lines(&KEYBINDING_HINTS, cols));
+    lines
+}
+
+/// Lay out `entries` as `  name  description` rows. Descriptions that do not
+/// fit are wrapped and continue under the description column.
+fn two_column_lines(entries: &[(&str, &str)], cols: usize) -> Vec<String> {
+    let name_width = entries
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let indent = name_width + 4;
+    let description_width = cols.saturating_sub(indent).max(1);
+
+    let mut lines = Vec::new();
+    for (name, description) in entries {
+        for (i, part) in wrap_words(description, description_width)
+            .into_iter()
+            .enumerate()
+        {
+            let label = if i == 0 { *name } else { "" };
+            let line = format!("  {:<name_width$}  {}", label, part);
+            lines.push(line.trim_end().to_string());
+        }
+    }
+    lines
+}
+
+/// Greedy word wrap of `text` to `width` characters per line. Words longer
+/// than `width` are split.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+            line_len = 0;
+        }
+        while word.len() > width {
+            let rest = word.split_off(width);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+        line_len += word.len();
+        line.extend(word);
+    }
+
+    if line_len > 0 || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
+
+use crate::animation::{DEFAULT_ANIMATION_TIMEOUT, spawn_box_animation};
+use crate::commands::{CommandHandler, CommandResult};
+use crate::{InputState, display_width};
+
+/// Delay between progress steps
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Cells in the progress bar, whatever the total
+pub const PROGRESS_BAR_WIDTH: usize = 10;
+
+/// The `progress <label> <total>` command: counts from 1 to `total` in a
+/// progress bar
+#[derive(Debug)]
+pub struct ProgressCommand {
+    /// How long the bar may run before it jumps to `total`
+    pub timeout: Duration,
+}
+
+impl Default for ProgressCommand {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_ANIMATION_TIMEOUT,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ProgressCommand {
+    fn name(&self) -> &str {
+        "progress"
+    }
+
+    fn description(&self) -> &str {
+        "Show a labelled progress bar counting to a total, e.g. progress \"Downloading\" 50"
+    }
+
+    async fn handle(
+        &self,
+        args: &[&str],
+        state: &mut InputState,
+        out: Arc<Mutex<std::io::Stdout>>,
+    ) -> anyhow::Result<CommandResult> {
+        let (label, total) = match parse_progress_args(args) {
+            Ok(parsed) => parsed,
+            Err(message) => return Ok(CommandResult::Error(message)),
+        };
+        let frames = progress_frames(label, total);
+        // "│ " and " │" around the longest frame, which is the last
+        let width = frames
+            .last()
+            .map_or(0, |frame| display_width(frame) + 4)
+            .min(state.cols);
+
+        // Only one animation runs at a time
+        if let Some(previous) = state.active_animation.take() {
+            previous.cancel();
+        }
+        let timing = (PROGRESS_INTERVAL, self.timeout);
+        let handle = spawn_box_animation(state, out, width, frames, timing)?;
+        state.active_animation = Some(handle);
+        Ok(CommandResult::Handled {
+            output_height: crate::animation::BOX_ANIMATION_HEIGHT,
+        })
+    }
+}
+
+/// Split `progress` arguments into the label and a total of at least 1.
+///
+/// # Errors
+///
+/// Returns a message for the user if an argument is missing or the total
+/// is not a positive number.
+pub fn parse_progress_args<'a>(args: &[&'a str]) -> Result<(&'a str, u32), String> {
+    let [label, total] = args else {
+        return Err("usage: progress <label> <total>".to_string());
+    };
+    match total.parse::<u32>() {
+        Ok(0) => Err("progress total must be at least 1".to_string()),
+        Ok(total) => Ok((label, total)),
+        Err(e) => Err(format!("invalid progress total {:?}: {}", total, e)),
+    }
+}
+
+/// Frames from `label [█░░░░░░░░░] 1/total` to `total/total`
+pub fn progress_frames(label: &str, total: u32) -> Vec<String> {
+    (1..=total)
+        .map(|step| {
+            let filled = step as usize * PROGRESS_BAR_WIDTH / total as usize;
+            format!(
+                "{} [{}{}] {}/{}",
+                label,
+                "█".repeat(filled),
+                "░".repeat(PROGRESS_BAR_WIDTH - filled),
+                step,
+                total
+            )
+        })
+        .collect()
+}
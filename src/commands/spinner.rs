@@ -0,0 +1,103 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::Duration;
+
+use crate::animation::{
+    AnimationHandle, AnimationRegistry, SPINNER_ANIMATION_HEIGHT, run_line_animation,
+};
+use crate::{InputState, ScrollEvent, ui};
+
+/// Braille frames cycled by the spinner
+pub const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Delay between spinner frames
+pub const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+
+/// One full rotation of the spinner, each frame followed by `label` if given
+pub fn spinner_frames(label: Option<&str>) -> Vec<String> {
+    SPINNER_FRAMES
+        .iter()
+        .map(|frame| match label {
+            Some(label) => format!("{} {}", frame, label),
+            None => frame.to_string(),
+        })
+        .collect()
+}
+
+/// Handle the spinner command
+///
+/// The spinner runs until the returned handle is cancelled.
+pub async fn handle_spinner_command(
+    state: &mut InputState,
+    out: Arc<Mutex<std::io::Stdout>>,
+    label: Option<&str>,
+) -> anyhow::Result<AnimationHandle> {
+    // Subscribe to scroll events using broadcast channel
+    let scroll_rx = state.setup_scroll_broadcast();
+
+    // The spinner takes the bottom line of the scroll region once it is pushed up
+    let scroll_region_bottom = state.scroll_region_rows();
+    {
+        let mut out_guard = out.lock().unwrap();
+        ui::print_to_scroll_region(&mut *out_guard, "", state)?;
+    }
+
+    let (handle, cancel_rx) = AnimationHandle::new();
+    let report = handle.error_reporter();
+    let out_clone = out.clone();
+    let cols = state.cols;
+    let rows = state.rows;
+    let label = label.map(str::to_string);
+    let spinner_line = scroll_region_bottom.saturating_sub(SPINNER_ANIMATION_HEIGHT - 1);
+
+    AnimationRegistry::global().spawn(async move {
+        let result = run_spinner(
+            out_clone,
+            cols,
+            rows,
+            scroll_rx,
+            spinner_line,
+            label,
+            cancel_rx,
+        )
+        .await;
+        report(result);
+    });
+
+    Ok(handle)
+}
+
+/// Runs the spinner on `line` until `cancel_rx` receives a value.
+///
+/// # Arguments
+///
+/// * `out` - Shared output handle, normally stdout
+/// * `cols` - Terminal width in columns
+/// * `rows` - Terminal height in rows
+/// * `scroll_rx` - Receiver for scroll events
+/// * `line` - The row where the spinner should be drawn
+/// * `label` - Optional text shown after the spinner
+/// * `cancel_rx` - Stops the spinner when a value is sent
+///
+/// # Returns
+///
+/// Returns `Ok(())` once cancelled or an error if the operation fails.
+pub async fn run_spinner<W: Write>(
+    out: Arc<Mutex<W>>,
+    cols: usize,
+    rows: usize,
+    scroll_rx: broadcast::Receiver<ScrollEvent>,
+    line: usize,
+    label: Option<String>,
+    mut cancel_rx: oneshot::Receiver<()>,
+) -> anyhow::Result<()> {
+    let frames = spinner_frames(label.as_deref()).into_iter().cycle();
+    // Frames already come SPINNER_INTERVAL apart, so none need dropping
+    let timing = (SPINNER_INTERVAL, Duration::ZERO);
+    let animation = run_line_animation(out, cols, rows, line, frames, timing, scroll_rx);
+    tokio::select! {
+        result = animation => result,
+        Ok(()) = &mut cancel_rx => Ok(()),
+    }
+}
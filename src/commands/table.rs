@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use unicode_width::UnicodeWidthChar;
+
+use crate::commands::{CommandHandler, CommandResult};
+use crate::{InputState, display_width};
+
+/// The `table` command: prints comma-separated rows as an aligned table,
+/// the first row being the headers
+pub struct TableCommand;
+
+#[async_trait]
+impl CommandHandler for TableCommand {
+    fn name(&self) -> &str {
+        "table"
+    }
+
+    fn description(&self) -> &str {
+        "Print a table, e.g. table name,size a.txt,12 b.txt,3"
+    }
+
+    async fn handle(
+        &self,
+        args: &[&str],
+        state: &mut InputState,
+        _out: Arc<Mutex<std::io::Stdout>>,
+    ) -> anyhow::Result<CommandResult> {
+        let Some((headers, rows)) = args.split_first() else {
+            return Ok(CommandResult::Error(
+                "usage: table <header,...> [<value,...> ...]".to_string(),
+            ));
+        };
+        let headers: Vec<&str> = headers.split(',').collect();
+        let rows: Vec<Vec<&str>> = rows.iter().map(|row| row.split(',').collect()).collect();
+        Ok(CommandResult::PrintOutput {
+            text: format_table(&headers, &rows, state.cols).join("\n"),
+        })
+    }
+}
+
+/// Lays out `rows` under `headers` in columns separated by `│`, with a
+/// `─` and `┼` rule below the headers.
+///
+/// Each column is as wide as its widest cell, but takes at most
+/// `cols / headers.len()` columns including padding and separator; longer
+/// cells are cut short with `…`. Missing cells are left blank and cells
+/// past the last header are dropped.
+pub fn format_table(headers: &[&str], rows: &[Vec<&str>], cols: usize) -> Vec<String> {
+    if headers.is_empty() {
+        return Vec::new();
+    }
+    // " cell " plus one separator
+    let max_width = (cols / headers.len()).saturating_sub(3).max(1);
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| display_width(cell(row, column)))
+                .chain([display_width(headers[column])])
+                .max()
+                .unwrap_or(0)
+                .min(max_width)
+        })
+        .collect();
+
+    let format_row = |row: &[&str]| {
+        let line = widths
+            .iter()
+            .enumerate()
+            .map(|(column, &width)| format!(" {} ", pad(cell(row, column), width)))
+            .collect::<Vec<_>>()
+            .join("│");
+        line.trim_end().to_string()
+    };
+
+    let mut lines = vec![format_row(headers)];
+    lines.push(
+        widths
+            .iter()
+            .map(|width| "─".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("┼"),
+    );
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines
+}
+
+/// The cell in `column` of `row`, blank if the row is too short
+fn cell<'a>(row: &[&'a str], column: usize) -> &'a str {
+    row.get(column).copied().unwrap_or("")
+}
+
+/// `text` padded with spaces to `width` columns, or cut to fit with `…`
+fn pad(text: &str, width: usize) -> String {
+    let text_width = display_width(text);
+    if text_width <= width {
+        return format!("{}{}", text, " ".repeat(width - text_width));
+    }
+    let mut cut = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width > width.saturating_sub(1) {
+            break;
+        }
+        cut.push(c);
+        used += c_width;
+    }
+    cut.push('…');
+    format!("{}{}", cut, " ".repeat(width.saturating_sub(used + 1)))
+}
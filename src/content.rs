@@ -0,0 +1,207 @@
+use crossterm::{cursor::MoveTo, queue, style::Print};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::ops::Range;
+use tokio::sync::broadcast;
+
+use crate::ScrollEvent;
+use crate::error::TermboxError;
+
+/// Lines kept by [`ContentBuffer::default`]
+pub const DEFAULT_MAX_CONTENT_LINES: usize = 1000;
+
+/// One line printed to the scroll region
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentLine {
+    /// The line as printed, after the output formatter
+    pub text: String,
+    /// When the line was printed
+    #[cfg(feature = "timestamps")]
+    pub submitted_at: chrono::DateTime<chrono::Utc>,
+    /// Position among every line printed to the buffer, starting at 1. Keeps
+    /// counting when old lines are dropped or the buffer is cleared.
+    pub line_number: usize,
+}
+
+/// The lines printed to the scroll region, most recent last.
+///
+/// Only the newest `max_lines` lines are kept. They are used to repaint the
+/// scroll region after a resize, see `ui::redraw_scroll_region`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentBuffer {
+    lines: VecDeque<ContentLine>,
+    max_lines: usize,
+    next_line_number: usize,
+}
+
+impl ContentBuffer {
+    /// An empty buffer keeping at most `max_lines` lines
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            max_lines,
+            next_line_number: 1,
+        }
+    }
+
+    /// Append one line, dropping the oldest once `max_lines` is exceeded
+    pub fn push_line(&mut self, line: impl Into<String>) {
+        self.lines.push_back(ContentLine {
+            text: line.into(),
+            #[cfg(feature = "timestamps")]
+            submitted_at: chrono::Utc::now(),
+            line_number: self.next_line_number,
+        });
+        self.next_line_number += 1;
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Append every `\n` separated line of `text`, as printed by
+    /// `ui::print_to_scroll_region`
+    pub fn push_text(&mut self, text: &str) {
+        for line in text.split('\n') {
+            self.push_line(line);
+        }
+    }
+
+    /// The most recent `n` lines, oldest first
+    pub fn last_n(&self, n: usize) -> Vec<&str> {
+        let skip = self.lines.len().saturating_sub(n);
+        self.lines
+            .iter()
+            .skip(skip)
+            .map(|line| line.text.as_str())
+            .collect()
+    }
+
+    /// The text of all stored lines, oldest first
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|line| line.text.as_str())
+    }
+
+    /// All stored lines in the order they were printed
+    pub fn iter(&self) -> impl Iterator<Item = &ContentLine> {
+        self.lines.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn max_lines(&self) -> usize {
+        self.max_lines
+    }
+
+    /// Forget every line, e.g. after the screen was cleared
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+impl Default for ContentBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONTENT_LINES)
+    }
+}
+
+/// The rows output scrolls in, apart from the rows kept for the input box.
+///
+/// Owns the terminal's scroll region: setting it, printing into it and
+/// clearing it. `InputState::content_area` gives the area around the current
+/// frame.
+#[derive(Debug, Clone)]
+pub struct ContentArea {
+    /// Terminal height in rows
+    pub rows: usize,
+    /// Width output is printed at
+    pub cols: usize,
+    /// Rows at the bottom of the terminal outside the area
+    pub bottom_reserved: usize,
+    /// Rows at the top of the terminal outside the area, e.g. for a frame
+    /// drawn at the top
+    pub top_reserved: usize,
+    /// Told how far the area scrolled after each print, see
+    /// `InputState::setup_scroll_broadcast`
+    pub scroll_broadcast: Option<broadcast::Sender<ScrollEvent>>,
+}
+
+impl ContentArea {
+    /// The rows of a `cols` x `rows` terminal above the bottom
+    /// `bottom_reserved` rows
+    pub fn new(cols: usize, rows: usize, bottom_reserved: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            bottom_reserved,
+            top_reserved: 0,
+            scroll_broadcast: None,
+        }
+    }
+
+    /// The terminal rows of the area; empty when the reserved rows fill the
+    /// terminal
+    pub fn region(&self) -> Range<usize> {
+        let end = self.rows.saturating_sub(self.bottom_reserved);
+        self.top_reserved.min(end)..end
+    }
+
+    /// Set the terminal's scroll region to the area
+    pub fn apply<W: Write>(&self, out: &mut W) -> Result<(), TermboxError> {
+        let region = self.region();
+        // DECSTBM is 1-based & inclusive, and a region needs at least one row
+        let top = region.start + 1;
+        let bottom = region.end.max(top);
+        queue!(out, Print(format!("\x1B[{};{}r", top, bottom)))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Print `text` at the bottom of the area so the rows above scroll up,
+    /// and send how far as a `ScrollEvent::ScrolledUp`.
+    ///
+    /// Newlines become `\r\n` so every line starts at column 0. Lines are
+    /// printed as they are; break them at `cols` first if the terminal
+    /// would wrap them somewhere else.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of rows the area scrolled up by, or an error if
+    /// output operations fail.
+    pub fn print<W: Write>(&self, out: &mut W, text: &str) -> Result<usize, TermboxError> {
+        let bottom = self.region().end.saturating_sub(1);
+        let line_count = crate::ui::write_str_to_scroll_region(out, text, bottom, self.cols)?;
+        if let Some(broadcast_tx) = &self.scroll_broadcast {
+            let _ = broadcast_tx.send(ScrollEvent::ScrolledUp(line_count));
+        }
+        Ok(line_count)
+    }
+
+    /// Overwrite every row of the area with spaces
+    pub fn clear<W: Write>(&self, out: &mut W) -> Result<(), TermboxError> {
+        let clear_line = " ".repeat(self.cols);
+        for row in self.region() {
+            queue!(out, MoveTo(0, row as u16), Print(&clear_line))?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Adopt a new terminal size, keeping the reserved rows, and set the
+    /// scroll region again
+    pub fn resize<W: Write>(
+        &mut self,
+        out: &mut W,
+        new_rows: usize,
+        new_cols: usize,
+    ) -> Result<(), TermboxError> {
+        self.rows = new_rows;
+        self.cols = new_cols;
+        self.apply(out)
+    }
+}
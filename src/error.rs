@@ -0,0 +1,16 @@
+use std::io;
+
+/// Errors returned by termbox's drawing and editing functions
+#[derive(Debug, thiserror::Error)]
+pub enum TermboxError {
+    /// The terminal is too narrow to draw the frame and prompt
+    #[error("terminal too small")]
+    TerminalTooSmall { min_cols: usize, actual_cols: usize },
+    /// Writing to the terminal or a file failed
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// A byte offset or range of the buffer is out of bounds or splits a
+    /// character
+    #[error("invalid UTF-8 boundary")]
+    InvalidBoundary,
+}
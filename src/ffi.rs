@@ -0,0 +1,194 @@
+//! C interface for embedding the input box state in other languages.
+//!
+//! Generate the header with `cbindgen --config cbindgen.toml --output termbox.h`.
+//! Drawing is left to the embedder; these functions only edit the state.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::{InputState, KeyAction, zeroize};
+
+/// Returned by `termbox_handle_key` for `KeyAction::Continue`
+pub const TERMBOX_ACTION_CONTINUE: u32 = 0;
+/// Returned by `termbox_handle_key` for `KeyAction::Exit`
+pub const TERMBOX_ACTION_EXIT: u32 = 1;
+/// Returned by `termbox_handle_key` for `KeyAction::Submit`; read the text
+/// with `termbox_get_submitted`
+pub const TERMBOX_ACTION_SUBMIT: u32 = 2;
+/// Returned by `termbox_handle_key` for `KeyAction::ClearScreen`
+pub const TERMBOX_ACTION_CLEAR_SCREEN: u32 = 3;
+/// Returned by `termbox_handle_key` for `KeyAction::ShowHelp`
+pub const TERMBOX_ACTION_SHOW_HELP: u32 = 4;
+/// Returned by `termbox_handle_key` for `KeyAction::Suspend`
+pub const TERMBOX_ACTION_SUSPEND: u32 = 5;
+
+// Keys without a character, numbered past the last Unicode scalar value so
+// any other key code is the character itself
+pub const TERMBOX_KEY_ENTER: u32 = 0x11_0000;
+pub const TERMBOX_KEY_BACKSPACE: u32 = 0x11_0001;
+pub const TERMBOX_KEY_DELETE: u32 = 0x11_0002;
+pub const TERMBOX_KEY_LEFT: u32 = 0x11_0003;
+pub const TERMBOX_KEY_RIGHT: u32 = 0x11_0004;
+pub const TERMBOX_KEY_UP: u32 = 0x11_0005;
+pub const TERMBOX_KEY_DOWN: u32 = 0x11_0006;
+pub const TERMBOX_KEY_HOME: u32 = 0x11_0007;
+pub const TERMBOX_KEY_END: u32 = 0x11_0008;
+pub const TERMBOX_KEY_TAB: u32 = 0x11_0009;
+pub const TERMBOX_KEY_BACKTAB: u32 = 0x11_000A;
+pub const TERMBOX_KEY_ESC: u32 = 0x11_000B;
+pub const TERMBOX_KEY_INSERT: u32 = 0x11_000C;
+
+/// Modifier bits for `termbox_handle_key`, the same as crossterm's
+pub const TERMBOX_MOD_SHIFT: u32 = 0b0001;
+pub const TERMBOX_MOD_CONTROL: u32 = 0b0010;
+pub const TERMBOX_MOD_ALT: u32 = 0b0100;
+
+/// An input state handed out to C, with the text of the last submission
+/// kept for `termbox_get_submitted`
+pub struct TermboxState {
+    state: InputState,
+    /// Wiped when replaced by the next submission and when freed
+    last_submitted: String,
+}
+
+impl Drop for TermboxState {
+    fn drop(&mut self) {
+        zeroize(&mut self.last_submitted);
+        self.state.wipe_password();
+    }
+}
+
+/// Create an input state for a `cols` x `rows` terminal. Release it with
+/// `termbox_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn termbox_new(cols: usize, rows: usize) -> *mut TermboxState {
+    Box::into_raw(Box::new(TermboxState {
+        state: InputState::new(cols, rows),
+        last_submitted: String::new(),
+    }))
+}
+
+/// Handle one key press and return one of the `TERMBOX_ACTION_*` values.
+///
+/// `key_code` is a Unicode scalar value or one of the `TERMBOX_KEY_*`
+/// values; anything else is ignored. `modifiers` combines `TERMBOX_MOD_*`
+/// bits. A null `state` returns `TERMBOX_ACTION_CONTINUE`.
+///
+/// # Safety
+///
+/// `state` must be null or a pointer returned by `termbox_new` that has not
+/// been freed, and not be used from another thread during the call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn termbox_handle_key(
+    state: *mut TermboxState,
+    key_code: u32,
+    modifiers: u32,
+) -> u32 {
+    // SAFETY: the caller guarantees `state` is null or valid and unaliased
+    let Some(termbox) = (unsafe { state.as_mut() }) else {
+        return TERMBOX_ACTION_CONTINUE;
+    };
+    let modifiers = KeyModifiers::from_bits_truncate(modifiers as u8);
+    match termbox
+        .state
+        .handle_key(key_code_from_u32(key_code), modifiers)
+    {
+        KeyAction::Continue => TERMBOX_ACTION_CONTINUE,
+        KeyAction::Exit => TERMBOX_ACTION_EXIT,
+        KeyAction::Submit(text) => {
+            zeroize(&mut termbox.last_submitted);
+            termbox.last_submitted = text;
+            TERMBOX_ACTION_SUBMIT
+        }
+        KeyAction::ClearScreen => TERMBOX_ACTION_CLEAR_SCREEN,
+        KeyAction::ShowHelp => TERMBOX_ACTION_SHOW_HELP,
+        KeyAction::Suspend => TERMBOX_ACTION_SUSPEND,
+    }
+}
+
+/// Copy the UTF-8 bytes of the buffer, without a terminating NUL, into
+/// `out`. At most `len` bytes are copied; the full length of the buffer is
+/// returned, so a call with `len` 0 tells how large `out` must be.
+///
+/// # Safety
+///
+/// `state` must be null or a valid pointer from `termbox_new`, and `out`
+/// must be valid for writes of `len` bytes unless `len` is 0.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn termbox_get_buffer(
+    state: *const TermboxState,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    // SAFETY: the caller guarantees `state` is null or valid
+    let Some(termbox) = (unsafe { state.as_ref() }) else {
+        return 0;
+    };
+    // SAFETY: the caller guarantees `out` is valid for `len` bytes
+    unsafe { copy_out(termbox.state.buffer(), out, len) }
+}
+
+/// Copy the text of the last `TERMBOX_ACTION_SUBMIT` into `out`, like
+/// `termbox_get_buffer`. Returns 0 before the first submission.
+///
+/// # Safety
+///
+/// The same as for `termbox_get_buffer`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn termbox_get_submitted(
+    state: *const TermboxState,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    // SAFETY: the caller guarantees `state` is null or valid
+    let Some(termbox) = (unsafe { state.as_ref() }) else {
+        return 0;
+    };
+    // SAFETY: the caller guarantees `out` is valid for `len` bytes
+    unsafe { copy_out(&termbox.last_submitted, out, len) }
+}
+
+/// Release a state created by `termbox_new`, wiping the last submission
+/// and a password being typed. Null is ignored.
+///
+/// # Safety
+///
+/// `state` must be null or a pointer from `termbox_new` that is not used
+/// again afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn termbox_free(state: *mut TermboxState) {
+    if !state.is_null() {
+        // SAFETY: the caller passes ownership of a pointer from `Box::into_raw`
+        drop(unsafe { Box::from_raw(state) });
+    }
+}
+
+/// # Safety
+///
+/// `out` must be valid for writes of `len` bytes unless `len` is 0.
+unsafe fn copy_out(text: &str, out: *mut u8, len: usize) -> usize {
+    let count = text.len().min(len);
+    if count > 0 && !out.is_null() {
+        // SAFETY: `count <= len` and the caller guarantees `out` holds `len` bytes
+        unsafe { std::ptr::copy_nonoverlapping(text.as_ptr(), out, count) };
+    }
+    text.len()
+}
+
+fn key_code_from_u32(key_code: u32) -> KeyCode {
+    match key_code {
+        TERMBOX_KEY_ENTER => KeyCode::Enter,
+        TERMBOX_KEY_BACKSPACE => KeyCode::Backspace,
+        TERMBOX_KEY_DELETE => KeyCode::Delete,
+        TERMBOX_KEY_LEFT => KeyCode::Left,
+        TERMBOX_KEY_RIGHT => KeyCode::Right,
+        TERMBOX_KEY_UP => KeyCode::Up,
+        TERMBOX_KEY_DOWN => KeyCode::Down,
+        TERMBOX_KEY_HOME => KeyCode::Home,
+        TERMBOX_KEY_END => KeyCode::End,
+        TERMBOX_KEY_TAB => KeyCode::Tab,
+        TERMBOX_KEY_BACKTAB => KeyCode::BackTab,
+        TERMBOX_KEY_ESC => KeyCode::Esc,
+        TERMBOX_KEY_INSERT => KeyCode::Insert,
+        code => char::from_u32(code).map_or(KeyCode::Null, KeyCode::Char),
+    }
+}
@@ -0,0 +1,46 @@
+/// Rewrites text before `ui::print_to_scroll_region` prints it
+pub trait OutputFormatter {
+    /// Returns `text` as it should appear above the input box. `cols` is the
+    /// terminal width, for formatters that lay text out themselves.
+    fn format(&self, text: &str, cols: usize) -> String;
+}
+
+/// Starts every line with `prefix`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixFormatter {
+    pub prefix: String,
+}
+
+impl OutputFormatter for PrefixFormatter {
+    fn format(&self, text: &str, _cols: usize) -> String {
+        prefix_lines(text, &self.prefix)
+    }
+}
+
+/// Starts every line with the local time as `HH:MM:SS `
+#[cfg(feature = "timestamps")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimestampFormatter;
+
+#[cfg(feature = "timestamps")]
+impl OutputFormatter for TimestampFormatter {
+    fn format(&self, text: &str, _cols: usize) -> String {
+        prefix_lines(text, &timestamp_prefix(&chrono::Local::now()))
+    }
+}
+
+/// The `HH:MM:SS ` prefix `TimestampFormatter` puts before lines printed at `time`
+#[cfg(feature = "timestamps")]
+pub fn timestamp_prefix<Tz: chrono::TimeZone>(time: &chrono::DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    time.format("%H:%M:%S ").to_string()
+}
+
+fn prefix_lines(text: &str, prefix: &str) -> String {
+    text.split('\n')
+        .map(|line| format!("{prefix}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
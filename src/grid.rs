@@ -0,0 +1,151 @@
+use crate::error::TermboxError;
+use crossterm::style::{
+    Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::{cursor::MoveTo, queue};
+use std::io::Write;
+use unicode_width::UnicodeWidthChar;
+
+/// `ch` of the cell covered by the right half of a wide character to its left
+pub const WIDE_CONTINUATION: char = '\0';
+
+/// One character cell of the terminal with its colors and attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalCell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+impl Default for TerminalCell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            fg: Color::Reset,
+            bg: Color::Reset,
+            bold: false,
+            underline: false,
+        }
+    }
+}
+
+/// What the terminal shows, one cell per column and row.
+///
+/// Drawing compares the grid for the next frame with the previous one, see
+/// [`diff_and_draw`], so only cells that changed are written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalGrid {
+    /// Cells indexed by `[row][col]`
+    pub cells: Vec<Vec<TerminalCell>>,
+    pub cols: usize,
+    pub rows: usize,
+}
+
+impl TerminalGrid {
+    /// A grid of blank cells
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cells: vec![vec![TerminalCell::default(); cols]; rows],
+            cols,
+            rows,
+        }
+    }
+
+    /// Write `text` starting at `(col, row)` with default colors, clipped to
+    /// the grid. Returns the column after the last character.
+    pub fn put_str(&mut self, col: usize, row: usize, text: &str) -> usize {
+        self.put_styled(col, row, text, TerminalCell::default())
+    }
+
+    /// Like [`put_str`](Self::put_str), taking colors and weight from `style`
+    /// and ignoring its `ch`. A wide character takes two cells, the second
+    /// holding [`WIDE_CONTINUATION`].
+    pub fn put_styled(&mut self, col: usize, row: usize, text: &str, style: TerminalCell) -> usize {
+        let mut col = col;
+        for ch in text.chars() {
+            let width = ch.width().unwrap_or(1).max(1);
+            for (i, ch) in [ch, WIDE_CONTINUATION].into_iter().take(width).enumerate() {
+                if let Some(cell) = self
+                    .cells
+                    .get_mut(row)
+                    .and_then(|cells| cells.get_mut(col + i))
+                {
+                    *cell = TerminalCell { ch, ..style };
+                }
+            }
+            col += width;
+        }
+        col
+    }
+}
+
+/// Draws the cells of `new` that differ from `old`.
+///
+/// A `MoveTo` is emitted at the start of each run of changed cells and colors
+/// are only set when they differ from the previous changed cell. If the grids
+/// have different sizes every cell of `new` is drawn.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `old` - What the terminal currently shows
+/// * `new` - What the terminal should show
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if output operations fail.
+pub fn diff_and_draw<W: Write>(
+    out: &mut W,
+    old: &TerminalGrid,
+    new: &TerminalGrid,
+) -> Result<(), TermboxError> {
+    let same_size = old.cols == new.cols && old.rows == new.rows;
+    let mut pen = TerminalCell::default();
+
+    for (row, cells) in new.cells.iter().enumerate() {
+        // Column the terminal cursor is at after the last print on this row
+        let mut cursor_col = None;
+        for (col, cell) in cells.iter().enumerate() {
+            // Drawn along with the wide character to its left
+            if cell.ch == WIDE_CONTINUATION || (same_size && old.cells[row][col] == *cell) {
+                continue;
+            }
+            if cursor_col != Some(col) {
+                queue!(out, MoveTo(col as u16, row as u16))?;
+            }
+            if cell.fg != pen.fg {
+                queue!(out, SetForegroundColor(cell.fg))?;
+            }
+            if cell.bg != pen.bg {
+                queue!(out, SetBackgroundColor(cell.bg))?;
+            }
+            if cell.bold != pen.bold {
+                let attribute = if cell.bold {
+                    Attribute::Bold
+                } else {
+                    Attribute::NormalIntensity
+                };
+                queue!(out, SetAttribute(attribute))?;
+            }
+            if cell.underline != pen.underline {
+                let attribute = if cell.underline {
+                    Attribute::Underlined
+                } else {
+                    Attribute::NoUnderline
+                };
+                queue!(out, SetAttribute(attribute))?;
+            }
+            pen = *cell;
+            queue!(out, Print(cell.ch))?;
+            cursor_col = Some(col + cell.ch.width().unwrap_or(1).max(1));
+        }
+    }
+
+    if pen.fg != Color::Reset || pen.bg != Color::Reset || pen.bold || pen.underline {
+        queue!(out, SetAttribute(Attribute::Reset))?;
+    }
+    out.flush()?;
+    Ok(())
+}
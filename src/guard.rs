@@ -0,0 +1,167 @@
+use crossterm::{
+    cursor::{MoveTo, Show},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    queue,
+    style::Print,
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use std::io::{Stdout, Write, stdout};
+use std::panic;
+use std::sync::{Mutex, Once};
+
+use crate::animation::AnimationRegistry;
+
+/// Restores the terminal when dropped.
+///
+/// Leaves raw mode, resets the scroll region, disables bracketed paste and
+/// mouse capture and shows the cursor, then moves the cursor to the bottom row. Animations
+/// registered with `cancel_animations_on_drop` are stopped first. Because this
+/// happens in `Drop`, the terminal is restored on early returns and panics
+/// as well as on a normal exit.
+pub struct TermboxGuard<W: Write = Stdout> {
+    /// Terminal width in columns
+    pub cols: usize,
+    /// Terminal height in rows
+    pub rows: usize,
+    out: W,
+    animations: Option<AnimationRegistry>,
+    set_raw_mode: fn(bool) -> std::io::Result<()>,
+    suspended: bool,
+}
+
+/// Turn raw mode on or off with crossterm
+fn set_raw_mode(enabled: bool) -> std::io::Result<()> {
+    if enabled {
+        enable_raw_mode()
+    } else {
+        disable_raw_mode()
+    }
+}
+
+impl TermboxGuard {
+    /// Create a guard that restores stdout. Construct it right after
+    /// `enable_raw_mode()`.
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self::with_writer(cols, rows, stdout())
+    }
+}
+
+impl<W: Write> TermboxGuard<W> {
+    /// Create a guard that writes its restore sequences to `out`
+    pub fn with_writer(cols: usize, rows: usize, out: W) -> Self {
+        Self {
+            cols,
+            rows,
+            out,
+            animations: None,
+            set_raw_mode,
+            suspended: false,
+        }
+    }
+
+    /// Replace the function that turns raw mode on and off, e.g. to record
+    /// the transitions where there is no terminal
+    pub fn set_raw_mode_switch(&mut self, set_raw_mode: fn(bool) -> std::io::Result<()>) {
+        self.set_raw_mode = set_raw_mode;
+    }
+
+    /// Cancel the animations of `registry` before restoring the terminal, so
+    /// none of them draws over it afterwards
+    pub fn cancel_animations_on_drop(&mut self, registry: AnimationRegistry) {
+        self.animations = Some(registry);
+    }
+
+    /// Update the terminal size after a resize
+    pub fn set_size(&mut self, cols: usize, rows: usize) {
+        self.cols = cols;
+        self.rows = rows;
+    }
+
+    /// Whether `suspend` was called without a `resume` after it
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Give the terminal back to the shell before stopping the process,
+    /// restoring it like `Drop` does but leaving animations running
+    pub fn suspend(&mut self) -> anyhow::Result<()> {
+        self.release()?;
+        self.suspended = true;
+        Ok(())
+    }
+
+    /// Take the terminal over again after `suspend`: raw mode, bracketed
+    /// paste and mouse capture. The caller sets the scroll region and
+    /// redraws the frame.
+    pub fn resume(&mut self) -> anyhow::Result<()> {
+        (self.set_raw_mode)(true)?;
+        queue!(self.out, EnableBracketedPaste, EnableMouseCapture)?;
+        self.out.flush()?;
+        self.suspended = false;
+        Ok(())
+    }
+
+    fn restore(&mut self) -> anyhow::Result<()> {
+        if let Some(animations) = &self.animations {
+            animations.cancel_all();
+        }
+        self.release()
+    }
+
+    fn release(&mut self) -> anyhow::Result<()> {
+        queue!(
+            self.out,
+            Print("\x1B[r"), // give terminal its full screen back
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            Show,
+            MoveTo(0, self.rows.saturating_sub(1) as u16)
+        )?;
+        self.out.flush()?;
+        (self.set_raw_mode)(false)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for TermboxGuard<W> {
+    fn drop(&mut self) {
+        // Nothing useful can be done with an error while dropping
+        let _ = self.restore();
+    }
+}
+
+/// Install a panic hook that restores stdout before the panic message is
+/// printed.
+///
+/// Complements `TermboxGuard` for panics whose unwinding never reaches its
+/// `Drop`, such as a panic while already panicking or with `panic = "abort"`.
+/// The hook leaves raw mode, resets the scroll region, disables bracketed
+/// paste and mouse capture and shows the cursor, then runs the previously
+/// installed hook, which prints the panic info. Only the first call installs
+/// the hook.
+pub fn set_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| set_panic_hook_with_writer(stdout()));
+}
+
+/// Like `set_panic_hook`, but writes the restore sequences to `out`. Every
+/// call chains another hook in front of the installed one.
+pub fn set_panic_hook_with_writer<W: Write + Send + 'static>(out: W) {
+    let out = Mutex::new(out);
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        // A panic while the writer was locked leaves it poisoned, not broken
+        let mut out = out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let _ = queue!(
+            out,
+            Print("\x1B[r"),
+            DisableBracketedPaste,
+            DisableMouseCapture,
+            Show
+        );
+        let _ = out.flush();
+        drop(out);
+        previous(info);
+    }));
+}
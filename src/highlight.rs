@@ -0,0 +1,53 @@
+use crossterm::style::Color;
+use regex::Regex;
+use std::ops::Range;
+
+/// How a highlighted span of input is drawn. `None` colors keep the theme's
+/// text colors.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+/// Colors parts of the input as it is typed
+pub trait SyntaxHighlighter {
+    /// Returns the byte ranges of `text` to draw in each style. Later spans
+    /// take precedence where spans overlap; the selection is drawn on top.
+    fn highlight(&self, text: &str) -> Vec<(Range<usize>, Style)>;
+}
+
+/// Highlights every match of a set of regular expressions
+#[derive(Debug, Clone, Default)]
+pub struct RegexHighlighter {
+    rules: Vec<(Regex, Style)>,
+}
+
+impl RegexHighlighter {
+    /// A highlighter without any rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule drawing every match of `pattern` in `style`. Rules added
+    /// later win where matches overlap.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pattern` is not a valid regular expression.
+    pub fn with_rule(mut self, pattern: &str, style: Style) -> anyhow::Result<Self> {
+        self.rules.push((Regex::new(pattern)?, style));
+        Ok(self)
+    }
+}
+
+impl SyntaxHighlighter for RegexHighlighter {
+    fn highlight(&self, text: &str) -> Vec<(Range<usize>, Style)> {
+        self.rules
+            .iter()
+            .flat_map(|(regex, style)| regex.find_iter(text).map(|m| (m.range(), *style)))
+            .collect()
+    }
+}
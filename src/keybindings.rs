@@ -0,0 +1,222 @@
+//! Configurable key bindings: which editing action each key press triggers.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::HashMap;
+
+/// An editing action a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputAction {
+    /// Insert a newline in `MultiLine` mode. In other modes a newline bound
+    /// to Enter submits instead, and any other key does nothing.
+    InsertNewline,
+    Submit,
+    Exit,
+    /// Copy the selection to the kill ring, or exit when nothing is selected
+    CopyOrExit,
+    ClearScreen,
+    /// Paste from the clipboard; does nothing without the `clipboard` feature
+    Paste,
+    /// Start a reverse incremental history search
+    SearchHistory,
+    /// Delete the selection or the character before the cursor
+    DeleteBefore,
+    /// Delete the selection or the character after the cursor
+    DeleteAfter,
+    MoveLeft,
+    MoveRight,
+    MoveLineStart,
+    MoveLineEnd,
+    /// Delete the text of the logical line the cursor is on
+    ClearLine,
+    /// Replace the buffer with the previous history entry
+    HistoryPrev,
+    /// Replace the buffer with the next history entry, or the text typed
+    /// before browsing history after the newest one
+    HistoryNext,
+    /// Complete the word before the cursor, or insert a tab per `tab_mode`
+    /// when there is no tab completer
+    Indent,
+    Dedent,
+    ToggleOverwrite,
+    /// Print the `help` command's output above the input box
+    ShowHelp,
+    /// Delete the whole buffer
+    ClearBuffer,
+    /// Stop the process like Ctrl+Z in a shell, which raw mode prevents
+    Suspend,
+    /// Restore the buffer from before the last edit
+    Undo,
+}
+
+/// Which of Enter and another key submits the input in `MultiLine` mode, the
+/// other inserting a newline. In `SingleLine` mode Enter always submits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SubmitKey {
+    /// Enter submits and Alt+Enter inserts a newline
+    #[default]
+    AltEnter,
+    /// The other way around: Enter inserts a newline and Alt+Enter submits
+    Enter,
+    /// Enter inserts a newline and Ctrl+Enter submits. Only terminals that
+    /// report Ctrl+Enter apart from Enter can submit this way.
+    CtrlEnter,
+    /// Enter inserts a newline and Ctrl+D submits instead of exiting
+    CtrlD,
+}
+
+/// Maps key presses to actions.
+///
+/// Keys without a binding fall back to the built-in behavior: characters are
+/// typed, anything else is ignored. A binding for a key without Shift also
+/// applies with Shift held, so Shift+Left still moves left while extending
+/// the selection, and a key other than a character bound without modifiers
+/// also applies with any modifiers that have no binding of their own.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Vec<KeyBinding>", from = "Vec<KeyBinding>")
+)]
+pub struct KeyBindings {
+    bindings: HashMap<(KeyCode, KeyModifiers), InputAction>,
+}
+
+/// How a single binding is serialized; JSON maps cannot have tuple keys
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct KeyBinding {
+    key: KeyCode,
+    modifiers: KeyModifiers,
+    action: InputAction,
+}
+
+#[cfg(feature = "serde")]
+impl From<KeyBindings> for Vec<KeyBinding> {
+    fn from(bindings: KeyBindings) -> Self {
+        bindings
+            .bindings
+            .into_iter()
+            .map(|((key, modifiers), action)| KeyBinding {
+                key,
+                modifiers,
+                action,
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Vec<KeyBinding>> for KeyBindings {
+    fn from(bindings: Vec<KeyBinding>) -> Self {
+        let mut key_bindings = KeyBindings::empty();
+        for binding in bindings {
+            key_bindings.bind(binding.key, binding.modifiers, binding.action);
+        }
+        key_bindings
+    }
+}
+
+impl KeyBindings {
+    /// Bindings with no keys bound at all
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Bind a key, returning the action it was bound to before
+    pub fn bind(
+        &mut self,
+        key_code: KeyCode,
+        modifiers: KeyModifiers,
+        action: InputAction,
+    ) -> Option<InputAction> {
+        self.bindings.insert((key_code, modifiers), action)
+    }
+
+    /// Remove the binding of a key, returning the action it was bound to
+    pub fn unbind(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> Option<InputAction> {
+        self.bindings.remove(&(key_code, modifiers))
+    }
+
+    /// The action for a key press, if it is bound
+    pub fn get(&self, key_code: KeyCode, modifiers: KeyModifiers) -> Option<InputAction> {
+        let unshifted = modifiers - KeyModifiers::SHIFT;
+        self.bindings
+            .get(&(key_code, modifiers))
+            .or_else(|| self.bindings.get(&(key_code, unshifted)))
+            .or_else(|| match key_code {
+                KeyCode::Char(_) => None,
+                _ => self.bindings.get(&(key_code, KeyModifiers::NONE)),
+            })
+            .copied()
+    }
+
+    /// Bind the keys `submit_key` describes to `Submit` and `InsertNewline`:
+    /// Alt+Enter to `InsertNewline` for `SubmitKey::AltEnter`, and Enter for
+    /// the others. Other bindings are kept.
+    pub fn set_submit_key(&mut self, submit_key: SubmitKey) {
+        let (key_code, modifiers) = match submit_key {
+            SubmitKey::AltEnter => (KeyCode::Enter, KeyModifiers::NONE),
+            SubmitKey::Enter => (KeyCode::Enter, KeyModifiers::ALT),
+            SubmitKey::CtrlEnter => (KeyCode::Enter, KeyModifiers::CONTROL),
+            SubmitKey::CtrlD => (KeyCode::Char('d'), KeyModifiers::CONTROL),
+        };
+        let newline = match submit_key {
+            SubmitKey::AltEnter => KeyModifiers::ALT,
+            _ => KeyModifiers::NONE,
+        };
+        self.bind(KeyCode::Enter, newline, InputAction::InsertNewline);
+        self.bind(key_code, modifiers, InputAction::Submit);
+    }
+
+    /// Every binding, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (KeyCode, KeyModifiers, InputAction)> + '_ {
+        self.bindings
+            .iter()
+            .map(|(&(key_code, modifiers), &action)| (key_code, modifiers, action))
+    }
+}
+
+impl Default for KeyBindings {
+    /// The keys described in the README
+    fn default() -> Self {
+        use InputAction::*;
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let alt = KeyModifiers::ALT;
+
+        let mut bindings = Self::empty();
+        for (key_code, modifiers, action) in [
+            (KeyCode::Esc, none, Exit),
+            (KeyCode::Char('c'), ctrl, CopyOrExit),
+            (KeyCode::Char('d'), ctrl, Exit),
+            (KeyCode::Char('l'), ctrl, ClearScreen),
+            (KeyCode::Char('r'), ctrl, SearchHistory),
+            (KeyCode::Char('j'), ctrl, InsertNewline),
+            (KeyCode::Enter, none, Submit),
+            (KeyCode::Enter, alt, InsertNewline),
+            (KeyCode::Enter, ctrl | alt, InsertNewline),
+            (KeyCode::Backspace, none, DeleteBefore),
+            (KeyCode::Left, none, MoveLeft),
+            (KeyCode::Right, none, MoveRight),
+            (KeyCode::Home, none, MoveLineStart),
+            (KeyCode::End, none, MoveLineEnd),
+            (KeyCode::Tab, none, Indent),
+            (KeyCode::BackTab, none, Dedent),
+            (KeyCode::Insert, none, ToggleOverwrite),
+            (KeyCode::Char('_'), ctrl, Undo),
+            // Terminals send Ctrl+_ as 0x1F, which crossterm reads as Ctrl+7
+            (KeyCode::Char('7'), ctrl, Undo),
+        ] {
+            bindings.bind(key_code, modifiers, action);
+        }
+        #[cfg(feature = "clipboard")]
+        bindings.bind(KeyCode::Char('v'), ctrl, Paste);
+        #[cfg(unix)]
+        bindings.bind(KeyCode::Char('z'), ctrl, Suspend);
+        bindings
+    }
+}
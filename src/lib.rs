@@ -1,11 +1,56 @@
-use crossterm::event::{KeyCode, KeyModifiers};
+use crate::error::TermboxError;
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::{
+    Attribute, Color, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
 use crossterm::{cursor::MoveTo, queue, style::Print};
-use std::io::Write;
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
 use tokio::sync::broadcast;
+use unicode_bidi::BidiInfo;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+pub mod animation;
+pub mod app;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
 pub mod commands;
+pub mod content;
+pub mod error;
+pub mod ffi;
+pub mod format;
+pub mod grid;
+pub mod guard;
+pub mod highlight;
+pub mod keybindings;
+pub mod plugin;
+pub mod signals;
 pub mod ui;
 
+/// The most used types and functions, for `use termbox::prelude::*;`
+pub mod prelude {
+    pub use crate::commands::{CommandDispatcher, CommandResult};
+    pub use crate::error::TermboxError;
+    pub use crate::ui::RuleStyle;
+    pub use crate::{
+        Config, FRAME_CHARS, FramePosition, FrameStyle, InputAction, InputMode, InputState,
+        KeyAction, KeyBindings, Layout, ResizeAction, StatusFn, StatusMode, SubmitKey, TabMode,
+        Theme, WrapMode, calculate_cursor_position, calculate_required_lines,
+        capture_terminal_drawing, draw_frame_to_buffer, draw_prompt_line_to_buffer,
+        render_text_lines, to_strings,
+    };
+}
+
+pub use app::TermboxApp;
+pub use keybindings::{InputAction, KeyBindings, SubmitKey};
+
 const LEFT_FRAME_CHARS: usize = const_str::to_char_array!("│ > ").len();
 const RIGHT_FRAME_CHARS: usize = const_str::to_char_array!("│").len();
 
@@ -14,113 +59,2943 @@ const RIGHT_FRAME_CHARS: usize = const_str::to_char_array!("│").len();
 pub enum ScrollEvent {
     /// Terminal content scrolled up by N lines
     ScrolledUp(usize),
+    /// Terminal content scrolled down by N lines, e.g. when the frame grows
+    ScrolledDown(usize),
 }
 
 /// The number of characters used for frame borders and prompt prefix
 /// Format: "│ > " (4 chars) + "│" (1 char) = 5 chars total
 pub const FRAME_CHARS: usize = LEFT_FRAME_CHARS + RIGHT_FRAME_CHARS;
 
-/// Result of handling a keyboard event
-#[derive(Debug, PartialEq)]
-pub enum KeyAction {
-    Continue,
-    Exit,
+/// Default character drawn in place of each typed character in password mode
+pub const DEFAULT_MASK_CHAR: char = '*';
+
+/// Scroll events buffered per receiver; a receiver that falls further
+/// behind counts each lost event as one line, see `animation::drain_scroll_events`
+pub const SCROLL_CHANNEL_CAPACITY: usize = 64;
+
+/// Columns between tab stops when a literal tab is displayed
+pub const TAB_WIDTH: usize = 4;
+
+/// Brackets and quotes completed as they are typed, as `(opener, closer)`
+pub const DEFAULT_AUTO_PAIRS: [(char, char); 5] =
+    [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+
+/// Background color used to highlight selected text by the default theme
+pub const SELECTION_BG: Color = Color::DarkGrey;
+
+/// Background of the cursor's line with `Config::highlight_current_line`
+/// in the default theme, a grey darker than `SELECTION_BG`
+pub const CURRENT_LINE_BG: Color = Color::AnsiValue(236);
+
+/// Most edits [`InputState::undo`] can go back through
+pub const UNDO_LIMIT: usize = 100;
+
+/// Longest time between two clicks on the same spot that select a word
+pub const DOUBLE_CLICK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// How long `[max]` stays in the top border after a newline is dropped
+pub const MAX_LINES_INDICATOR_DURATION: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Shown in the bottom border while the buffer contains right-to-left text,
+/// which is drawn left to right like any other
+pub const RTL_WARNING: &str = "RTL text detected - display may be incorrect";
+
+/// Result of handling a keyboard event
+#[derive(Debug, PartialEq)]
+pub enum KeyAction {
+    Continue,
+    Exit,
+    /// The input was submitted; carries the submitted text.
+    ///
+    /// The buffer has already been cleared when this is returned, so callers no
+    /// longer need to detect Enter themselves and call `get_submitted_text`.
+    Submit(String),
+    /// Ctrl+L: the caller should clear the scroll region and redraw the frame
+    ClearScreen,
+    /// F1: the caller should run the `help` command
+    ShowHelp,
+    /// Ctrl+Z: the caller should give the terminal back and stop the process
+    /// until it is continued, see `TermboxGuard::suspend`
+    Suspend,
+}
+
+/// What the terminal needs after `InputState::handle_resize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeAction {
+    /// The size did not change, e.g. a repeated resize event
+    NoChange,
+    /// Only the height changed and the frame kept its height: the scroll
+    /// region must be set again and the frame redrawn, but the output above
+    /// it does not need to be rewrapped
+    RegionOnly,
+    /// The width or the frame height changed; repaint everything
+    FullRedraw,
+}
+
+/// Controls how the input box treats newlines and how its content is displayed
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputMode {
+    /// Alt+Enter or Ctrl+J insert a newline, Enter submits; see
+    /// `Config::submit_key` to swap them
+    #[default]
+    MultiLine,
+    /// Enter always submits, newline keys are ignored
+    SingleLine,
+    /// Single-line input drawn as `mask_char`, wiped from memory on submit
+    Password,
+}
+
+/// What the Tab key inserts when no tab completer is installed
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TabMode {
+    /// This many spaces; Shift+Tab removes up to as many leading spaces
+    Spaces(usize),
+    /// A `\t` character, displayed up to the next multiple of `TAB_WIDTH`
+    Literal,
+}
+
+impl Default for TabMode {
+    fn default() -> Self {
+        TabMode::Spaces(TAB_WIDTH)
+    }
+}
+
+/// How lines longer than the frame is wide are handled
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WrapMode {
+    /// Wrap them onto more rows for display only
+    #[default]
+    Soft,
+    /// In `MultiLine` mode, insert a `\n` before typing a character on a line
+    /// that already has this many; other modes wrap as with `Soft`
+    Hard(usize),
+    /// Keep each line on one row and scroll them sideways to follow the cursor
+    None,
+}
+
+/// Where the input box sits in the height of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FramePosition {
+    /// Against the bottom, with output scrolling above it
+    #[default]
+    Bottom,
+    /// Against the top, with output scrolling below it
+    Top,
+    /// In the middle, with output scrolling above it
+    Center,
+}
+
+/// How the top and bottom of the input box are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameStyle {
+    /// Rounded box drawing borders all around: `╭─╮`, `│` and `╰─╯`
+    #[default]
+    Rounded,
+    /// Only the `│` sides; the top and bottom rows are left blank, apart from
+    /// any title or status
+    Minimal,
+}
+
+impl FrameStyle {
+    /// Left corner, line and right corner of the top border
+    fn top_chars(self) -> (char, char, char) {
+        match self {
+            FrameStyle::Rounded => ('╭', '─', '╮'),
+            FrameStyle::Minimal => (' ', ' ', ' '),
+        }
+    }
+
+    /// Left corner, line and right corner of the bottom border
+    fn bottom_chars(self) -> (char, char, char) {
+        match self {
+            FrameStyle::Rounded => ('╰', '─', '╯'),
+            FrameStyle::Minimal => (' ', ' ', ' '),
+        }
+    }
+}
+
+/// Where the input box sits across the width of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Layout {
+    /// The box spans the whole width, below the output
+    #[default]
+    Single,
+    /// Output is kept to the columns left of `split_col`, a `│` separator is
+    /// drawn at it and the box takes the columns to its right
+    SideBySide { split_col: usize },
+}
+
+impl Layout {
+    /// First terminal column of the frame
+    pub fn frame_left(self) -> usize {
+        match self {
+            Layout::Single => 0,
+            Layout::SideBySide { split_col } => split_col + 1,
+        }
+    }
+
+    /// Width of the frame in a `cols` wide terminal
+    pub fn frame_cols(self, cols: usize) -> usize {
+        cols.saturating_sub(self.frame_left())
+    }
+
+    /// Width output is printed at in a `cols` wide terminal
+    pub fn output_cols(self, cols: usize) -> usize {
+        match self {
+            Layout::Single => cols,
+            Layout::SideBySide { split_col } => split_col.min(cols),
+        }
+    }
+}
+
+/// Count shown in the bottom border, next to the `OVR` indicator
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatusMode {
+    CharCount,
+    WordCount,
+    LineCount,
+    /// The cursor's line and column as `L:C`, both counted from 1
+    LineInfo,
+    /// Whatever the function returns for the current state; not saved by
+    /// the `serde` feature
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(StatusFn),
+    #[default]
+    None,
+}
+
+/// Builds the status text of `StatusMode::Custom`
+pub type StatusFn = Arc<dyn Fn(&InputState) -> String + Send + Sync>;
+
+impl std::fmt::Debug for StatusMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusMode::CharCount => f.write_str("CharCount"),
+            StatusMode::WordCount => f.write_str("WordCount"),
+            StatusMode::LineCount => f.write_str("LineCount"),
+            StatusMode::LineInfo => f.write_str("LineInfo"),
+            StatusMode::Custom(_) => f.write_str("Custom(..)"),
+            StatusMode::None => f.write_str("None"),
+        }
+    }
+}
+
+/// Custom modes are equal only when they share the same function
+impl PartialEq for StatusMode {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (StatusMode::Custom(a), StatusMode::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+/// Settings for a termbox session.
+///
+/// Start from `Config::default()` and set the fields you need; more fields
+/// may be added in later versions.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Config {
+    /// Mode the input box starts in
+    pub input_mode: InputMode,
+    /// Character drawn in place of each typed character in password mode
+    pub mask_char: char,
+    /// File the session is saved to, and how often. `TermboxApp` restores
+    /// the session from it on startup.
+    #[cfg(feature = "serde")]
+    pub auto_save: Option<(std::path::PathBuf, std::time::Duration)>,
+    /// Colors of the input box
+    pub theme: Theme,
+    /// Start each new line with the indentation of the line before it
+    pub auto_indent: bool,
+    /// Characters that insert their closing pair when typed; empty to disable
+    pub auto_pairs: Vec<(char, char)>,
+    /// What Tab inserts when there is no tab completer
+    pub tab_mode: TabMode,
+    /// Count shown in the bottom border
+    pub status_mode: StatusMode,
+    /// What each key does
+    pub key_bindings: KeyBindings,
+    /// Which key submits and which inserts a newline, applied to
+    /// `key_bindings` unless it is the default `SubmitKey::AltEnter`
+    pub submit_key: SubmitKey,
+    /// Actions of the function keys F1-F12 by number, used when
+    /// `key_bindings` has no binding for the key
+    pub function_keys: HashMap<u8, InputAction>,
+    /// How long the built-in box animations such as `tiktok` may run
+    pub default_animation_timeout: std::time::Duration,
+    /// Most logical lines the input may have; newlines past it are dropped
+    pub max_lines: Option<usize>,
+    /// How long lines are wrapped
+    pub wrap_mode: WrapMode,
+    /// Drawn at the end of each row a long line wraps from, e.g. `'↩'`
+    pub wrap_indicator: Option<char>,
+    /// Blank columns inside each side of the frame, around the prompt and text
+    pub frame_padding: usize,
+    /// Whether the frame has top and bottom borders
+    pub frame_style: FrameStyle,
+    /// Where the input box is drawn
+    pub layout: Layout,
+    /// Rows the input box is drawn at
+    pub frame_position: FramePosition,
+    /// Draw the rows of the line the cursor is on in `theme.current_line_bg`
+    pub highlight_current_line: bool,
+    /// Terminals wider than this many columns use `Layout::SideBySide`,
+    /// split in half, whatever `layout` says
+    pub multi_column_threshold: Option<usize>,
+    /// Start every line printed above the input box with the local time,
+    /// using `format::TimestampFormatter`
+    #[cfg(feature = "timestamps")]
+    pub show_timestamps: bool,
+}
+
+/// F1 shows help and F5 clears the input
+fn default_function_keys() -> HashMap<u8, InputAction> {
+    HashMap::from([(1, InputAction::ShowHelp), (5, InputAction::ClearBuffer)])
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            input_mode: InputMode::default(),
+            mask_char: DEFAULT_MASK_CHAR,
+            theme: Theme::default(),
+            auto_indent: false,
+            auto_pairs: DEFAULT_AUTO_PAIRS.to_vec(),
+            tab_mode: TabMode::default(),
+            status_mode: StatusMode::default(),
+            key_bindings: KeyBindings::default(),
+            submit_key: SubmitKey::default(),
+            function_keys: default_function_keys(),
+            default_animation_timeout: animation::DEFAULT_ANIMATION_TIMEOUT,
+            max_lines: None,
+            wrap_mode: WrapMode::default(),
+            wrap_indicator: None,
+            frame_padding: 0,
+            frame_style: FrameStyle::default(),
+            layout: Layout::default(),
+            frame_position: FramePosition::default(),
+            highlight_current_line: false,
+            multi_column_threshold: None,
+            #[cfg(feature = "timestamps")]
+            show_timestamps: false,
+            #[cfg(feature = "serde")]
+            auto_save: None,
+        }
+    }
+}
+
+/// Colors used to draw the input box.
+///
+/// `Color::Reset` keeps the terminal's own color, and parts drawn entirely in
+/// `Color::Reset` are printed without any color escape sequences.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    /// Frame borders and the title embedded in the top border
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub border_fg: Color,
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub border_bg: Color,
+    /// The `>` prompt on the first line
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub prompt_fg: Color,
+    /// Input text, completion candidates and the empty space around them
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub text_fg: Color,
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub text_bg: Color,
+    /// Background of selected text
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub selection_bg: Color,
+    /// Background of the line the cursor is on, when
+    /// `Config::highlight_current_line` is set
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub current_line_bg: Color,
+    /// Hint text shown in place of empty input. Nothing draws a placeholder
+    /// yet; the color is here so themes stay complete.
+    #[cfg_attr(feature = "serde", serde(with = "theme_color"))]
+    pub placeholder_fg: Color,
+}
+
+/// Serializes colors by name, adding `"reset"` for `Color::Reset`, which
+/// crossterm cannot serialize itself
+#[cfg(feature = "serde")]
+mod theme_color {
+    use crossterm::style::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        match color {
+            Color::Reset => serializer.serialize_str("reset"),
+            color => color.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if name == "reset" {
+            return Ok(Color::Reset);
+        }
+        Color::deserialize(serde::de::value::StrDeserializer::new(&name))
+    }
+}
+
+impl Theme {
+    /// Light borders and text for terminals with a dark background
+    pub fn dark() -> Self {
+        Self {
+            border_fg: Color::DarkCyan,
+            border_bg: Color::Reset,
+            prompt_fg: Color::Cyan,
+            text_fg: Color::White,
+            text_bg: Color::Reset,
+            selection_bg: Color::DarkGrey,
+            current_line_bg: CURRENT_LINE_BG,
+            placeholder_fg: Color::DarkGrey,
+        }
+    }
+
+    /// Dark borders and text for terminals with a light background
+    pub fn light() -> Self {
+        Self {
+            border_fg: Color::DarkBlue,
+            border_bg: Color::Reset,
+            prompt_fg: Color::Blue,
+            text_fg: Color::Black,
+            text_bg: Color::Reset,
+            selection_bg: Color::Grey,
+            current_line_bg: Color::AnsiValue(254),
+            placeholder_fg: Color::Grey,
+        }
+    }
+}
+
+impl Default for Theme {
+    /// The terminal's own colors everywhere. Selections still get
+    /// `SELECTION_BG` and the current line `CURRENT_LINE_BG` so they stay
+    /// visible.
+    fn default() -> Self {
+        Self {
+            border_fg: Color::Reset,
+            border_bg: Color::Reset,
+            prompt_fg: Color::Reset,
+            text_fg: Color::Reset,
+            text_bg: Color::Reset,
+            selection_bg: SELECTION_BG,
+            current_line_bg: CURRENT_LINE_BG,
+            placeholder_fg: Color::Reset,
+        }
+    }
+}
+
+/// Runs termbox with `config`, choosing the mode from stdout.
+///
+/// When stdout is a terminal the interactive input box is shown. Otherwise,
+/// e.g. when output is piped to a file, stdin is copied to stdout line by line
+/// without drawing anything.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on successful completion or an error if terminal operations fail.
+pub async fn run(config: Config) -> anyhow::Result<()> {
+    if std::io::stdout().is_terminal() {
+        app::run_tty(config).await
+    } else {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        app::run_non_tty(stdin, &mut std::io::stdout()).await
+    }
+}
+
+/// The parts of an `InputState` worth keeping between sessions
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputStateSnapshot {
+    pub buffer: String,
+    pub cursor_byte_offset: usize,
+    /// Previously submitted entries, oldest first
+    pub history: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+impl InputStateSnapshot {
+    /// Write the snapshot to `path` as JSON.
+    ///
+    /// The JSON goes to `<path>.tmp` first and is then renamed over `path`, so
+    /// a crash mid-write never leaves a truncated file behind.
+    pub fn save_to_file(&self, path: &std::path::Path) -> Result<(), TermboxError> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let json = serde_json::to_vec(self).map_err(std::io::Error::from)?;
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Supplies completion candidates when the user presses Tab
+pub trait TabCompletion {
+    /// Returns candidates for the text before the cursor.
+    ///
+    /// Each candidate replaces the word being completed, i.e. the trailing run
+    /// of non-whitespace characters in `prefix`.
+    fn complete(&self, prefix: &str) -> Vec<String>;
+}
+
+/// State of an in-progress reverse incremental history search (Ctrl+R)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchState {
+    pub query: String,
+    /// Index into `history` of the current match, or `history.len()` before
+    /// anything has matched
+    pub match_index: usize,
+    /// Buffer contents from before the search, restored when it is cancelled
+    pub draft: String,
+}
+
+/// A change [`InputState::undo`] can revert: the text `removed` at byte
+/// `start` of the buffer and the length of what replaced it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferEdit {
+    pub start: usize,
+    pub removed: String,
+    pub inserted: usize,
+}
+
+/// The edits made by one key press, paste or `replace_range` call, undone
+/// together, and the cursor from before them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoEntry {
+    pub edits: Vec<BufferEdit>,
+    pub cursor: usize,
+}
+
+/// What `InputState` knows about its logical lines besides `line_lengths`,
+/// kept up to date by `splice` for the lines each edit touches
+#[derive(Debug, Clone, Default)]
+struct LineLayout {
+    /// Rows each line wraps to at `cols` text columns
+    rows: Vec<usize>,
+    /// Sum of `rows`
+    total_rows: usize,
+    /// Text columns `rows` was wrapped for
+    cols: usize,
+    /// Lines with characters other than one-column ASCII. While there are
+    /// none, a new width only needs the lengths divided again.
+    non_ascii_lines: usize,
+    /// Lines with right-to-left text, see `InputState::has_rtl`
+    rtl_lines: usize,
+    /// `(byte offset, index)` of the start of the line last edited, so the
+    /// line of the next edit is found by counting only the newlines between
+    hint: (usize, usize),
+    /// Set by `InputState::buffer_mut`: nothing here may match the buffer
+    stale: bool,
+}
+
+/// A logical line as measured by `InputState::measure_line`
+struct LineMeasure {
+    width: usize,
+    rows: usize,
+    one_column: bool,
+    rtl: bool,
+}
+
+/// Callback for `InputState::on_change`
+pub type OnChange = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Verdict of an `InputState::on_submit_async` callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmitAction {
+    Accept,
+    /// Keep the input and show the message in the top border
+    Reject(String),
+}
+
+/// Callback for `InputState::on_submit_async`
+pub type OnSubmitAsync =
+    Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = SubmitAction> + Send>> + Send + Sync>;
+
+/// State of the input application
+pub struct InputState {
+    /// The text being edited. Read it with [`buffer`](Self::buffer) and edit
+    /// it with [`replace_range`](Self::replace_range) or
+    /// [`buffer_mut`](Self::buffer_mut).
+    buffer: String,
+    pub cols: usize,
+    pub rows: usize,
+    pub required_lines: usize,
+    /// Tells animations how far the content above the frame moved. Created
+    /// by the first [`setup_scroll_broadcast`](Self::setup_scroll_broadcast).
+    pub scroll_broadcast: Option<broadcast::Sender<ScrollEvent>>,
+    pub last_command_height: usize,
+    pub input_mode: InputMode,
+    pub mask_char: char,
+    /// Set while a bracketed paste is being inserted; frame recalculation is
+    /// deferred until the paste ends
+    pub paste_mode: bool,
+    pub tab_completer: Option<Arc<dyn TabCompletion + Send + Sync>>,
+    /// Colors the input as it is drawn; not used in password mode
+    pub highlighter: Option<Arc<dyn highlight::SyntaxHighlighter + Send + Sync>>,
+    /// Called with the buffer after each key, paste or edit that changed
+    /// it; never in password mode
+    pub on_change: Option<OnChange>,
+    /// Checks input before [`handle_key_async`](Self::handle_key_async)
+    /// submits it; the buffer is only cleared once it accepts
+    pub on_submit_async: Option<OnSubmitAsync>,
+    /// Message of the last rejected submission, shown in red in the top
+    /// border until the next key
+    pub submit_error: Option<String>,
+    /// Rewrites text printed above the input box
+    pub output_formatter: Option<Arc<dyn format::OutputFormatter + Send + Sync>>,
+    /// Consulted in order before the built-in key bindings
+    pub plugins: Vec<Arc<dyn plugin::Plugin + Send + Sync>>,
+    /// State kept by plugins, one value per type
+    pub plugin_storage: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    /// Candidates shown under the input when a completion was ambiguous
+    pub completion_popup: Vec<String>,
+    /// Previously submitted entries, oldest first
+    pub history: Vec<String>,
+    pub search: Option<SearchState>,
+    /// Index into `history` of the entry shown by `InputAction::HistoryPrev`
+    /// and the text typed before browsing history, restored past the newest
+    pub history_browse: Option<(usize, String)>,
+    /// Byte offset of the cursor in `buffer`, always on a char boundary
+    pub cursor_byte_offset: usize,
+    /// Byte offset where the selection was started; the selection spans from
+    /// here to the cursor
+    pub selection_start: Option<usize>,
+    /// Typed characters replace the one under the cursor instead of being
+    /// inserted; toggled with Insert
+    pub overwrite_mode: bool,
+    /// Text copied with Ctrl+C, most recent last
+    pub kill_ring: Vec<String>,
+    /// The edits of each key press, paste and `replace_range` call, most
+    /// recent last, for [`undo`](Self::undo). Nothing is recorded in
+    /// password mode.
+    pub undo_stack: Vec<UndoEntry>,
+    /// Edits of the key press, paste or call in progress, pushed to
+    /// `undo_stack` when it ends
+    undo_recording: Option<UndoEntry>,
+    /// The most recently started background animation, if any. Cleared by
+    /// [`clear_finished_animations`](Self::clear_finished_animations).
+    pub active_animation: Option<animation::AnimationHandle>,
+    /// The running spinner, cancelled on the next Enter
+    pub spinner: Option<animation::AnimationHandle>,
+    pub theme: Theme,
+    /// Newlines repeat the leading whitespace of the line they split
+    pub auto_indent: bool,
+    /// `(opener, closer)` pairs completed as they are typed, except in
+    /// password mode
+    pub auto_pairs: Vec<(char, char)>,
+    /// What Tab inserts when there is no tab completer
+    pub tab_mode: TabMode,
+    /// Count shown in the bottom border, except in password mode
+    pub status_mode: StatusMode,
+    /// What each key does
+    pub key_bindings: KeyBindings,
+    /// Actions of function keys without a binding in `key_bindings`
+    pub function_keys: HashMap<u8, InputAction>,
+    /// Most logical lines the buffer may have
+    pub max_lines: Option<usize>,
+    /// When a newline was last dropped because of `max_lines`. `[max]` is
+    /// shown in the top border until the next key or for
+    /// [`MAX_LINES_INDICATOR_DURATION`].
+    pub max_lines_reached: Option<tokio::time::Instant>,
+    /// How long lines are wrapped
+    pub wrap_mode: WrapMode,
+    /// Drawn in the last column of rows a long line wraps from; the text is
+    /// laid out a column narrower while it is set
+    pub wrap_indicator: Option<char>,
+    /// Blank columns inside each side of the frame; the text is laid out
+    /// twice as many columns narrower
+    pub frame_padding: usize,
+    /// Whether the frame has top and bottom borders. Their rows are kept
+    /// either way, so `required_lines` does not depend on it.
+    pub frame_style: FrameStyle,
+    /// Where the input box is drawn, see [`effective_layout`](Self::effective_layout)
+    pub layout: Layout,
+    /// Width past which the box is drawn side by side with the output
+    pub multi_column_threshold: Option<usize>,
+    /// Rows the input box is drawn at, see [`frame_start_row`](Self::frame_start_row)
+    pub frame_position: FramePosition,
+    /// Draw the cursor's line in `theme.current_line_bg`
+    pub highlight_current_line: bool,
+    /// Time and `(column, row)` of the last left click, to detect a double click
+    pub last_click: Option<(tokio::time::Instant, u16, u16)>,
+    /// Display width of each logical line, e.g. with tabs expanded. Edits
+    /// update only the lines they touch, so typing does not measure or wrap
+    /// the whole buffer again to size the frame.
+    pub line_lengths: Vec<usize>,
+    /// Rows and counts kept alongside `line_lengths`
+    line_layout: LineLayout,
+    /// Whether the buffer contains right-to-left text such as Hebrew or
+    /// Arabic, kept up to date with `line_lengths`. Flagged with `⚠` in the
+    /// top border and [`RTL_WARNING`] in the bottom border.
+    pub has_rtl: bool,
+    /// Rows of text scrolled past at the top of a frame taller than the
+    /// terminal. Kept while the cursor's row is in view, and moved just far
+    /// enough to bring it back otherwise.
+    pub scroll_offset: usize,
+    /// What the last prompt draw put on screen. Set to `None` after drawing
+    /// the frame any other way so the next draw repaints it in full.
+    pub prev_grid: Option<grid::TerminalGrid>,
+    /// Everything printed to the scroll region, for redrawing it on resize
+    pub content: content::ContentBuffer,
+    /// Clipboard used by Ctrl+V, the system clipboard by default
+    #[cfg(feature = "clipboard")]
+    pub clipboard: Arc<dyn clipboard::ClipboardProvider + Send + Sync>,
+}
+
+/// A copy of the state for editing separately. Animations keep running for
+/// the original only, so the copy has no `active_animation` or `spinner`, and
+/// its plugins start over with an empty `plugin_storage`, whose values need
+/// not be `Clone`.
+impl Clone for InputState {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone(),
+            cols: self.cols,
+            rows: self.rows,
+            required_lines: self.required_lines,
+            scroll_broadcast: self.scroll_broadcast.clone(),
+            last_command_height: self.last_command_height,
+            input_mode: self.input_mode.clone(),
+            mask_char: self.mask_char,
+            paste_mode: self.paste_mode,
+            tab_completer: self.tab_completer.clone(),
+            highlighter: self.highlighter.clone(),
+            on_change: self.on_change.clone(),
+            on_submit_async: self.on_submit_async.clone(),
+            submit_error: self.submit_error.clone(),
+            output_formatter: self.output_formatter.clone(),
+            plugins: self.plugins.clone(),
+            plugin_storage: HashMap::new(),
+            completion_popup: self.completion_popup.clone(),
+            history: self.history.clone(),
+            search: self.search.clone(),
+            history_browse: self.history_browse.clone(),
+            cursor_byte_offset: self.cursor_byte_offset,
+            selection_start: self.selection_start,
+            overwrite_mode: self.overwrite_mode,
+            kill_ring: self.kill_ring.clone(),
+            undo_stack: self.undo_stack.clone(),
+            undo_recording: self.undo_recording.clone(),
+            active_animation: None,
+            spinner: None,
+            theme: self.theme,
+            auto_indent: self.auto_indent,
+            auto_pairs: self.auto_pairs.clone(),
+            tab_mode: self.tab_mode,
+            status_mode: self.status_mode.clone(),
+            key_bindings: self.key_bindings.clone(),
+            function_keys: self.function_keys.clone(),
+            max_lines: self.max_lines,
+            max_lines_reached: self.max_lines_reached,
+            wrap_mode: self.wrap_mode,
+            wrap_indicator: self.wrap_indicator,
+            frame_padding: self.frame_padding,
+            frame_style: self.frame_style,
+            layout: self.layout,
+            multi_column_threshold: self.multi_column_threshold,
+            frame_position: self.frame_position,
+            highlight_current_line: self.highlight_current_line,
+            last_click: self.last_click,
+            line_lengths: self.line_lengths.clone(),
+            line_layout: self.line_layout.clone(),
+            has_rtl: self.has_rtl,
+            scroll_offset: self.scroll_offset,
+            prev_grid: self.prev_grid.clone(),
+            content: self.content.clone(),
+            #[cfg(feature = "clipboard")]
+            clipboard: self.clipboard.clone(),
+        }
+    }
+}
+
+impl InputState {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let required_lines = calculate_required_lines("", cols);
+        Self {
+            buffer: String::new(),
+            cols,
+            rows,
+            required_lines,
+            scroll_broadcast: None,
+            last_command_height: 0,
+            input_mode: InputMode::default(),
+            mask_char: DEFAULT_MASK_CHAR,
+            paste_mode: false,
+            tab_completer: None,
+            highlighter: None,
+            output_formatter: None,
+            on_change: None,
+            on_submit_async: None,
+            submit_error: None,
+            plugins: Vec::new(),
+            plugin_storage: HashMap::new(),
+            completion_popup: Vec::new(),
+            history: Vec::new(),
+            search: None,
+            history_browse: None,
+            cursor_byte_offset: 0,
+            selection_start: None,
+            overwrite_mode: false,
+            kill_ring: Vec::new(),
+            undo_stack: Vec::new(),
+            undo_recording: None,
+            active_animation: None,
+            spinner: None,
+            theme: Theme::default(),
+            auto_indent: false,
+            auto_pairs: DEFAULT_AUTO_PAIRS.to_vec(),
+            tab_mode: TabMode::default(),
+            status_mode: StatusMode::default(),
+            key_bindings: KeyBindings::default(),
+            function_keys: default_function_keys(),
+            max_lines: None,
+            max_lines_reached: None,
+            wrap_mode: WrapMode::default(),
+            wrap_indicator: None,
+            frame_padding: 0,
+            frame_style: FrameStyle::default(),
+            layout: Layout::default(),
+            multi_column_threshold: None,
+            frame_position: FramePosition::default(),
+            highlight_current_line: false,
+            last_click: None,
+            line_lengths: vec![0],
+            line_layout: LineLayout {
+                rows: vec![1],
+                total_rows: 1,
+                ..LineLayout::default()
+            },
+            has_rtl: false,
+            scroll_offset: 0,
+            prev_grid: None,
+            content: content::ContentBuffer::default(),
+            #[cfg(feature = "clipboard")]
+            clipboard: Arc::new(clipboard::SystemClipboard),
+        }
+    }
+
+    /// Create an input state with `text` already in the buffer and the
+    /// cursor at its end
+    pub fn with_initial_text(cols: usize, rows: usize, text: &str) -> Self {
+        let mut state = Self::new(cols, rows);
+        state.set_buffer(text);
+        state
+    }
+
+    /// Like [`with_initial_text`](Self::with_initial_text), with the cursor
+    /// at byte `cursor` of `text` instead of its end
+    ///
+    /// # Errors
+    ///
+    /// Returns `TermboxError::InvalidBoundary` if `cursor` is past the end of
+    /// `text` or inside a character.
+    pub fn with_initial_text_and_cursor(
+        cols: usize,
+        rows: usize,
+        text: &str,
+        cursor: usize,
+    ) -> Result<Self, TermboxError> {
+        if !text.is_char_boundary(cursor) {
+            return Err(TermboxError::InvalidBoundary);
+        }
+        let mut state = Self::with_initial_text(cols, rows, text);
+        state.cursor_byte_offset = cursor;
+        Ok(state)
+    }
+
+    /// Create an input state with the settings from `config`
+    pub fn with_config(cols: usize, rows: usize, config: Config) -> Self {
+        let mut state = Self::new(cols, rows);
+        state.mask_char = config.mask_char;
+        state.theme = config.theme;
+        state.auto_indent = config.auto_indent;
+        state.auto_pairs = config.auto_pairs;
+        state.tab_mode = config.tab_mode;
+        state.status_mode = config.status_mode;
+        state.key_bindings = config.key_bindings;
+        if config.submit_key != SubmitKey::AltEnter {
+            state.key_bindings.set_submit_key(config.submit_key);
+        }
+        state.function_keys = config.function_keys;
+        state.max_lines = config.max_lines;
+        state.wrap_mode = config.wrap_mode;
+        state.wrap_indicator = config.wrap_indicator;
+        state.frame_padding = config.frame_padding;
+        state.frame_style = config.frame_style;
+        state.layout = config.layout;
+        state.multi_column_threshold = config.multi_column_threshold;
+        state.frame_position = config.frame_position;
+        state.highlight_current_line = config.highlight_current_line;
+        #[cfg(feature = "timestamps")]
+        if config.show_timestamps {
+            state.set_output_formatter(Arc::new(format::TimestampFormatter));
+        }
+        state.set_mode(config.input_mode);
+        state
+    }
+
+    /// Replace the clipboard used for Ctrl+V
+    #[cfg(feature = "clipboard")]
+    pub fn set_clipboard(
+        &mut self,
+        clipboard: Arc<dyn clipboard::ClipboardProvider + Send + Sync>,
+    ) {
+        self.clipboard = clipboard;
+    }
+
+    /// Install the completer consulted when Tab is pressed
+    pub fn set_tab_completer(&mut self, completer: Arc<dyn TabCompletion + Send + Sync>) {
+        self.tab_completer = Some(completer);
+    }
+
+    /// Add a plugin that sees keys after the ones registered before it
+    pub fn register_plugin(&mut self, plugin: impl plugin::Plugin + Send + Sync + 'static) {
+        self.plugins.push(Arc::new(plugin));
+    }
+
+    /// The plugin state of type `T`, if any was stored
+    pub fn plugin_state<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.plugin_storage
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// The plugin state of type `T`, created with `T::default()` on first use
+    pub fn plugin_state_mut<T: Any + Send + Sync + Default>(&mut self) -> &mut T {
+        self.plugin_storage
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut()
+            .expect("plugin_storage is keyed by the TypeId of its value")
+    }
+
+    /// Install the callback told about every change of the buffer
+    pub fn set_on_change(&mut self, on_change: impl Fn(&str) + Send + Sync + 'static) {
+        self.on_change = Some(Arc::new(on_change));
+    }
+
+    /// Copy of the buffer to compare against after an edit, if anyone is
+    /// listening for changes
+    fn change_baseline(&self) -> Option<String> {
+        (self.on_change.is_some() && self.input_mode != InputMode::Password)
+            .then(|| self.buffer.clone())
+    }
+
+    /// Call `on_change` if the buffer differs from `before`
+    fn notify_change(&self, before: Option<String>) {
+        if let (Some(before), Some(on_change)) = (before, &self.on_change)
+            && before != self.buffer
+        {
+            on_change(&self.buffer);
+        }
+    }
+
+    /// Start recording the edits `splice` makes for [`undo`](Self::undo),
+    /// except in password mode, so the password is not copied. Returns
+    /// false if a recording is already under way, e.g. for a paste by a key
+    /// press, whose edits then belong to it.
+    fn begin_undo(&mut self) -> bool {
+        if self.undo_recording.is_some() || self.input_mode == InputMode::Password {
+            return false;
+        }
+        self.undo_recording = Some(UndoEntry {
+            edits: Vec::new(),
+            cursor: self.cursor_byte_offset,
+        });
+        true
+    }
+
+    /// End the recording `begin_undo` started, pushing any edits to
+    /// `undo_stack` and dropping the oldest entry past [`UNDO_LIMIT`]
+    fn end_undo(&mut self, started: bool) {
+        if !started {
+            return;
+        }
+        let Some(entry) = self.undo_recording.take() else {
+            return;
+        };
+        if entry.edits.is_empty() {
+            return;
+        }
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(entry);
+    }
+
+    /// Install the highlighter used to color the input
+    pub fn set_highlighter(
+        &mut self,
+        highlighter: Arc<dyn highlight::SyntaxHighlighter + Send + Sync>,
+    ) {
+        self.highlighter = Some(highlighter);
+    }
+
+    /// Install the formatter applied to text printed above the input box
+    pub fn set_output_formatter(
+        &mut self,
+        formatter: Arc<dyn format::OutputFormatter + Send + Sync>,
+    ) {
+        self.output_formatter = Some(formatter);
+    }
+
+    /// Switch the input mode, recalculating the frame size for the new display
+    pub fn set_mode(&mut self, mode: InputMode) {
+        if self.input_mode == InputMode::Password && mode != InputMode::Password {
+            // The password stays in the buffer, but not any bytes left over
+            // from editing it
+            let text = self.buffer.as_str().to_owned();
+            zeroize(&mut self.buffer);
+            self.buffer = text;
+        }
+        self.input_mode = mode;
+        self.update_required_lines();
+        self.scroll_to_cursor();
+    }
+
+    /// Returns the text as it should appear on screen.
+    ///
+    /// In `Password` mode every character except newlines is replaced by
+    /// `mask_char`, so the plaintext never reaches the drawing functions.
+    /// Otherwise tabs are expanded to spaces, see [`expand_tabs`].
+    pub fn display_text(&self) -> Cow<'_, str> {
+        let masked = self.mask_text(&self.buffer);
+        match self.visible_ranges(&masked) {
+            Some(ranges) => Cow::Owned(
+                masked
+                    .split('\n')
+                    .zip(ranges)
+                    .map(|(line, range)| &line[range])
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            None => masked,
+        }
+    }
+
+    /// In `WrapMode::None`, the part of each line of the masked text that
+    /// is visible, scrolled just far enough to show the cursor
+    fn visible_ranges(&self, masked: &str) -> Option<Vec<Range<usize>>> {
+        if self.wrap_mode != WrapMode::None {
+            return None;
+        }
+        let width = content_width(text_cols(self.frame_cols(), None, self.frame_padding));
+        let cursor = self.masked_offset(self.cursor_byte_offset.min(self.buffer.len()));
+        let column = masked[find_line_start(masked, cursor)..cursor].width();
+        let scroll = (column + 1).saturating_sub(width);
+        Some(
+            masked
+                .split('\n')
+                .map(|line| visible_columns(line, scroll, width))
+                .collect(),
+        )
+    }
+
+    /// Applies the current mode's masking or tab expansion to `text`, e.g. to
+    /// echo a submitted password without revealing it
+    pub fn mask_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.input_mode {
+            InputMode::Password => Cow::Owned(
+                text.chars()
+                    .map(|c| if c == '\n' { c } else { self.mask_char })
+                    .collect(),
+            ),
+            _ => expand_tabs(text, TAB_WIDTH),
+        }
+    }
+
+    /// Like [`handle_key`](Self::handle_key), but a key that would submit
+    /// the input first awaits `on_submit_async`. A rejected submission keeps
+    /// the buffer, sets `submit_error` and returns `KeyAction::Continue`.
+    pub async fn handle_key_async(
+        &mut self,
+        key_code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> KeyAction {
+        let submits = self.search.is_none()
+            && self.binding_submits(key_code, modifiers)
+            && self.completion_popup.is_empty()
+            && !self.buffer.is_empty();
+        if submits
+            && let Some(on_submit) = self.on_submit_async.clone()
+            && let SubmitAction::Reject(message) = on_submit(self.buffer.clone()).await
+        {
+            self.submit_error = Some(message);
+            return KeyAction::Continue;
+        }
+        self.handle_key(key_code, modifiers)
+    }
+
+    pub fn handle_key(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> KeyAction {
+        self.submit_error = None;
+        self.max_lines_reached = None;
+        let before = self.change_baseline();
+        let undo = self.begin_undo();
+        let action = self.apply_key(key_code, modifiers);
+        // A submission is in the history, not the undo stack
+        if matches!(action, KeyAction::Submit(_)) && undo {
+            self.undo_recording = None;
+        }
+        self.end_undo(undo);
+        self.scroll_to_cursor();
+        self.notify_change(before);
+        action
+    }
+
+    fn apply_key(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> KeyAction {
+        // `buffer` is public and may have been edited directly
+        self.clamp_cursor();
+
+        // Cloned so plugins can borrow the state mutably
+        for plugin in self.plugins.clone() {
+            if let Some(action) = plugin.on_key(key_code, modifiers, self) {
+                self.clamp_cursor();
+                return action;
+            }
+        }
+
+        // Any key other than another Tab dismisses the completion popup
+        if !self.completion_popup.is_empty() && key_code != KeyCode::Tab {
+            self.completion_popup.clear();
+            self.refresh_required_lines();
+            if key_code == KeyCode::Esc {
+                return KeyAction::Continue;
+            }
+        }
+
+        if self.search.is_some()
+            && let Some(action) = self.handle_search_key(key_code, modifiers)
+        {
+            return action;
+        }
+
+        let bound = self
+            .key_bindings
+            .get(key_code, modifiers)
+            .or_else(|| match key_code {
+                KeyCode::F(n) => self.function_keys.get(&n).copied(),
+                _ => None,
+            });
+        let is_motion = matches!(
+            bound,
+            Some(
+                InputAction::MoveLeft
+                    | InputAction::MoveRight
+                    | InputAction::MoveLineStart
+                    | InputAction::MoveLineEnd
+            )
+        );
+        let extends_selection = is_motion && modifiers.contains(KeyModifiers::SHIFT);
+        if extends_selection && self.selection_start.is_none() {
+            self.selection_start = Some(self.cursor_byte_offset);
+        }
+
+        let action = match (bound, key_code) {
+            (Some(action), _) => self.apply_action(action, key_code),
+            (None, KeyCode::Char(c)) if self.overwrite_mode => {
+                self.overwrite_char(c);
+                KeyAction::Continue
+            }
+            (None, KeyCode::Char(c)) => {
+                self.insert_char(c);
+                KeyAction::Continue
+            }
+            (None, _) => KeyAction::Continue,
+        };
+
+        // Any other key pressed without Ctrl ends the selection
+        if !extends_selection && !modifiers.contains(KeyModifiers::CONTROL) {
+            self.selection_start = None;
+        }
+
+        action
+    }
+
+    /// Perform a bound action for a press of `key_code`
+    fn apply_action(&mut self, action: InputAction, key_code: KeyCode) -> KeyAction {
+        match action {
+            InputAction::InsertNewline if self.input_mode == InputMode::MultiLine => {
+                self.insert_newline();
+            }
+            // Outside multi-line mode an Enter that would insert a newline submits
+            InputAction::InsertNewline if key_code != KeyCode::Enter => {}
+            InputAction::InsertNewline | InputAction::Submit => {
+                return KeyAction::Submit(self.get_submitted_text().unwrap_or_default());
+            }
+            InputAction::Exit => return KeyAction::Exit,
+            // With a selection, copy instead of exiting
+            InputAction::CopyOrExit => match self.selected_text() {
+                // A password is never copied anywhere
+                Some(_) if self.input_mode == InputMode::Password => {
+                    self.selection_start = None;
+                }
+                Some(text) => {
+                    // An unavailable clipboard still leaves the kill ring copy
+                    #[cfg(feature = "clipboard")]
+                    let _ = self.clipboard.set_text(text);
+                    self.kill_ring.push(text.to_string());
+                    self.selection_start = None;
+                }
+                None => return KeyAction::Exit,
+            },
+            InputAction::ClearScreen => return KeyAction::ClearScreen,
+            InputAction::Paste => {
+                // Pasted line breaks follow the same rules as a bracketed paste;
+                // an unavailable clipboard simply pastes nothing
+                #[cfg(feature = "clipboard")]
+                if let Ok(mut text) = self.clipboard.get_text() {
+                    self.handle_paste(&text);
+                    if self.input_mode == InputMode::Password {
+                        zeroize(&mut text);
+                    }
+                }
+            }
+            // Searching would show history entries while a password is typed
+            InputAction::SearchHistory if self.input_mode == InputMode::Password => {}
+            InputAction::SearchHistory => {
+                self.search = Some(SearchState {
+                    query: String::new(),
+                    match_index: self.history.len(),
+                    draft: self.buffer.clone(),
+                });
+            }
+            InputAction::DeleteBefore => {
+                if !self.delete_selection() && self.cursor_byte_offset > 0 {
+                    let start = self.prev_char_boundary(self.cursor_byte_offset);
+                    // Deleting an opener right before its closer removes both
+                    let end = if self.pair_around_cursor().is_some() {
+                        self.next_char_boundary(self.cursor_byte_offset)
+                    } else {
+                        self.cursor_byte_offset
+                    };
+                    self.splice(start..end, "");
+                    self.cursor_byte_offset = start;
+                }
+            }
+            InputAction::DeleteAfter => {
+                if !self.delete_selection() && self.cursor_byte_offset < self.buffer.len() {
+                    let at = self.cursor_byte_offset;
+                    let end = self.next_char_boundary(at);
+                    self.splice(at..end, "");
+                }
+            }
+            InputAction::MoveLeft => {
+                self.cursor_byte_offset = self.prev_char_boundary(self.cursor_byte_offset);
+            }
+            InputAction::MoveRight => {
+                self.cursor_byte_offset = self.next_char_boundary(self.cursor_byte_offset);
+            }
+            InputAction::MoveLineStart => {
+                self.cursor_byte_offset = find_line_start(&self.buffer, self.cursor_byte_offset);
+            }
+            InputAction::MoveLineEnd => {
+                self.cursor_byte_offset = find_line_end(&self.buffer, self.cursor_byte_offset);
+            }
+            InputAction::ClearLine => {
+                let start = find_line_start(&self.buffer, self.cursor_byte_offset);
+                let end = find_line_end(&self.buffer, self.cursor_byte_offset);
+                self.selection_start = None;
+                self.splice(start..end, "");
+                self.cursor_byte_offset = start;
+            }
+            // Browsing would copy the password into the draft it restores
+            InputAction::HistoryPrev | InputAction::HistoryNext
+                if self.input_mode == InputMode::Password => {}
+            InputAction::HistoryPrev => {
+                let index = self
+                    .history_browse
+                    .as_ref()
+                    .map_or(self.history.len(), |(index, _)| *index);
+                if index > 0 {
+                    if self.history_browse.is_none() {
+                        self.history_browse = Some((index, self.buffer.clone()));
+                    }
+                    self.show_history_entry(index - 1);
+                }
+            }
+            InputAction::HistoryNext => {
+                if let Some((index, draft)) = self.history_browse.clone() {
+                    if index + 1 < self.history.len() {
+                        self.show_history_entry(index + 1);
+                    } else {
+                        self.history_browse = None;
+                        self.selection_start = None;
+                        self.splice(0..self.buffer.len(), &draft);
+                        self.cursor_byte_offset = self.buffer.len();
+                    }
+                }
+            }
+            InputAction::Indent if self.tab_completer.is_some() => self.complete(),
+            InputAction::Indent => match self.tab_mode {
+                TabMode::Spaces(width) => self.insert_str(&" ".repeat(width)),
+                TabMode::Literal => self.insert_str("\t"),
+            },
+            InputAction::Dedent => self.dedent(),
+            InputAction::ToggleOverwrite => self.overwrite_mode = !self.overwrite_mode,
+            InputAction::ShowHelp => return KeyAction::ShowHelp,
+            InputAction::Suspend => return KeyAction::Suspend,
+            InputAction::ClearBuffer => {
+                self.selection_start = None;
+                self.splice(0..self.buffer.len(), "");
+                self.cursor_byte_offset = 0;
+            }
+            InputAction::Undo => {
+                self.undo_edit();
+            }
+        }
+        KeyAction::Continue
+    }
+
+    /// Load `history[index]` into the buffer with the cursor at its end
+    fn show_history_entry(&mut self, index: usize) {
+        if let Some((browsed, _)) = self.history_browse.as_mut() {
+            *browsed = index;
+        }
+        self.selection_start = None;
+        let entry = self.history[index].clone();
+        self.splice(0..self.buffer.len(), &entry);
+        self.cursor_byte_offset = self.buffer.len();
+    }
+
+    /// Replace the bytes of `buffer` in `range` with `replacement`.
+    ///
+    /// The cursor is only moved if it would be past the end of the buffer.
+    /// The edit is recorded for [`undo`](Self::undo).
+    ///
+    /// # Errors
+    ///
+    /// Returns `TermboxError::InvalidBoundary` and leaves the buffer unchanged
+    /// if `range` is out of bounds, reversed or does not start and end on
+    /// character boundaries.
+    pub fn replace_range(
+        &mut self,
+        range: Range<usize>,
+        replacement: &str,
+    ) -> Result<(), TermboxError> {
+        let valid = range.start <= range.end
+            && self.buffer.is_char_boundary(range.start)
+            && self.buffer.is_char_boundary(range.end);
+        if !valid {
+            return Err(TermboxError::InvalidBoundary);
+        }
+        let before = self.change_baseline();
+        let undo = self.begin_undo();
+        self.splice(range, replacement);
+        self.end_undo(undo);
+        self.scroll_to_cursor();
+        self.notify_change(before);
+        Ok(())
+    }
+
+    /// Remove the bytes of `buffer` in `range`, as `replace_range(range, "")`
+    /// does, so it can be undone, e.g. `delete_range(cursor..find_line_end(&buffer, cursor))` to
+    /// kill the rest of the line.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TermboxError::InvalidBoundary` as [`replace_range`](Self::replace_range) does.
+    pub fn delete_range(&mut self, range: Range<usize>) -> Result<(), TermboxError> {
+        self.replace_range(range, "")
+    }
+
+    /// Insert `text` at byte `offset` of `buffer`, like `replace_range`, so
+    /// it can be undone. The cursor is not moved.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TermboxError::InvalidBoundary` and leaves the buffer unchanged
+    /// if `offset` is past the end of the buffer or inside a character.
+    pub fn insert_at(&mut self, offset: usize, text: &str) -> Result<(), TermboxError> {
+        self.replace_range(offset..offset, text)
+    }
+
+    /// Restore the buffer and cursor from before the last edit made with a
+    /// key, a paste or [`replace_range`](Self::replace_range). Returns
+    /// whether there was an edit to undo.
+    pub fn undo(&mut self) -> bool {
+        let before = self.change_baseline();
+        let undone = self.undo_edit();
+        self.scroll_to_cursor();
+        self.notify_change(before);
+        undone
+    }
+
+    /// [`undo`](Self::undo) without telling `on_change`
+    fn undo_edit(&mut self) -> bool {
+        let Some(entry) = self.undo_stack.pop() else {
+            return false;
+        };
+        self.selection_start = None;
+        self.history_browse = None;
+        // Reverting is not an edit to record itself
+        let recording = self.undo_recording.take();
+        for edit in entry.edits.iter().rev() {
+            let range = edit.start..edit.start + edit.inserted;
+            // `buffer` may have been edited directly since
+            if self.buffer.get(range.clone()).is_none() {
+                break;
+            }
+            self.splice(range, &edit.removed);
+        }
+        self.undo_recording = recording;
+        self.cursor_byte_offset = entry.cursor;
+        self.clamp_cursor();
+        true
+    }
+
+    /// `replace_range` for ranges known to be valid: every edit of the
+    /// buffer goes through here
+    fn splice(&mut self, range: Range<usize>, replacement: &str) {
+        if let Some(entry) = &mut self.undo_recording
+            && !(range.is_empty() && replacement.is_empty())
+        {
+            entry.edits.push(BufferEdit {
+                start: range.start,
+                removed: self.buffer[range.clone()].to_string(),
+                inserted: replacement.len(),
+            });
+        }
+        if self.input_mode == InputMode::Password {
+            self.reserve_wiped(replacement.len().saturating_sub(range.len()));
+        }
+        if self.line_layout.stale {
+            self.buffer.replace_range(range, replacement);
+            self.clamp_cursor();
+            self.update_required_lines();
+            return;
+        }
+        self.fit_lines_to_width();
+
+        // Only the lines from the start of the range to the end of the
+        // replacement change; measure what they were before replacing them
+        let start = find_line_start(&self.buffer, range.start);
+        let first_line = self.line_index(start);
+        let mut replaced_lines = 0;
+        let (mut non_ascii, mut rtl) = (0, 0);
+        for line in self.buffer[start..find_line_end(&self.buffer, range.end)].split('\n') {
+            replaced_lines += 1;
+            non_ascii += usize::from(!self.one_column_line(line));
+            rtl += usize::from(contains_rtl(line));
+        }
+        self.buffer.replace_range(range.clone(), replacement);
+        self.clamp_cursor();
+
+        let end = find_line_end(&self.buffer, range.start + replacement.len());
+        let width = content_width(self.line_layout.cols);
+        let measured: Vec<_> = self.buffer[start..end]
+            .split('\n')
+            .map(|line| self.measure_line(line, width))
+            .collect();
+        let layout = &mut self.line_layout;
+        let replaced = first_line..first_line + replaced_lines;
+        let removed_rows: usize = layout
+            .rows
+            .splice(replaced.clone(), measured.iter().map(|line| line.rows))
+            .sum();
+        layout.total_rows =
+            layout.total_rows - removed_rows + measured.iter().map(|line| line.rows).sum::<usize>();
+        layout.non_ascii_lines = layout.non_ascii_lines - non_ascii
+            + measured.iter().filter(|line| !line.one_column).count();
+        layout.rtl_lines = layout.rtl_lines - rtl + measured.iter().filter(|line| line.rtl).count();
+        layout.hint = (start, first_line);
+        self.has_rtl = layout.rtl_lines > 0;
+        self.line_lengths
+            .splice(replaced, measured.iter().map(|line| line.width));
+        if !self.paste_mode {
+            self.required_lines = self.required_lines_from_line_lengths();
+        }
+    }
+
+    /// Index of the logical line starting at byte `start`, counting the
+    /// newlines from the line of the last edit rather than from the top
+    fn line_index(&self, start: usize) -> usize {
+        let (hint_start, hint) = self.line_layout.hint;
+        if start >= hint_start {
+            hint + self.buffer[hint_start..start].matches('\n').count()
+        } else {
+            hint - self.buffer[start..hint_start].matches('\n').count()
+        }
+    }
+
+    /// Whether every character of `line` is displayed in one column, so its
+    /// rows are its length divided by the width
+    fn one_column_line(&self, line: &str) -> bool {
+        match self.input_mode {
+            InputMode::Password => line.is_empty() || is_printable_ascii(self.mask_char),
+            _ => line.chars().all(|c| is_printable_ascii(c) || c == '\t'),
+        }
+    }
+
+    /// Measure `line`, which has no newlines, as displayed in a frame with
+    /// `width` columns of text
+    fn measure_line(&self, line: &str, width: usize) -> LineMeasure {
+        let shown = self.mask_text(line);
+        let one_column = self.one_column_line(line);
+        let (width, rows) = if one_column {
+            (shown.len(), shown.len().div_ceil(width).max(1))
+        } else {
+            (
+                display_width(&shown),
+                wrap_prompt_lines(&shown, width).len(),
+            )
+        };
+        LineMeasure {
+            width,
+            rows,
+            one_column,
+            rtl: contains_rtl(line),
+        }
+    }
+
+    /// Make room for `additional` more bytes in the buffer, wiping the old
+    /// allocation if it has to be replaced, so a growing password leaves no
+    /// copies in freed memory
+    fn reserve_wiped(&mut self, additional: usize) {
+        if self.buffer.capacity() - self.buffer.len() >= additional {
+            return;
+        }
+        let capacity = (self.buffer.len() + additional).max(2 * self.buffer.capacity());
+        let mut grown = String::with_capacity(capacity);
+        grown.push_str(&self.buffer);
+        zeroize(&mut self.buffer);
+        self.buffer = grown;
+    }
+
+    /// Insert `text` at the cursor, replacing the selection if there is one,
+    /// and move the cursor past it
+    fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        let at = self.cursor_byte_offset;
+        self.splice(at..at, text);
+        self.cursor_byte_offset = at + text.len();
+    }
+
+    /// Insert `c`, completing or skipping over brackets and quotes from
+    /// `auto_pairs`
+    fn insert_char(&mut self, c: char) {
+        let pairing = self.input_mode != InputMode::Password && self.selection_range().is_none();
+        let next = self.buffer[self.cursor_byte_offset..].chars().next();
+        let prev = self.buffer[..self.cursor_byte_offset].chars().next_back();
+
+        // Typing the closer that is already next just steps over it
+        if pairing && next == Some(c) && self.auto_pairs.iter().any(|&(_, close)| close == c) {
+            self.cursor_byte_offset += c.len_utf8();
+            return;
+        }
+
+        if let WrapMode::Hard(width) = self.wrap_mode
+            && width > 0
+            && self.input_mode == InputMode::MultiLine
+            && self.selection_range().is_none()
+            && self.current_line().chars().count() >= width
+            && self.newlines_allowed() != Some(0)
+        {
+            self.insert_str("\n");
+        }
+
+        let closer = self
+            .auto_pairs
+            .iter()
+            .find(|&&(open, _)| open == c)
+            .map(|&(_, close)| close)
+            // A quote right after a word is more likely an apostrophe
+            .filter(|&close| close != c || !prev.is_some_and(char::is_alphanumeric));
+        match closer {
+            Some(close) if pairing => {
+                self.insert_str(&format!("{}{}", c, close));
+                self.cursor_byte_offset -= close.len_utf8();
+            }
+            _ => self.insert_str(c.encode_utf8(&mut [0; 4])),
+        }
+    }
+
+    /// The `auto_pairs` entry whose opener is just before the cursor and whose
+    /// closer is just after it
+    fn pair_around_cursor(&self) -> Option<(char, char)> {
+        if self.input_mode == InputMode::Password {
+            return None;
+        }
+        let prev = self.buffer[..self.cursor_byte_offset].chars().next_back()?;
+        let next = self.buffer[self.cursor_byte_offset..].chars().next()?;
+        self.auto_pairs
+            .iter()
+            .copied()
+            .find(|&pair| pair == (prev, next))
+    }
+
+    /// Remove one level of indentation from the start of the cursor's line:
+    /// a leading tab, or up to a tab's worth of leading spaces
+    fn dedent(&mut self) {
+        let line_start = find_line_start(&self.buffer, self.cursor_byte_offset);
+        let width = match self.tab_mode {
+            TabMode::Spaces(width) => width,
+            TabMode::Literal => TAB_WIDTH,
+        };
+        let line = &self.buffer[line_start..];
+        let removed = if line.starts_with('\t') {
+            1
+        } else {
+            line.bytes().take(width).take_while(|&b| b == b' ').count()
+        };
+        if removed == 0 {
+            return;
+        }
+        let cursor = self.cursor_byte_offset;
+        self.splice(line_start..line_start + removed, "");
+        self.cursor_byte_offset = cursor.saturating_sub(removed).max(line_start);
+        self.selection_start = None;
+    }
+
+    /// Insert a newline, followed by the current line's indentation when
+    /// `auto_indent` is on
+    fn insert_newline(&mut self) {
+        if self.newlines_allowed() == Some(0) {
+            self.max_lines_reached = Some(tokio::time::Instant::now());
+            return;
+        }
+        let indent = if self.auto_indent {
+            leading_whitespace(&self.current_line()[..self.current_line_offset()])
+        } else {
+            ""
+        };
+        let text = format!("\n{}", indent);
+        self.insert_str(&text);
+    }
+
+    /// Replace the character at the cursor with `c` and move past it. At the
+    /// end of a line, or with a selection, `c` is inserted instead.
+    fn overwrite_char(&mut self, c: char) {
+        let at = self.cursor_byte_offset;
+        if self.selection_range().is_some() || self.buffer[at..].starts_with('\n') {
+            self.insert_str(c.encode_utf8(&mut [0; 4]));
+            return;
+        }
+        let end = self.next_char_boundary(at);
+        self.splice(at..end, c.encode_utf8(&mut [0; 4]));
+        self.cursor_byte_offset = at + c.len_utf8();
+    }
+
+    /// Remove the selected text, returning true if anything was selected
+    fn delete_selection(&mut self) -> bool {
+        let Some(range) = self.selection_range() else {
+            self.selection_start = None;
+            return false;
+        };
+        let start = range.start;
+        self.splice(range, "");
+        self.cursor_byte_offset = start;
+        self.selection_start = None;
+        true
+    }
+
+    /// The selected byte range of `buffer`, or `None` if nothing is selected
+    pub fn selection_range(&self) -> Option<Range<usize>> {
+        let start = self.selection_start?.min(self.buffer.len());
+        let cursor = self.cursor_byte_offset;
+        (start != cursor).then(|| start.min(cursor)..start.max(cursor))
+    }
+
+    /// The selected text, or `None` if nothing is selected
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection_range().map(|range| &self.buffer[range])
+    }
+
+    /// Pull the cursor back inside the buffer and onto a char boundary
+    fn clamp_cursor(&mut self) {
+        self.cursor_byte_offset = self.cursor_byte_offset.min(self.buffer.len());
+        while !self.buffer.is_char_boundary(self.cursor_byte_offset) {
+            self.cursor_byte_offset -= 1;
+        }
+    }
+
+    fn prev_char_boundary(&self, offset: usize) -> usize {
+        self.buffer[..offset]
+            .chars()
+            .next_back()
+            .map_or(0, |c| offset - c.len_utf8())
+    }
+
+    fn next_char_boundary(&self, offset: usize) -> usize {
+        self.buffer[offset..]
+            .chars()
+            .next()
+            .map_or(offset, |c| offset + c.len_utf8())
+    }
+
+    /// Map a byte offset in `buffer` to the same position in `display_text`
+    fn display_offset(&self, offset: usize) -> usize {
+        let masked_offset = self.masked_offset(offset);
+        let masked = self.mask_text(&self.buffer);
+        let Some(ranges) = self.visible_ranges(&masked) else {
+            return masked_offset;
+        };
+        let mut shown = 0;
+        let mut line_start = 0;
+        for (line, range) in masked.split('\n').zip(ranges) {
+            let line_end = line_start + line.len();
+            if masked_offset <= line_end {
+                return shown + (masked_offset - line_start).clamp(range.start, range.end)
+                    - range.start;
+            }
+            shown += range.len() + 1;
+            line_start = line_end + 1;
+        }
+        shown
+    }
+
+    /// Offset in `mask_text(&self.buffer)` of the buffer byte offset `offset`
+    fn masked_offset(&self, offset: usize) -> usize {
+        match self.input_mode {
+            InputMode::Password => self.buffer[..offset]
+                .chars()
+                .map(|c| {
+                    if c == '\n' {
+                        1
+                    } else {
+                        self.mask_char.len_utf8()
+                    }
+                })
+                .sum(),
+            _ => expand_tabs(&self.buffer[..offset], TAB_WIDTH).len(),
+        }
+    }
+
+    /// Terminal `(column, row)` where the cursor is drawn
+    pub fn cursor_position(&self) -> (usize, usize) {
+        let display = self.display_text();
+        let cursor = self.display_offset(self.cursor_byte_offset.min(self.buffer.len()));
+        let (col, row) = calculate_cursor_position(
+            &display[..cursor],
+            self.text_cols(),
+            self.frame_end_row(),
+            self.required_lines,
+        );
+        (
+            self.effective_layout().frame_left() + self.frame_padding + col,
+            row.saturating_sub(self.shown_scroll_offset())
+                .min(self.frame_end_row().saturating_sub(2)),
+        )
+    }
+
+    /// Move the cursor to the character clicked at terminal `(column, row)`.
+    ///
+    /// Clicks outside the rows of the text are ignored; clicks left of the
+    /// text or past the end of a line go to its start or end.
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the click was inside the text, i.e. the cursor moved.
+    pub fn handle_click(&mut self, column: usize, row: usize) -> bool {
+        let display = self.display_text();
+        let line_count = wrap_prompt_lines(&display, content_width(self.text_cols())).len();
+        let Some(visual_row) = self.visual_row_at(row).filter(|&r| r < line_count) else {
+            return false;
+        };
+        let visual_col = column.saturating_sub(self.text_left());
+        let offset = visual_pos_to_byte_offset(&display, self.text_cols(), visual_row, visual_col);
+        let offset = self.buffer_offset(self.unscrolled_offset(offset));
+        self.selection_start = None;
+        self.cursor_byte_offset = offset;
+        true
+    }
+
+    /// Handle a mouse event. A left click moves the cursor like
+    /// [`handle_click`](Self::handle_click), and a second click on the same
+    /// spot within [`DOUBLE_CLICK_INTERVAL`] selects the word there.
+    ///
+    /// Returns true if the input box needs to be redrawn.
+    pub fn handle_mouse(&mut self, event: MouseEvent) -> bool {
+        let MouseEventKind::Down(MouseButton::Left) = event.kind else {
+            return false;
+        };
+        let now = tokio::time::Instant::now();
+        let double_click = self.last_click.is_some_and(|(at, column, row)| {
+            (column, row) == (event.column, event.row)
+                && now.duration_since(at) < DOUBLE_CLICK_INTERVAL
+        });
+        // A third click starts over
+        self.last_click = (!double_click).then_some((now, event.column, event.row));
+        if double_click {
+            self.select_word_at(event.column as usize, event.row as usize)
+        } else {
+            self.handle_click(event.column as usize, event.row as usize)
+        }
+    }
+
+    /// Select the word drawn at terminal `(column, row)`, returning false
+    /// if there is none
+    fn select_word_at(&mut self, column: usize, row: usize) -> bool {
+        let display = self.display_text();
+        let Some(visual_row) = self.visual_row_at(row) else {
+            return false;
+        };
+        let visual_col = column.saturating_sub(self.text_left());
+        let Some((start, end)) =
+            find_word_at_visual_pos(&display, self.text_cols(), visual_col, visual_row)
+        else {
+            return false;
+        };
+        let start = self.buffer_offset(self.unscrolled_offset(start));
+        let end = self.buffer_offset(self.unscrolled_offset(end));
+        self.selection_start = Some(start);
+        self.cursor_byte_offset = end;
+        true
+    }
+
+    /// The wrapped row of text or popup drawn at terminal `row`, counting
+    /// the rows scrolled past, or `None` outside the rows between the borders
+    fn visual_row_at(&self, row: usize) -> Option<usize> {
+        row.checked_sub(self.frame_start_row() + 1)
+            .filter(|&r| r < self.viewport_rows())
+            .map(|r| r + self.shown_scroll_offset())
+    }
+
+    /// Offset in the masked text of `display_offset` in `display_text()`,
+    /// which differ when lines are scrolled sideways
+    fn unscrolled_offset(&self, display_offset: usize) -> usize {
+        let masked = self.mask_text(&self.buffer);
+        let Some(ranges) = self.visible_ranges(&masked) else {
+            return display_offset;
+        };
+        let mut shown = 0;
+        let mut line_start = 0;
+        for (line, range) in masked.split('\n').zip(ranges) {
+            if display_offset <= shown + range.len() {
+                return line_start + range.start + (display_offset - shown);
+            }
+            shown += range.len() + 1;
+            line_start += line.len() + 1;
+        }
+        masked.len()
+    }
+
+    /// Map a byte offset in `display_text` back to `buffer`, the inverse of
+    /// `display_offset`. Offsets inside an expanded tab go to the tab.
+    fn buffer_offset(&self, display_offset: usize) -> usize {
+        let mut shown = 0;
+        let mut column = 0;
+        for (i, c) in self.buffer.char_indices() {
+            let len = match (c, &self.input_mode) {
+                ('\n', _) => 1,
+                (_, InputMode::Password) => self.mask_char.len_utf8(),
+                ('\t', _) => TAB_WIDTH - column % TAB_WIDTH,
+                _ => c.len_utf8(),
+            };
+            column = match c {
+                '\n' => 0,
+                '\t' => column + TAB_WIDTH - column % TAB_WIDTH,
+                _ => column + 1,
+            };
+            if shown + len > display_offset {
+                return i;
+            }
+            shown += len;
+        }
+        self.buffer.len()
+    }
+
+    /// Replace the whole buffer with `text`, e.g. to pre-fill the input, and
+    /// move the cursor to its end. Edits made before cannot be undone.
+    pub fn set_buffer(&mut self, text: &str) {
+        let before = self.change_baseline();
+        self.undo_stack.clear();
+        self.selection_start = None;
+        self.splice(0..self.buffer.len(), text);
+        self.cursor_byte_offset = self.buffer.len();
+        self.scroll_to_cursor();
+        self.notify_change(before);
+    }
+
+    /// The logical lines of the buffer, split like [`str::lines`]: a trailing
+    /// newline does not start another line
+    pub fn lines(&self) -> std::str::Lines<'_> {
+        self.buffer.lines()
+    }
+
+    /// Number of [`lines`](Self::lines), at least 1 for an empty buffer
+    pub fn line_count(&self) -> usize {
+        self.buffer.lines().count().max(1)
+    }
+
+    /// Whether the buffer holds no text
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Number of Unicode scalar values in the buffer
+    pub fn char_count(&self) -> usize {
+        self.buffer.chars().count()
+    }
+
+    /// The logical line the cursor is on, without its `\n`
+    pub fn current_line(&self) -> &str {
+        let cursor = self.cursor_byte_offset.min(self.buffer.len());
+        &self.buffer[find_line_start(&self.buffer, cursor)..find_line_end(&self.buffer, cursor)]
+    }
+
+    /// Byte offset of the cursor from the start of
+    /// [`current_line`](Self::current_line)
+    pub fn current_line_offset(&self) -> usize {
+        let cursor = self.cursor_byte_offset.min(self.buffer.len());
+        cursor - find_line_start(&self.buffer, cursor)
+    }
+
+    /// The cursor's `(logical_line, column)`: the number of newlines before it
+    /// and its display column within that line, with tabs expanded
+    pub fn cursor_line_col(&self) -> (usize, usize) {
+        let before = &self.buffer[..self.cursor_byte_offset.min(self.buffer.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before[..line_start].matches('\n').count();
+        let column = display_width(&expand_tabs(&before[line_start..], TAB_WIDTH));
+        (line, column)
+    }
+
+    /// The cursor's `(visual_row, visual_col)` inside the frame, counting
+    /// wrapped rows, as drawn by [`cursor_position`](Self::cursor_position)
+    pub fn visual_cursor_pos(&self) -> (usize, usize) {
+        let (col, row) = self.cursor_position();
+        let first_row = self.frame_start_row() + 1;
+        (
+            row.saturating_sub(first_row),
+            col.saturating_sub(self.text_left()),
+        )
+    }
+
+    /// Index of the wrapped row the cursor is on, 0 for the first row of
+    /// text. The same row [`cursor_position`](Self::cursor_position) puts
+    /// the cursor on, but independent of where the frame is drawn, so it
+    /// can be compared with a scroll offset into the visual lines.
+    pub fn visual_cursor_row_index(&self) -> usize {
+        let display = self.display_text();
+        let cursor = self.display_offset(self.cursor_byte_offset.min(self.buffer.len()));
+        let text = expand_tabs(&display[..cursor], TAB_WIDTH);
+        wrap_prompt_lines(&text, content_width(self.text_cols()))
+            .len()
+            .saturating_sub(1)
+    }
+
+    /// The lines of `buffer` as wrapped in the frame of a `cols` wide
+    /// terminal, borrowed from the buffer.
+    ///
+    /// Tabs are not expanded here; wrap `display_text` the same way to get
+    /// exactly what is drawn for text containing tabs.
+    pub fn visual_lines_for(&self, cols: usize) -> Vec<&str> {
+        wrap_prompt_lines(&self.buffer, content_width(cols))
+    }
+
+    /// The layout the box is drawn in at the current width: side by side
+    /// past `multi_column_threshold`, `layout` otherwise
+    pub fn effective_layout(&self) -> Layout {
+        match self.multi_column_threshold {
+            Some(threshold) if self.cols > threshold => Layout::SideBySide {
+                split_col: self.cols / 2,
+            },
+            _ => self.layout,
+        }
+    }
+
+    /// Width of the frame in the current layout
+    fn frame_cols(&self) -> usize {
+        self.effective_layout().frame_cols(self.cols)
+    }
+
+    /// Terminal width the text is laid out for, see [`PromptOptions::wrap_indicator`]
+    /// and [`PromptOptions::frame_padding`]
+    fn text_cols(&self) -> usize {
+        text_cols(self.frame_cols(), self.wrap_indicator, self.frame_padding)
+    }
+
+    /// Terminal column the first character of each row of text is drawn at
+    fn text_left(&self) -> usize {
+        self.effective_layout().frame_left() + LEFT_FRAME_CHARS + self.frame_padding
+    }
+
+    /// Columns of text on each row of the frame at the current width and
+    /// padding
+    pub fn content_width(&self) -> usize {
+        content_width(self.text_cols())
+    }
+
+    /// Whether the frame leaves no column for text at the current width, in
+    /// which case nothing is drawn until a resize makes room
+    pub fn too_narrow(&self) -> bool {
+        self.frame_cols() <= FRAME_CHARS + 2 * self.frame_padding
+    }
+
+    /// [`visual_lines_for`](Self::visual_lines_for) at the current width
+    pub fn visual_lines(&self) -> Vec<&str> {
+        self.visual_lines_for(self.text_cols())
+    }
+
+    /// Row of the bottom of the scroll region, where output is printed and
+    /// animations reserve their rows; 0 when a frame at the bottom fills
+    /// the terminal
+    pub fn scroll_region_rows(&self) -> usize {
+        self.scroll_region().end.saturating_sub(1)
+    }
+
+    /// Rows output scrolls in: those above the frame, or below it with
+    /// `FramePosition::Top`. Rows below a centered frame are not used.
+    pub fn scroll_region(&self) -> Range<usize> {
+        match self.frame_position {
+            FramePosition::Top => self.required_lines.min(self.rows)..self.rows,
+            FramePosition::Bottom | FramePosition::Center => 0..self.frame_start_row(),
+        }
+    }
+
+    /// The [`scroll_region`](Self::scroll_region) as a
+    /// [`ContentArea`](content::ContentArea) at the width output is printed
+    /// at, reporting scrolling to [`setup_scroll_broadcast`](Self::setup_scroll_broadcast)
+    pub fn content_area(&self) -> content::ContentArea {
+        let region = self.scroll_region();
+        content::ContentArea {
+            rows: self.rows,
+            cols: self.effective_layout().output_cols(self.cols),
+            bottom_reserved: self.rows - region.end,
+            top_reserved: region.start,
+            scroll_broadcast: self.scroll_broadcast.clone(),
+        }
+    }
+
+    /// Row of the frame's top border
+    pub fn frame_start_row(&self) -> usize {
+        self.frame_start_row_for(self.required_lines)
+    }
+
+    /// Row the top border of a frame `required_lines` tall would be drawn at
+    pub fn frame_start_row_for(&self, required_lines: usize) -> usize {
+        let bottom = self.rows.saturating_sub(required_lines);
+        match self.frame_position {
+            FramePosition::Bottom => bottom,
+            FramePosition::Top => 0,
+            FramePosition::Center => (self.rows / 2)
+                .saturating_sub(required_lines / 2)
+                .min(bottom),
+        }
+    }
+
+    /// Row below the frame's bottom border, at most the terminal height for
+    /// a frame taller than the terminal. The drawing functions take this as
+    /// the terminal height, as they draw the frame against it.
+    pub fn frame_end_row(&self) -> usize {
+        match self.frame_position {
+            FramePosition::Bottom => self.rows,
+            FramePosition::Top | FramePosition::Center => {
+                (self.frame_start_row() + self.required_lines).min(self.rows)
+            }
+        }
+    }
+
+    /// Rows between the borders that fit on screen, fewer than the rows of
+    /// text and popup when the frame is taller than the terminal
+    fn viewport_rows(&self) -> usize {
+        (self.frame_end_row().saturating_sub(self.frame_start_row())).saturating_sub(2)
+    }
+
+    /// `scroll_offset` as drawn: at most the rows that do not fit
+    fn shown_scroll_offset(&self) -> usize {
+        let rows = self.required_lines.saturating_sub(2);
+        self.scroll_offset
+            .min(rows.saturating_sub(self.viewport_rows()))
+    }
+
+    /// Scroll a frame taller than the terminal just far enough to show the
+    /// cursor's row
+    fn scroll_to_cursor(&mut self) {
+        let viewport = self.viewport_rows();
+        if self.required_lines.saturating_sub(2) <= viewport {
+            self.scroll_offset = 0;
+            return;
+        }
+        let row = self.visual_cursor_row_index();
+        let lowest = (row + 1).saturating_sub(viewport.max(1));
+        self.scroll_offset = self.shown_scroll_offset().clamp(lowest, row);
+    }
+
+    /// Adopt a new terminal size, returning what must be redrawn
+    pub fn handle_resize(&mut self, new_cols: usize, new_rows: usize) -> ResizeAction {
+        if (new_cols, new_rows) == (self.cols, self.rows) {
+            return ResizeAction::NoChange;
+        }
+        let old_cols = self.cols;
+        let old_required_lines = self.required_lines;
+        self.cols = new_cols;
+        self.rows = new_rows;
+        self.prev_grid = None;
+        self.refresh_required_lines();
+        self.scroll_to_cursor();
+        if self.cols == old_cols && self.required_lines == old_required_lines {
+            ResizeAction::RegionOnly
+        } else {
+            ResizeAction::FullRedraw
+        }
+    }
+
+    /// Start a paste: characters are still inserted by `handle_key`, but the
+    /// frame size is not recalculated until `end_paste` is called
+    pub fn begin_paste(&mut self) {
+        self.paste_mode = true;
+    }
+
+    /// Finish a paste and recalculate the frame size once for all inserted text
+    pub fn end_paste(&mut self) {
+        self.paste_mode = false;
+        self.refresh_required_lines();
+    }
+
+    /// Insert a complete pasted string, such as crossterm's `Event::Paste`.
+    ///
+    /// Terminals deliver pasted line breaks as `\r` or `\r\n`; both become `\n`.
+    /// Outside `MultiLine` mode line breaks are dropped, like the newline keys.
+    /// In `Password` mode the normalized copy is wiped once inserted; `text`
+    /// itself is the caller's to wipe.
+    pub fn handle_paste(&mut self, text: &str) {
+        let before = self.change_baseline();
+        let undo = self.begin_undo();
+        self.begin_paste();
+        // Normalized within one allocation, so there is a single copy to wipe
+        let mut normalized = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\r' {
+                chars.next_if_eq(&'\n');
+                normalized.push('\n');
+            } else {
+                normalized.push(c);
+            }
+        }
+        if self.input_mode != InputMode::MultiLine {
+            normalized.retain(|c| c != '\n');
+        }
+        if let Some(mut allowed) = self.newlines_allowed() {
+            normalized.retain(|c| {
+                if c != '\n' {
+                    return true;
+                }
+                if allowed == 0 {
+                    self.max_lines_reached = Some(tokio::time::Instant::now());
+                    return false;
+                }
+                allowed -= 1;
+                true
+            });
+        }
+        self.clamp_cursor();
+        self.insert_str(&normalized);
+        if self.input_mode == InputMode::Password {
+            zeroize(&mut normalized);
+        }
+        self.end_paste();
+        self.end_undo(undo);
+        self.scroll_to_cursor();
+        self.notify_change(before);
+    }
+
+    /// Handle a key while a history search is active.
+    ///
+    /// Returns `None` when the key ended the search and should then be handled
+    /// as a normal key press.
+    fn handle_search_key(
+        &mut self,
+        key_code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> Option<KeyAction> {
+        let search = self.search.as_mut()?;
+        match key_code {
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                // Cycle to the next older match
+                let before = search.match_index;
+                let query = search.query.clone();
+                self.search_history(&query, before);
+            }
+            KeyCode::Char(c) if !modifiers.contains(KeyModifiers::CONTROL) => {
+                search.query.push(c);
+                // The current match may still match the longer query
+                let before = (search.match_index + 1).min(self.history.len());
+                let query = search.query.clone();
+                self.search_history(&query, before);
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                let query = search.query.clone();
+                self.search_history(&query, self.history.len());
+            }
+            KeyCode::Enter => {
+                // Keep the matched entry in the buffer for editing
+                self.search = None;
+            }
+            KeyCode::Esc => self.cancel_search(),
+            _ => {
+                self.cancel_search();
+                return None;
+            }
+        }
+        Some(KeyAction::Continue)
+    }
+
+    /// Find the newest history entry before index `before` containing `query`
+    /// and load it into the buffer; leaves everything unchanged if none matches
+    fn search_history(&mut self, query: &str, before: usize) {
+        let found = (0..before).rev().find(|&i| self.history[i].contains(query));
+        if let (Some(index), Some(search)) = (found, self.search.as_mut()) {
+            search.match_index = index;
+            self.selection_start = None;
+            let entry = self.history[index].clone();
+            self.splice(0..self.buffer.len(), &entry);
+            self.cursor_byte_offset = self.buffer.len();
+        }
+    }
+
+    /// Leave history search and restore the text typed before it started
+    fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.selection_start = None;
+            self.splice(0..self.buffer.len(), &search.draft);
+            self.cursor_byte_offset = self.buffer.len();
+        }
+    }
+
+    /// Measure the buffer. `visual_lines` counts the rows of `display_text`,
+    /// which differ from the buffer's in password mode.
+    pub fn text_metrics(&self) -> TextMetrics {
+        let mut metrics = TextMetrics::new(&self.buffer, self.text_cols());
+        if self.input_mode == InputMode::Password {
+            metrics.visual_lines = visual_lines(&self.display_text(), self.text_cols());
+        }
+        metrics
+    }
+
+    /// Words in the buffer; punctuation between words is not counted
+    pub fn word_count(&self) -> usize {
+        count_words(&self.buffer)
+    }
+
+    /// Status shown at the right of the bottom border, if any: `OVR` in
+    /// overwrite mode followed by the count chosen by `status_mode`, after
+    /// [`RTL_WARNING`] while the buffer has right-to-left text
+    pub fn status(&self) -> Option<String> {
+        let status = self.mode_status();
+        if !self.rtl_warning_visible() {
+            return status;
+        }
+        Some(match status {
+            Some(status) => format!("{RTL_WARNING} {status}"),
+            None => RTL_WARNING.to_string(),
+        })
+    }
+
+    /// Whether the right-to-left warning is shown; never in password mode,
+    /// where it would tell what kind of text was typed
+    fn rtl_warning_visible(&self) -> bool {
+        self.has_rtl && self.input_mode != InputMode::Password
+    }
+
+    /// The text `status_mode` asks for, empty for `StatusMode::None`
+    pub fn status_string(&self) -> String {
+        match &self.status_mode {
+            StatusMode::CharCount => plural(self.char_count(), "char"),
+            StatusMode::WordCount => plural(self.word_count(), "word"),
+            StatusMode::LineCount => plural(self.line_count(), "line"),
+            StatusMode::LineInfo => {
+                let (line, column) = self.cursor_line_col();
+                format!("{}:{}", line + 1, column + 1)
+            }
+            StatusMode::Custom(status) => status(self),
+            StatusMode::None => String::new(),
+        }
+    }
+
+    /// The `OVR` indicator and count part of [`status`](Self::status)
+    fn mode_status(&self) -> Option<String> {
+        let count = Some(self.status_string())
+            .filter(|count| !count.is_empty() && self.input_mode != InputMode::Password);
+        match (self.overwrite_mode, count) {
+            (true, Some(count)) => Some(format!("OVR {count}")),
+            (true, None) => Some("OVR".to_string()),
+            (false, count) => count,
+        }
+    }
+
+    /// How many more newlines `max_lines` allows, or `None` without a limit
+    fn newlines_allowed(&self) -> Option<usize> {
+        let max_lines = self.max_lines?;
+        let newlines = self.buffer.matches('\n').count();
+        Some(max_lines.saturating_sub(1).saturating_sub(newlines))
+    }
+
+    /// When the `[max]` indicator should disappear, if it is showing
+    pub fn max_lines_indicator_deadline(&self) -> Option<tokio::time::Instant> {
+        self.max_lines_reached
+            .map(|reached| reached + MAX_LINES_INDICATOR_DURATION)
+    }
+
+    /// Whether `[max]` is shown because a newline was just dropped
+    fn max_lines_indicator_visible(&self) -> bool {
+        self.max_lines_indicator_deadline()
+            .is_some_and(|deadline| tokio::time::Instant::now() < deadline)
+    }
+
+    /// Title shown in the top border, if any: the search prompt during a
+    /// history search, then a rejected submission's message, then `[max]`
+    /// right after hitting `max_lines`, otherwise `[hidden]` in password mode.
+    /// Starts with `⚠` while the buffer has right-to-left text.
+    pub fn title(&self) -> Option<String> {
+        let title = self.mode_title();
+        if !self.rtl_warning_visible() {
+            return title;
+        }
+        Some(match title {
+            Some(title) => format!("⚠ {title}"),
+            None => "⚠".to_string(),
+        })
+    }
+
+    /// [`title`](Self::title) without the right-to-left warning
+    fn mode_title(&self) -> Option<String> {
+        let Some(search) = self.search.as_ref() else {
+            if let Some(message) = &self.submit_error {
+                return Some(message.clone());
+            }
+            if self.max_lines_indicator_visible() {
+                return Some("[max]".to_string());
+            }
+            return (self.input_mode == InputMode::Password).then(|| "[hidden]".to_string());
+        };
+        let matched = self
+            .history
+            .get(search.match_index)
+            .is_some_and(|entry| entry.contains(&search.query));
+        let failed = if matched || search.query.is_empty() {
+            ""
+        } else {
+            "failed "
+        };
+        Some(format!("({}reverse-i-search): {}", failed, search.query))
+    }
+
+    /// Ask the tab completer for candidates and apply the result: a single
+    /// candidate replaces the current word, several open the popup
+    fn complete(&mut self) {
+        // Never hand a password to a completer
+        if self.input_mode == InputMode::Password {
+            return;
+        }
+        let Some(completer) = self.tab_completer.clone() else {
+            return;
+        };
+
+        let prefix = &self.buffer[..self.cursor_byte_offset];
+        let mut candidates = completer.complete(prefix);
+        if candidates.len() == 1 {
+            let word_start = prefix.trim_end_matches(|c: char| !c.is_whitespace()).len();
+            let candidate = candidates.remove(0);
+            self.splice(word_start..self.cursor_byte_offset, &candidate);
+            self.cursor_byte_offset = word_start + candidate.len();
+            self.completion_popup.clear();
+        } else {
+            self.completion_popup = candidates;
+        }
+        self.refresh_required_lines();
+    }
+
+    /// Decorations to draw alongside the display text
+    pub fn prompt_options(&self) -> PromptOptions<'_> {
+        let cursor = self.cursor_byte_offset.min(self.buffer.len());
+        PromptOptions {
+            popup: &self.completion_popup,
+            title: self.title(),
+            title_fg: (self.search.is_none()
+                && (self.submit_error.is_some() || self.max_lines_indicator_visible()))
+            .then_some(Color::Red),
+            status: self.status(),
+            cursor: Some(self.display_offset(cursor)),
+            selection: self
+                .selection_range()
+                .map(|range| self.display_offset(range.start)..self.display_offset(range.end)),
+            wrap_indicator: self.wrap_indicator,
+            frame_padding: self.frame_padding,
+            frame_style: self.frame_style,
+            layout: self.effective_layout(),
+            current_line: self.highlight_current_line.then(|| {
+                let display = self.display_text();
+                let cursor = self.display_offset(cursor).min(display.len());
+                display[..cursor].matches('\n').count()
+            }),
+            scroll_offset: self.shown_scroll_offset(),
+            theme: self.theme,
+            highlights: match &self.highlighter {
+                // Spans would reveal the shape of a password
+                Some(highlighter) if self.input_mode != InputMode::Password => highlighter
+                    .highlight(&self.buffer)
+                    .into_iter()
+                    .map(|(range, style)| {
+                        let range =
+                            range.start.min(self.buffer.len())..range.end.min(self.buffer.len());
+                        (
+                            self.display_offset(range.start)..self.display_offset(range.end),
+                            style,
+                        )
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    /// Measure every line again and resize the frame to fit them
+    fn update_required_lines(&mut self) {
+        let cols = self.text_cols();
+        let width = content_width(cols);
+        let measured: Vec<_> = self
+            .buffer
+            .split('\n')
+            .map(|line| self.measure_line(line, width))
+            .collect();
+        self.line_lengths = measured.iter().map(|line| line.width).collect();
+        let rows: Vec<_> = measured.iter().map(|line| line.rows).collect();
+        self.line_layout = LineLayout {
+            total_rows: rows.iter().sum(),
+            rows,
+            cols,
+            non_ascii_lines: measured.iter().filter(|line| !line.one_column).count(),
+            rtl_lines: measured.iter().filter(|line| line.rtl).count(),
+            hint: (0, 0),
+            stale: false,
+        };
+        self.has_rtl = self.line_layout.rtl_lines > 0;
+        self.refresh_required_lines();
+    }
+
+    /// Resize the frame for the measured lines, e.g. after the width or the
+    /// completion popup changed
+    fn refresh_required_lines(&mut self) {
+        if self.line_layout.stale {
+            self.update_required_lines();
+        } else if !self.paste_mode {
+            self.required_lines = self.required_lines_from_line_lengths();
+        }
+    }
+
+    /// Wrap the lines for the current width if they were wrapped for another:
+    /// by dividing `line_lengths` while every line is one-column ASCII, by
+    /// measuring them all again otherwise
+    fn fit_lines_to_width(&mut self) {
+        let cols = self.text_cols();
+        if self.line_layout.cols == cols {
+            return;
+        }
+        if self.line_layout.non_ascii_lines > 0 {
+            self.update_required_lines();
+            return;
+        }
+        let width = content_width(cols);
+        let layout = &mut self.line_layout;
+        layout.rows = self
+            .line_lengths
+            .iter()
+            .map(|&len| len.div_ceil(width).max(1))
+            .collect();
+        layout.total_rows = layout.rows.iter().sum();
+        layout.cols = cols;
+    }
+
+    /// Frame height for the measured lines at the current width
+    fn required_lines_from_line_lengths(&mut self) -> usize {
+        self.fit_lines_to_width();
+        let rows = match self.wrap_mode {
+            // Every line is cut to fit one row
+            WrapMode::None => self.line_lengths.len(),
+            _ => self.line_layout.total_rows,
+        };
+        rows + 2 + self.completion_popup.len()
+    }
+
+    /// Returns true if Enter with the given modifiers submits the input.
+    ///
+    /// Enter never submits during a history search, where it accepts the match.
+    pub fn is_submit_key(&self, modifiers: KeyModifiers) -> bool {
+        self.search.is_none() && self.binding_submits(KeyCode::Enter, modifiers)
+    }
+
+    /// Whether the binding of a key submits, ignoring history search
+    fn binding_submits(&self, key_code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match self.key_bindings.get(key_code, modifiers) {
+            Some(InputAction::Submit) => true,
+            Some(InputAction::InsertNewline) => {
+                key_code == KeyCode::Enter && self.input_mode != InputMode::MultiLine
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get_submitted_text(&mut self) -> Option<String> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        // A fresh allocation, so wiping a password from the buffer below
+        // also wipes bytes left over from edits past its length
+        let result = self.buffer.as_str().to_owned();
+        if self.input_mode != InputMode::Password && self.history.last() != Some(&result) {
+            self.history.push(result.clone());
+        }
+        self.clear_buffer();
+        // The next input starts a history of edits of its own
+        self.undo_stack.clear();
+        Some(result)
+    }
+
+    /// Empty the buffer without submitting it: the cursor moves to the
+    /// start, the selection is dropped and nothing is added to the history.
+    /// The old text can be brought back with [`undo`](Self::undo), except
+    /// in password mode, where it is wiped from memory.
+    pub fn clear(&mut self) {
+        let before = self.change_baseline();
+        let undo = self.begin_undo();
+        self.clear_buffer();
+        self.end_undo(undo);
+        self.notify_change(before);
+    }
+
+    /// Wipe the buffer with [`zeroize`] if it holds a password.
+    ///
+    /// Call before dropping a state that was used in `Password` mode;
+    /// `TermboxApp` does so when it is dropped. While in `Password` mode the
+    /// buffer is never left behind in memory it frees when it grows, and the
+    /// password is kept out of history, the kill ring and saved sessions.
+    pub fn wipe_password(&mut self) {
+        if self.input_mode == InputMode::Password {
+            self.clear_buffer();
+        }
+    }
+
+    /// [`clear`](Self::clear) without telling `on_change`, for callers that
+    /// do so themselves
+    fn clear_buffer(&mut self) {
+        if self.input_mode == InputMode::Password {
+            zeroize(&mut self.buffer);
+        } else {
+            // Through `splice`, so `clear` can be undone
+            self.splice(0..self.buffer.len(), "");
+        }
+        self.cursor_byte_offset = 0;
+        self.selection_start = None;
+        self.history_browse = None;
+        self.update_required_lines();
+        self.scroll_offset = 0;
+    }
+
+    /// The text being edited
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The text being edited, to change it in place. Such changes are not
+    /// recorded for undo or reported to `on_change`, and the next edit
+    /// measures every line again to size the frame.
+    pub fn buffer_mut(&mut self) -> &mut String {
+        self.line_layout.stale = true;
+        &mut self.buffer
+    }
+
+    /// Capture the buffer, cursor and history.
+    ///
+    /// In `Password` mode the buffer is left out, so a password never ends up
+    /// in a saved session.
+    pub fn snapshot(&self) -> InputStateSnapshot {
+        if self.input_mode == InputMode::Password {
+            return InputStateSnapshot {
+                history: self.history.clone(),
+                ..InputStateSnapshot::default()
+            };
+        }
+        InputStateSnapshot {
+            buffer: self.buffer.clone(),
+            cursor_byte_offset: self.cursor_byte_offset,
+            history: self.history.clone(),
+        }
+    }
+
+    /// Replace the buffer, cursor and history with those from `snap`.
+    ///
+    /// Any selection, search or completion popup is dropped, and a cursor
+    /// offset that does not fit the buffer is moved back inside it.
+    pub fn restore_snapshot(&mut self, snap: InputStateSnapshot) {
+        self.buffer = snap.buffer;
+        self.cursor_byte_offset = snap.cursor_byte_offset;
+        self.history = snap.history;
+        self.selection_start = None;
+        self.search = None;
+        self.completion_popup.clear();
+        self.clamp_cursor();
+        self.update_required_lines();
+        self.scroll_to_cursor();
+    }
+
+    /// Save the snapshot of this state to `path`; see
+    /// `InputStateSnapshot::save_to_file`
+    #[cfg(feature = "serde")]
+    pub fn save_to_file(&self, path: &std::path::Path) -> Result<(), TermboxError> {
+        self.snapshot().save_to_file(path)
+    }
+
+    /// Read a snapshot written by `save_to_file`
+    #[cfg(feature = "serde")]
+    pub fn load_from_file(path: &std::path::Path) -> Result<InputStateSnapshot, TermboxError> {
+        let json = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&json).map_err(std::io::Error::from)?)
+    }
+
+    /// Drop the handles of animations that have ended, so `active_animation`
+    /// and `spinner` only hold running ones. Returns the errors any of them
+    /// stopped on.
+    pub fn clear_finished_animations(&mut self) -> Vec<anyhow::Error> {
+        let mut errors = Vec::new();
+        for slot in [&mut self.active_animation, &mut self.spinner] {
+            if slot.as_ref().is_some_and(|handle| handle.is_finished()) {
+                errors.extend(slot.take().and_then(|handle| handle.take_error()));
+            }
+        }
+        errors
+    }
+
+    /// Subscribe to scroll events, creating the channel on first use. Each
+    /// receiver sees every event sent after it subscribed.
+    pub fn setup_scroll_broadcast(&mut self) -> broadcast::Receiver<ScrollEvent> {
+        self.scroll_broadcast
+            .get_or_insert_with(|| broadcast::channel(SCROLL_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Send `event` to every subscriber; does nothing before the first
+    /// subscription or once all receivers are gone
+    pub fn notify_scroll(&self, event: ScrollEvent) {
+        if let Some(broadcast_tx) = &self.scroll_broadcast {
+            let _ = broadcast_tx.send(event);
+        }
+    }
+}
+
+/// Replaces each `\t` in `text` with spaces up to the next multiple of
+/// `tab_width` columns, counted from the start of its line
+pub fn expand_tabs(text: &str, tab_width: usize) -> Cow<'_, str> {
+    if !text.contains('\t') {
+        return Cow::Borrowed(text);
+    }
+    let tab_width = tab_width.max(1);
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = tab_width - column % tab_width;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                expanded.push(c);
+                column = 0;
+            }
+            _ => {
+                expanded.push(c);
+                column += 1;
+            }
+        }
+    }
+    Cow::Owned(expanded)
+}
+
+/// Byte offset of the start of the logical line containing `byte_pos`: just
+/// past the last `\n` before it, or 0. A `byte_pos` on a `\n` belongs to the
+/// line that newline ends.
+pub fn find_line_start(text: &str, byte_pos: usize) -> usize {
+    text[..byte_pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Byte offset of the end of the logical line containing `byte_pos`: the
+/// first `\n` at or after it, or `text.len()`
+pub fn find_line_end(text: &str, byte_pos: usize) -> usize {
+    text[byte_pos..]
+        .find('\n')
+        .map_or(text.len(), |i| byte_pos + i)
+}
+
+/// Byte offset where readline's `backward-word` would move from `cursor`:
+/// back over any non-word characters, then to the start of the word before
+/// them. Word characters are Unicode letters and digits; everything else,
+/// including `\n`, separates words.
+pub fn find_word_boundary_left(text: &str, cursor: usize) -> usize {
+    text[..cursor]
+        .trim_end_matches(|c: char| !c.is_alphanumeric())
+        .trim_end_matches(char::is_alphanumeric)
+        .len()
+}
+
+/// Byte offset where readline's `forward-word` would move from `cursor`:
+/// forward over any non-word characters, then to the end of the word after
+/// them, as in [`find_word_boundary_left`]
+pub fn find_word_boundary_right(text: &str, cursor: usize) -> usize {
+    let rest = text[cursor..]
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .trim_start_matches(char::is_alphanumeric);
+    text.len() - rest.len()
+}
+
+/// The spaces and tabs at the start of `line`
+pub fn leading_whitespace(line: &str) -> &str {
+    let end = line
+        .find(|c: char| c != ' ' && c != '\t')
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+/// Overwrites every byte of `text`'s allocation with zeros, then empties it.
+///
+/// The writes are volatile and followed by a fence so the compiler cannot
+/// drop them as dead stores. Only this allocation is wiped; copies made
+/// earlier, e.g. by growing the string, are not. The capacity is kept.
+pub fn zeroize(text: &mut String) {
+    // SAFETY: only zero bytes are written, within the allocation, and the
+    // length is set to 0 afterwards, so the string stays valid UTF-8
+    unsafe {
+        let bytes = text.as_mut_vec();
+        let ptr = bytes.as_mut_ptr();
+        for i in 0..bytes.capacity() {
+            std::ptr::write_volatile(ptr.add(i), 0);
+        }
+        bytes.set_len(0);
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Calculates the number of terminal lines `text` takes when printed to the
+/// scroll region.
+///
+/// Each line of `text` takes at least one row and wraps every `cols`
+/// columns, with wide characters such as CJK ideographs taking two. A
+/// trailing newline adds an empty row.
+///
+/// # Arguments
+///
+/// * `text` - The output text, with `\n` line separators
+/// * `cols` - The terminal width in columns
+///
+/// # Returns
+///
+/// The number of rows the content scrolls up by once `text` is printed.
+pub fn output_line_count(text: &str, cols: usize) -> usize {
+    text.split('\n')
+        .map(|line| wrap_prompt_lines(line, cols.max(1)).len())
+        .sum()
+}
+
+/// Whether `c` is ASCII and takes one column; newlines count too, as they
+/// only split lines
+fn is_printable_ascii(c: char) -> bool {
+    c == ' ' || c == '\n' || c.is_ascii_graphic()
+}
+
+/// Number of terminal columns `text` takes up, counting wide characters such
+/// as CJK ideographs as two. Each character is measured on its own, the same
+/// way the input box wraps its lines.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(|c| c.width().unwrap_or(0)).sum()
 }
 
-/// State of the input application
-#[derive(Clone)]
-pub struct InputState {
-    pub buffer: String,
-    pub cols: usize,
-    pub rows: usize,
-    pub required_lines: usize,
-    pub scroll_broadcast: Option<broadcast::Sender<ScrollEvent>>,
-    pub last_command_height: usize,
+/// Byte offset in `text` of the character drawn at `visual_col` columns into
+/// the `visual_row`th line of the input box, counting wrapped lines.
+///
+/// `text` is the text as drawn, e.g. `InputState::display_text`. Columns past
+/// the end of a line give the end of that line and rows past the last line
+/// give the end of the text. A click on either half of a wide character
+/// gives the offset before it.
+pub fn visual_pos_to_byte_offset(
+    text: &str,
+    cols: usize,
+    visual_row: usize,
+    visual_col: usize,
+) -> usize {
+    let lines = wrap_prompt_lines(text, content_width(cols));
+    let Some(line) = lines.get(visual_row) else {
+        return text.len();
+    };
+    let line_start = line.as_ptr() as usize - text.as_ptr() as usize;
+    let mut col = 0;
+    for (offset, ch) in line.char_indices() {
+        col += ch.width().unwrap_or(0);
+        if visual_col < col {
+            return line_start + offset;
+        }
+    }
+    line_start + line.len()
 }
 
-impl InputState {
-    pub fn new(cols: usize, rows: usize) -> Self {
-        let required_lines = calculate_required_lines("", cols);
-        Self {
-            buffer: String::new(),
-            cols,
-            rows,
-            required_lines,
-            scroll_broadcast: None,
-            last_command_height: 0,
+/// Byte range `(start, end)` in `text` of the word drawn at `visual_col`
+/// columns into the `visual_row`th line of the input box, or `None` if no
+/// word is drawn there. Words are runs of letters, digits and underscores.
+pub fn find_word_at_visual_pos(
+    text: &str,
+    cols: usize,
+    visual_col: usize,
+    visual_row: usize,
+) -> Option<(usize, usize)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let offset = visual_pos_to_byte_offset(text, cols, visual_row, visual_col);
+    if !text[offset..].chars().next().is_some_and(is_word) {
+        return None;
+    }
+    let start = text[..offset].trim_end_matches(is_word).len();
+    let end = text[offset..]
+        .find(|c| !is_word(c))
+        .map_or(text.len(), |len| offset + len);
+    Some((start, end))
+}
+
+/// Byte range of `line` shown in `width` columns once the first `scroll`
+/// columns are scrolled out of view
+fn visible_columns(line: &str, scroll: usize, width: usize) -> Range<usize> {
+    let mut start = None;
+    let mut end = line.len();
+    let mut column = 0;
+    for (i, c) in line.char_indices() {
+        if column >= scroll {
+            start.get_or_insert(i);
+        }
+        column += c.width().unwrap_or(0);
+        if column > scroll + width {
+            end = i;
+            break;
         }
     }
+    start.unwrap_or(end).min(end)..end
+}
 
-    pub fn handle_key(&mut self, key_code: KeyCode, modifiers: KeyModifiers) -> KeyAction {
-        match key_code {
-            KeyCode::Esc => KeyAction::Exit,
+/// Columns available for text inside the frame. At least 1, so terminals
+/// narrower than the frame still get a result instead of an underflow.
+fn content_width(cols: usize) -> usize {
+    cols.saturating_sub(FRAME_CHARS).max(1)
+}
 
-            KeyCode::Char('c') | KeyCode::Char('d')
-                if modifiers.contains(KeyModifiers::CONTROL) =>
-            {
-                KeyAction::Exit
-            }
+/// Fails with `TermboxError::TerminalTooSmall` if `cols` leaves no room for
+/// text inside the frame with `frame_padding` columns on each side
+fn check_terminal_width(cols: usize, frame_padding: usize) -> Result<(), TermboxError> {
+    let min_cols = FRAME_CHARS + 2 * frame_padding + 1;
+    if cols < min_cols {
+        return Err(TermboxError::TerminalTooSmall {
+            min_cols,
+            actual_cols: cols,
+        });
+    }
+    Ok(())
+}
 
-            KeyCode::Enter if modifiers.contains(KeyModifiers::ALT) => {
-                self.buffer.push('\n');
-                self.update_required_lines();
-                KeyAction::Continue
-            }
+/// Size of a text in the units the input box cares about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextMetrics {
+    /// Lines separated by `\n`; empty text is one line
+    pub logical_lines: usize,
+    /// Rows the text takes in the frame after wrapping
+    pub visual_lines: usize,
+    pub char_count: usize,
+    pub byte_count: usize,
+    /// User-perceived characters, e.g. `"e\u{301}"` is one
+    pub grapheme_count: usize,
+    /// Words as counted by `InputState::word_count`
+    pub word_count: usize,
+}
 
-            KeyCode::Char('j') if modifiers.contains(KeyModifiers::CONTROL) => {
-                self.buffer.push('\n');
-                self.update_required_lines();
-                KeyAction::Continue
+impl TextMetrics {
+    /// Measure `text` as drawn in the frame of a `cols` wide terminal
+    pub fn new(text: &str, cols: usize) -> Self {
+        let mut metrics = Self {
+            logical_lines: 1,
+            visual_lines: visual_lines(text, cols),
+            word_count: count_words(text),
+            ..Self::default()
+        };
+        for grapheme in text.graphemes(true) {
+            metrics.grapheme_count += 1;
+            metrics.byte_count += grapheme.len();
+            for c in grapheme.chars() {
+                metrics.char_count += 1;
+                if c == '\n' {
+                    metrics.logical_lines += 1;
+                }
             }
+        }
+        metrics
+    }
+}
 
-            KeyCode::Enter => {
-                // Submit is handled separately in the main loop
-                KeyAction::Continue
-            }
+/// Rows `text` takes in the frame of a `cols` wide terminal after wrapping,
+/// with tabs expanded
+fn visual_lines(text: &str, cols: usize) -> usize {
+    wrap_prompt_lines(&expand_tabs(text, TAB_WIDTH), content_width(cols)).len()
+}
 
-            KeyCode::Backspace => {
-                self.buffer.pop();
-                self.update_required_lines();
-                KeyAction::Continue
-            }
+/// Count the word-boundary segments of `text` that contain a letter or digit,
+/// so `"don't"` is one word and a lone `","` none
+fn count_words(text: &str) -> usize {
+    text.split_word_bounds()
+        .filter(|segment| segment.chars().any(char::is_alphanumeric))
+        .count()
+}
 
-            KeyCode::Char(c) => {
-                self.buffer.push(c);
-                self.update_required_lines();
-                KeyAction::Continue
-            }
+/// Whether `text` has characters written right to left, such as Hebrew or
+/// Arabic letters
+fn contains_rtl(text: &str) -> bool {
+    !text.is_ascii() && BidiInfo::new(text, None).has_rtl()
+}
 
-            _ => KeyAction::Continue,
-        }
+/// `"1 word"`, `"2 words"`
+fn plural(count: usize, unit: &str) -> String {
+    match count {
+        1 => format!("1 {unit}"),
+        _ => format!("{count} {unit}s"),
     }
+}
 
-    pub fn handle_resize(&mut self, new_cols: usize, new_rows: usize) {
-        self.cols = new_cols;
-        self.rows = new_rows;
-        self.update_required_lines();
-    }
+/// A result of [`calculate_required_lines`] with the text and width it was
+/// worked out for, so the same call can be answered without wrapping again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrapCache {
+    /// Hash of the text, see [`WrapCache::hash_text`]
+    pub text_hash: u64,
+    pub cols: usize,
+    pub result: usize,
+}
 
-    fn update_required_lines(&mut self) {
-        self.required_lines = calculate_required_lines(&self.buffer, self.cols);
+impl WrapCache {
+    /// Wrap `text` for a `cols` wide terminal and remember the result
+    pub fn new(text: &str, cols: usize) -> Self {
+        Self {
+            text_hash: Self::hash_text(text),
+            cols,
+            result: calculate_required_lines(text, cols),
+        }
     }
 
-    pub fn get_submitted_text(&mut self) -> Option<String> {
-        if self.buffer.is_empty() {
-            None
-        } else {
-            let result = self.buffer.clone();
-            self.buffer.clear();
-            self.update_required_lines();
-            Some(result)
-        }
+    /// The cached result if it is for `text` at `cols`
+    pub fn get(&self, text: &str, cols: usize) -> Option<usize> {
+        (self.cols == cols && self.text_hash == Self::hash_text(text)).then_some(self.result)
     }
 
-    pub fn setup_scroll_broadcast(&mut self) -> broadcast::Receiver<ScrollEvent> {
-        if self.scroll_broadcast.is_none() {
-            let (tx, _) = broadcast::channel(100);
-            self.scroll_broadcast = Some(tx);
-        }
-        self.scroll_broadcast.as_ref().unwrap().subscribe()
+    /// A fast, non-cryptographic hash of `text`, the same for the whole run
+    pub fn hash_text(text: &str) -> u64 {
+        ahash::RandomState::with_seeds(0, 0, 0, 0).hash_one(text)
     }
 }
 
@@ -139,23 +3014,8 @@ impl InputState {
 ///
 /// The total number of lines needed for the input box frame and content.
 pub fn calculate_required_lines(text: &str, cols: usize) -> usize {
-    if text.is_empty() {
-        return 3; // minimum: top border, input line, bottom border
-    }
-    let inner_width = cols - FRAME_CHARS;
-
-    // Split text by newlines and calculate wrapped lines for each segment
-    let mut total_lines = 0;
-    for line in text.split('\n') {
-        if line.is_empty() {
-            total_lines += 1; // Empty lines still take up space
-        } else {
-            let wrapped_lines = (line.len() + inner_width - 1) / inner_width;
-            total_lines += wrapped_lines.max(1);
-        }
-    }
-
-    total_lines + 2 // add top and bottom borders
+    // Empty text still has one input line, so the minimum is 3
+    visual_lines(text, cols) + 2 // add top and bottom borders
 }
 
 /// Calculates the exact cursor position for the current text input.
@@ -180,187 +3040,784 @@ pub fn calculate_cursor_position(
     rows: usize,
     required_lines: usize,
 ) -> (usize, usize) {
-    let inner_width = cols - FRAME_CHARS;
+    let inner_width = content_width(cols);
+    let text = expand_tabs(text, TAB_WIDTH);
 
     // Split text into display lines, same as draw_prompt_line
-    let mut lines = Vec::new();
-
-    for text_line in text.split('\n') {
-        if text_line.is_empty() {
-            lines.push(""); // Empty lines from newlines
-        } else {
-            // Handle wrapping for this line segment
-            let mut current_pos = 0;
-            while current_pos < text_line.len() {
-                let end_pos = (current_pos + inner_width).min(text_line.len());
-                lines.push(&text_line[current_pos..end_pos]);
-                current_pos = end_pos;
-            }
-        }
-    }
+    let lines = wrap_prompt_lines(&text, inner_width);
 
     // Cursor is at the end of the last line
     let last_line = lines.last().unwrap_or(&"");
-    let cursor_row = rows - required_lines + 1 + lines.len() - 1;
-    let cursor_col = 4 + last_line.len(); // "│ > " = 4 chars + length of last line
+    // Below the top border, on the last of the lines
+    let cursor_row = rows
+        .saturating_sub(required_lines)
+        .saturating_add(lines.len());
+    let cursor_col = 4 + display_width(last_line); // "│ > " = 4 chars + width of last line
 
     (cursor_col, cursor_row)
 }
 
-/// Renders the input prompt and text content as strings for testing
-pub fn render_text_lines(text: &str, cols: usize) -> Vec<String> {
-    let content_width = cols - FRAME_CHARS;
-    let mut lines = Vec::new();
+/// One row of the frame drawn by [`render_text_lines`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedLine {
+    /// The row as drawn, borders included
+    pub content: String,
+    /// Index of the logical line, split at `\n`, shown on this row. The top
+    /// and bottom borders count as part of the first and last logical line.
+    pub source_logical_line: usize,
+    /// Which wrapped row of its logical line this is, 0 for the first row and
+    /// for the borders
+    pub visual_line_in_source: usize,
+    /// Whether the row continues a logical line that did not fit the width
+    pub is_continuation: bool,
+}
 
-    // Split text into lines, handling both newlines and wrapping
-    let mut display_lines = Vec::new();
+/// The `content` of each line, for callers that only need the text
+pub fn to_strings(lines: &[RenderedLine]) -> Vec<String> {
+    lines.iter().map(|line| line.content.clone()).collect()
+}
 
-    for text_line in text.split('\n') {
-        if text_line.is_empty() {
-            display_lines.push(""); // Empty lines from newlines
-        } else {
-            // Handle wrapping for this line segment
-            let mut current_pos = 0;
-            while current_pos < text_line.len() {
-                let end_pos = (current_pos + content_width).min(text_line.len());
-                display_lines.push(&text_line[current_pos..end_pos]);
-                current_pos = end_pos;
-            }
-        }
-    }
+/// Renders the input prompt and text content as lines for testing, from the
+/// top border to the bottom border
+pub fn render_text_lines(text: &str, cols: usize) -> Vec<RenderedLine> {
+    render_padded_text_lines(text, cols, 0)
+}
+
+/// [`render_text_lines`] with `frame_padding` blank columns inside each side
+/// of the frame, see [`PromptOptions::frame_padding`]
+pub fn render_padded_text_lines(
+    text: &str,
+    cols: usize,
+    frame_padding: usize,
+) -> Vec<RenderedLine> {
+    let content_width = content_width(text_cols(cols, None, frame_padding));
+    let pad = " ".repeat(frame_padding);
+    let text = expand_tabs(text, TAB_WIDTH);
+    let mut lines = Vec::new();
+    let border = |content: String, source_logical_line| RenderedLine {
+        content,
+        source_logical_line,
+        visual_line_in_source: 0,
+        is_continuation: false,
+    };
 
     // Create the visual representation
-    let horiz = "─".repeat(cols - 2);
-    lines.push(format!("╭{}╮", horiz)); // Top border
+    let horiz = "─".repeat(cols.saturating_sub(2));
+    lines.push(border(format!("╭{}╮", horiz), 0)); // Top border
 
-    // Add content lines
-    for (i, line) in display_lines.iter().enumerate() {
+    // Add content lines, split at newlines and wrapped to the width
+    let mut last_logical_line = 0;
+    for (i, (source, visual, line)) in wrap_logical_lines(&text, content_width).enumerate() {
         let prefix = if i == 0 { "> " } else { "  " }; // prompt on first line only
-        let padding = " ".repeat(content_width.saturating_sub(line.len()));
-        lines.push(format!("│ {}{}{}│", prefix, line, padding));
+        let padding = " ".repeat(content_width.saturating_sub(display_width(line)));
+        lines.push(RenderedLine {
+            content: format!("│{pad} {prefix}{line}{padding}{pad}│"),
+            source_logical_line: source,
+            visual_line_in_source: visual,
+            is_continuation: visual > 0,
+        });
+        last_logical_line = source;
     }
 
-    lines.push(format!("╰{}╯", horiz)); // Bottom border
+    lines.push(border(format!("╰{}╯", horiz), last_logical_line)); // Bottom border
     lines
 }
 
 /// Draws the border frame around the input box to a buffer for testing
 pub fn draw_frame_to_buffer<W: Write>(
     out: &mut W,
-    (cols, rows): (usize, usize),
+    dimensions: (usize, usize),
+    required_lines: usize,
+) -> Result<(), TermboxError> {
+    draw_themed_frame_to_buffer(out, dimensions, required_lines, &Theme::default())
+}
+
+/// Draws the border frame around the input box in the border colors of `theme`
+pub fn draw_themed_frame_to_buffer<W: Write>(
+    out: &mut W,
+    dimensions: (usize, usize),
+    required_lines: usize,
+    theme: &Theme,
+) -> Result<(), TermboxError> {
+    draw_layout_frame_to_buffer(out, dimensions, required_lines, theme, Layout::Single)
+}
+
+/// Draws the border frame in the columns `layout` gives the input box. In
+/// `Layout::SideBySide` the separator is drawn down the whole terminal.
+pub fn draw_layout_frame_to_buffer<W: Write>(
+    out: &mut W,
+    dimensions: (usize, usize),
+    required_lines: usize,
+    theme: &Theme,
+    layout: Layout,
+) -> Result<(), TermboxError> {
+    draw_styled_frame_to_buffer(
+        out,
+        dimensions,
+        required_lines,
+        theme,
+        (layout, FrameStyle::default()),
+    )
+}
+
+/// [`draw_layout_frame_to_buffer`] in `frame_style`. `FrameStyle::Minimal`
+/// draws only the sides, leaving the top and bottom rows to the prompt.
+pub fn draw_styled_frame_to_buffer<W: Write>(
+    out: &mut W,
+    (term_cols, rows): (usize, usize),
     required_lines: usize,
-) -> anyhow::Result<()> {
-    let horiz = "─".repeat(cols - 2);
+    theme: &Theme,
+    (layout, frame_style): (Layout, FrameStyle),
+) -> Result<(), TermboxError> {
+    let left = layout.frame_left();
+    let cols = layout.frame_cols(term_cols);
+    check_terminal_width(cols, 0)?;
+    let horiz = "─".repeat(cols.saturating_sub(2));
     let clear_line = " ".repeat(cols);
-    let frame_start = rows - required_lines;
+    let frame_start = frame_top(rows, required_lines);
+    let bottom_row = rows.saturating_sub(1);
 
-    // Clear only lines that won't interfere with scroll region content
-    let scroll_region_bottom = rows - required_lines - 1;
+    // Clear only lines that won't interfere with scroll region content. With
+    // the frame filling the whole terminal there are no rows above it.
+    let scroll_region_bottom = frame_start.checked_sub(1);
     for i in 1..=2 {
-        let clear_row = frame_start - i;
-        if clear_row > scroll_region_bottom {
-            queue!(out, MoveTo(0, clear_row as u16), Print(&clear_line))?;
+        let Some(clear_row) = frame_start.checked_sub(i) else {
+            break;
+        };
+        if scroll_region_bottom.is_some_and(|bottom| clear_row > bottom) {
+            queue!(
+                out,
+                MoveTo(left as u16, clear_row as u16),
+                Print(&clear_line)
+            )?;
         }
     }
 
-    queue!(
-        out,
-        // draw top border
-        MoveTo(0, frame_start as u16),
-        Print(format!("╭{}╮", horiz))
-    )?;
+    let (fg, bg) = (theme.border_fg, theme.border_bg);
+
+    if let Layout::SideBySide { split_col } = layout {
+        for row in 0..rows {
+            queue!(out, MoveTo(split_col as u16, row as u16))?;
+            print_colored(out, "│", fg, bg)?;
+        }
+    }
+
+    // draw top border
+    if frame_style == FrameStyle::Rounded {
+        queue!(out, MoveTo(left as u16, frame_start as u16))?;
+        print_colored(out, &format!("╭{}╮", horiz), fg, bg)?;
+    }
 
     // draw middle lines (input area) - only clear and draw the borders, not the content
-    for i in 1..required_lines - 1 {
-        queue!(
-            out,
-            MoveTo(0, (frame_start + i) as u16),
-            Print("│"),
-            MoveTo((cols - 1) as u16, (frame_start + i) as u16),
-            Print("│")
-        )?;
+    for row in frame_start + 1..bottom_row {
+        queue!(out, MoveTo(left as u16, row as u16))?;
+        print_colored(out, "│", fg, bg)?;
+        queue!(out, MoveTo((left + cols - 1) as u16, row as u16))?;
+        print_colored(out, "│", fg, bg)?;
     }
 
     // draw bottom border
-    queue!(
-        out,
-        MoveTo(0, (rows - 1) as u16),
-        Print(format!("╰{}╯", horiz))
-    )?;
+    if frame_style == FrameStyle::Rounded {
+        queue!(out, MoveTo(left as u16, bottom_row as u16))?;
+        print_colored(out, &format!("╰{}╯", horiz), fg, bg)?;
+    }
 
     out.flush()?;
     Ok(())
 }
 
+/// Optional elements drawn inside the frame besides the input text
+#[derive(Debug, Default, Clone)]
+pub struct PromptOptions<'a> {
+    /// Completion candidates listed below the text, one per row.
+    /// `required_lines` must include a row for each of them.
+    pub popup: &'a [String],
+    /// Text embedded in the top border, truncated to fit
+    pub title: Option<String>,
+    /// Color of the title; `None` draws it in the border colors
+    pub title_fg: Option<Color>,
+    /// Text embedded at the right of the bottom border, truncated to fit
+    pub status: Option<String>,
+    /// Byte offset of the cursor in the text; `None` places it at the end
+    pub cursor: Option<usize>,
+    /// Byte range of the text to highlight with `theme.selection_bg`
+    pub selection: Option<Range<usize>>,
+    /// Byte ranges of the text drawn in a syntax highlighting style
+    pub highlights: Vec<(Range<usize>, highlight::Style)>,
+    /// Rows of text and popup scrolled past at the top of a frame taller
+    /// than the terminal. A scrollbar is drawn over the right border while
+    /// they do not all fit.
+    pub scroll_offset: usize,
+    /// Drawn in the last column of each row that a wrapped line continues
+    /// on. Text is laid out one column narrower to make room for it.
+    pub wrap_indicator: Option<char>,
+    /// Blank columns inside each side of the frame, around the prompt and
+    /// text. Text and popup are laid out twice as many columns narrower.
+    pub frame_padding: usize,
+    /// Whether the top and bottom borders are drawn. Their rows are part of
+    /// `required_lines` either way.
+    pub frame_style: FrameStyle,
+    /// Columns of the terminal the frame is drawn in
+    pub layout: Layout,
+    /// Logical line of the text whose rows get `theme.current_line_bg`
+    pub current_line: Option<usize>,
+    pub theme: Theme,
+}
+
+/// The width to lay out text for in a `cols` wide terminal: one column less
+/// when a wrap indicator takes the last column inside the frame, and the
+/// frame padding on both sides less
+fn text_cols(cols: usize, wrap_indicator: Option<char>, frame_padding: usize) -> usize {
+    cols.saturating_sub(usize::from(wrap_indicator.is_some()))
+        .saturating_sub(2 * frame_padding)
+}
+
+/// What goes in the wrap indicator column of row `i` of `lines`, as given
+/// by [`wrap_logical_lines`]: the indicator if the next row continues the
+/// same logical line, a blank if not, and nothing without an indicator
+fn wrap_indicator_cell(
+    lines: &[(usize, usize, &str)],
+    i: usize,
+    wrap_indicator: Option<char>,
+) -> String {
+    let continued = lines.get(i + 1).is_some_and(|&(_, visual, _)| visual > 0);
+    match wrap_indicator {
+        Some(indicator) if continued => indicator.to_string(),
+        Some(_) => " ".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Builds the top border, embedding `title` after the left corner if given
+fn top_border(cols: usize, title: Option<&str>, style: FrameStyle) -> String {
+    let inner = cols.saturating_sub(2);
+    let (left, line, right) = style.top_chars();
+    let line = |n| line.to_string().repeat(n);
+    match fitted_title(cols, title) {
+        Some(title) => {
+            let used = title.chars().count() + 3;
+            format!("{left}{} {} {}{right}", line(1), title, line(inner - used))
+        }
+        None => format!("{left}{}{right}", line(inner)),
+    }
+}
+
+/// Column where [`top_border`] starts the title
+const TITLE_COLUMN: usize = 3;
+
+/// `title` truncated to fit in the top border, or `None` if there is no room
+fn fitted_title(cols: usize, title: Option<&str>) -> Option<String> {
+    let inner = cols.saturating_sub(2);
+    let title = title.filter(|_| inner > 4)?;
+    Some(title.chars().take(inner - 4).collect())
+}
+
+/// Builds the bottom border, embedding `status` before the right corner if given
+fn bottom_border(cols: usize, status: Option<&str>, style: FrameStyle) -> String {
+    let inner = cols.saturating_sub(2);
+    let (left, line, right) = style.bottom_chars();
+    let line = |n| line.to_string().repeat(n);
+    match status {
+        Some(status) if inner > 4 => {
+            let status: String = status.chars().take(inner - 4).collect();
+            let used = status.chars().count() + 3;
+            format!("{left}{} {} {}{right}", line(inner - used), status, line(1))
+        }
+        _ => format!("{left}{}{right}", line(inner)),
+    }
+}
+
+/// First row of a frame `required_lines` tall against the bottom of a
+/// terminal `rows` tall, or the top row if the frame is taller than that
+fn frame_top(rows: usize, required_lines: usize) -> usize {
+    rows.saturating_sub(required_lines)
+}
+
+/// Rows shown of the `total` rows of text and popup in a frame
+/// `required_lines` tall at the bottom of a terminal `rows` tall, as
+/// `(first, count)`: `scroll_offset` kept from scrolling past the last row,
+/// and the rows between the borders
+fn visible_rows(
+    total: usize,
+    rows: usize,
+    required_lines: usize,
+    scroll_offset: usize,
+) -> (usize, usize) {
+    let viewport = rows.min(required_lines).saturating_sub(2);
+    (scroll_offset.min(total.saturating_sub(viewport)), viewport)
+}
+
+/// Right border of row `i` of the frame's viewport: the scrollbar thumb
+/// where it covers the row if the `total` rows do not all fit, `│` otherwise
+fn right_border(i: usize, total: usize, (first, viewport): (usize, usize)) -> &'static str {
+    let (start, height) = ui::compute_scrollbar(total, viewport, first);
+    if total > viewport && (start..start + height).contains(&i) {
+        ui::SCROLLBAR_THUMB
+    } else {
+        "│"
+    }
+}
+
 /// Draws the input prompt and text content within the frame to a buffer for testing
 pub fn draw_prompt_line_to_buffer<W: Write>(
     out: &mut W,
     buf: &str,
-    (cols, rows): (usize, usize),
+    dimensions: (usize, usize),
     required_lines: usize,
-) -> anyhow::Result<()> {
-    let content_width = cols - FRAME_CHARS; // "│ > " + content + "│"
-    let frame_start = rows - required_lines;
+) -> Result<(), TermboxError> {
+    draw_prompt_to_buffer(
+        out,
+        buf,
+        dimensions,
+        required_lines,
+        &PromptOptions::default(),
+    )
+}
+
+/// Draws the input prompt, text content and any extra elements from `options`
+/// within the frame
+pub fn draw_prompt_to_buffer<W: Write>(
+    out: &mut W,
+    buf: &str,
+    (term_cols, rows): (usize, usize),
+    required_lines: usize,
+    options: &PromptOptions,
+) -> Result<(), TermboxError> {
+    let left = options.layout.frame_left();
+    let cols = options.layout.frame_cols(term_cols);
+    check_terminal_width(cols, options.frame_padding)?;
+    // "│ > " + content + "│", with the wrap indicator taking a column of it
+    // from the text but not from the popup, and the padding from both
+    let pad = " ".repeat(options.frame_padding);
+    let popup_width = content_width(text_cols(cols, None, options.frame_padding));
+    let content_width = content_width(text_cols(
+        cols,
+        options.wrap_indicator,
+        options.frame_padding,
+    ));
+    let frame_start = frame_top(rows, required_lines);
+    let bottom_row = rows.saturating_sub(1);
     let clear_line = " ".repeat(cols);
 
     // Clear and redraw the entire frame area to ensure no artifacts
     for row in frame_start..rows {
-        queue!(out, MoveTo(0, row as u16), Print(&clear_line))?;
+        queue!(out, MoveTo(left as u16, row as u16), Print(&clear_line))?;
     }
 
     // Draw frame borders
-    let horiz = "─".repeat(cols - 2);
-    queue!(
+    let theme = &options.theme;
+    let (border_fg, border_bg) = (theme.border_fg, theme.border_bg);
+    if let Layout::SideBySide { split_col } = options.layout {
+        for row in frame_start..rows {
+            queue!(out, MoveTo(split_col as u16, row as u16))?;
+            print_colored(out, "│", border_fg, border_bg)?;
+        }
+    }
+    queue!(out, MoveTo(left as u16, frame_start as u16))?;
+    print_colored(
         out,
-        MoveTo(0, frame_start as u16),
-        Print(format!("╭{}╮", horiz)),
-        MoveTo(0, (rows - 1) as u16),
-        Print(format!("╰{}╯", horiz))
+        &top_border(cols, options.title.as_deref(), options.frame_style),
+        border_fg,
+        border_bg,
+    )?;
+    if let (Some(title_fg), Some(title)) = (
+        options.title_fg,
+        fitted_title(cols, options.title.as_deref()),
+    ) {
+        queue!(
+            out,
+            MoveTo((left + TITLE_COLUMN) as u16, frame_start as u16)
+        )?;
+        print_colored(out, &title, title_fg, border_bg)?;
+    }
+    queue!(out, MoveTo(left as u16, bottom_row as u16))?;
+    print_colored(
+        out,
+        &bottom_border(cols, options.status.as_deref(), options.frame_style),
+        border_fg,
+        border_bg,
     )?;
 
-    // Split text into lines, handling both newlines and wrapping
-    let mut lines = Vec::new();
+    let lines: Vec<_> = wrap_logical_lines(buf, content_width).collect();
+    let total = lines.len() + options.popup.len();
+    let visible = visible_rows(total, rows, required_lines, options.scroll_offset);
+    // Viewport row of row `i` of text and popup, if it is scrolled into view
+    let viewport_row = |i: usize| i.checked_sub(visible.0).filter(|&i| i < visible.1);
 
-    for text_line in buf.split('\n') {
-        if text_line.is_empty() {
-            lines.push(""); // Empty lines from newlines
-        } else {
-            // Handle wrapping for this line segment
-            let mut current_pos = 0;
-            while current_pos < text_line.len() {
-                let end_pos = (current_pos + content_width).min(text_line.len());
-                lines.push(&text_line[current_pos..end_pos]);
-                current_pos = end_pos;
-            }
-        }
+    // Draw each line with content, leaving out what is scrolled out of the
+    // viewport of a frame taller than the terminal
+    for (i, &(source, _, line)) in lines.iter().enumerate() {
+        let Some(v) = viewport_row(i) else { continue };
+        let row = frame_start + 1 + v;
+        let prefix = if i == 0 { "> " } else { "  " }; // prompt on first line only
+        let padding = " ".repeat(content_width.saturating_sub(display_width(line)));
+        let indicator = wrap_indicator_cell(&lines, i, options.wrap_indicator);
+        let bg = line_bg(source, options);
+
+        queue!(out, MoveTo(left as u16, row as u16))?;
+        print_colored(out, "│", border_fg, border_bg)?;
+        print_colored(out, &format!("{pad} "), theme.text_fg, bg)?;
+        print_colored(out, prefix, theme.prompt_fg, bg)?;
+        print_text_line(out, buf, line, bg, options)?;
+        print_colored(out, &padding, theme.text_fg, bg)?;
+        print_colored(out, &indicator, theme.prompt_fg, bg)?;
+        print_colored(out, &pad, theme.text_fg, bg)?;
+        print_colored(out, right_border(v, total, visible), border_fg, border_bg)?;
     }
 
-    // Draw each line with content
-    for (i, line) in lines.iter().enumerate() {
-        let row = frame_start + 1 + i;
-        let prefix = if i == 0 { "> " } else { "  " }; // prompt on first line only
-        let padding = " ".repeat(content_width.saturating_sub(line.len()));
+    // Draw completion candidates below the text, truncated to the content width
+    for (i, candidate) in options.popup.iter().enumerate() {
+        let Some(v) = viewport_row(lines.len() + i) else {
+            continue;
+        };
+        let row = frame_start + 1 + v;
+        let shown: String = candidate.chars().take(popup_width).collect();
+        let padding = " ".repeat(popup_width.saturating_sub(shown.chars().count()));
 
-        queue!(
+        queue!(out, MoveTo(left as u16, row as u16))?;
+        print_colored(out, "│", border_fg, border_bg)?;
+        print_colored(
             out,
-            MoveTo(0, row as u16),
-            Print(format!("│ {}{}{}│", prefix, line, padding))
+            &format!("{pad}   {shown}{padding}{pad}"),
+            theme.text_fg,
+            theme.text_bg,
         )?;
+        print_colored(out, right_border(v, total, visible), border_fg, border_bg)?;
     }
 
-    // Position cursor at the end of the text
-    let (cursor_col, cursor_row) = calculate_cursor_position(buf, cols, rows, required_lines);
+    // Position cursor, at the end of the text unless told otherwise
+    let (cursor_col, cursor_row) =
+        prompt_cursor_position(buf, (term_cols, rows), required_lines, options);
     queue!(out, MoveTo(cursor_col as u16, cursor_row as u16))?;
 
     out.flush()?;
     Ok(())
 }
 
+/// End of the wrapped piece of `line` starting at byte `start`: the
+/// characters that fit in `width` columns, measured like [`display_width`].
+/// A character wider than `width` columns gets a piece of its own.
+fn wrap_end(line: &str, start: usize, width: usize) -> usize {
+    let mut columns = 0;
+    for (i, c) in line[start..].char_indices() {
+        columns += c.width().unwrap_or(0);
+        if columns > width {
+            return if i > 0 {
+                start + i
+            } else {
+                start + c.len_utf8()
+            };
+        }
+    }
+    line.len()
+}
+
+/// Splits `buf` into the lines shown in the frame, breaking at newlines and
+/// wrapping every `content_width` columns without splitting a character
+fn wrap_prompt_lines(buf: &str, content_width: usize) -> Vec<&str> {
+    wrap_logical_lines(buf, content_width)
+        .map(|(_, _, line)| line)
+        .collect()
+}
+
+/// The rows of [`wrap_prompt_lines`] as `(logical line, row within it, text)`
+fn wrap_logical_lines(
+    buf: &str,
+    content_width: usize,
+) -> impl Iterator<Item = (usize, usize, &str)> {
+    buf.split('\n')
+        .enumerate()
+        .flat_map(move |(source, text_line)| {
+            let mut rows = Vec::new();
+            if text_line.is_empty() {
+                rows.push(text_line); // Empty lines from newlines
+            } else {
+                // Handle wrapping for this line segment
+                let mut current_pos = 0;
+                while current_pos < text_line.len() {
+                    let end_pos = wrap_end(text_line, current_pos, content_width);
+                    rows.push(&text_line[current_pos..end_pos]);
+                    current_pos = end_pos;
+                }
+            }
+            rows.into_iter()
+                .enumerate()
+                .map(move |(visual, line)| (source, visual, line))
+        })
+}
+
+/// Terminal position of the cursor for `options.cursor`, or the end of `buf`
+fn prompt_cursor_position(
+    buf: &str,
+    (cols, rows): (usize, usize),
+    required_lines: usize,
+    options: &PromptOptions,
+) -> (usize, usize) {
+    let cursor = options.cursor.unwrap_or(buf.len()).min(buf.len());
+    let cols = text_cols(
+        options.layout.frame_cols(cols),
+        options.wrap_indicator,
+        options.frame_padding,
+    );
+    let (col, row) = calculate_cursor_position(&buf[..cursor], cols, rows, required_lines);
+    let total = wrap_prompt_lines(buf, content_width(cols)).len() + options.popup.len();
+    let (first, _) = visible_rows(total, rows, required_lines, options.scroll_offset);
+    (
+        options.layout.frame_left() + options.frame_padding + col,
+        // Above the bottom border, where the rows of a too tall frame stop
+        row.saturating_sub(first).min(rows.saturating_sub(2)),
+    )
+}
+
+/// Builds the grid of the whole terminal with the frame drawn the same way as
+/// [`draw_prompt_to_buffer`]. Rows above the frame are left blank.
+pub fn build_prompt_grid(
+    buf: &str,
+    (term_cols, rows): (usize, usize),
+    required_lines: usize,
+    options: &PromptOptions,
+) -> grid::TerminalGrid {
+    let left = options.layout.frame_left();
+    let cols = options.layout.frame_cols(term_cols);
+    let pad = " ".repeat(options.frame_padding);
+    let popup_width = content_width(text_cols(cols, None, options.frame_padding));
+    let content_width = content_width(text_cols(
+        cols,
+        options.wrap_indicator,
+        options.frame_padding,
+    ));
+    let frame_start = frame_top(rows, required_lines);
+    let bottom_row = rows.saturating_sub(1);
+    let mut grid = grid::TerminalGrid::new(term_cols, rows);
+
+    let theme = &options.theme;
+    let style = |fg, bg| grid::TerminalCell {
+        fg,
+        bg,
+        ..Default::default()
+    };
+    let border = style(theme.border_fg, theme.border_bg);
+    let text = style(theme.text_fg, theme.text_bg);
+
+    if let Layout::SideBySide { split_col } = options.layout {
+        for row in frame_start..rows {
+            grid.put_styled(split_col, row, "│", border);
+        }
+    }
+    grid.put_styled(
+        left,
+        frame_start,
+        &top_border(cols, options.title.as_deref(), options.frame_style),
+        border,
+    );
+    if let (Some(title_fg), Some(title)) = (
+        options.title_fg,
+        fitted_title(cols, options.title.as_deref()),
+    ) {
+        grid.put_styled(
+            left + TITLE_COLUMN,
+            frame_start,
+            &title,
+            style(title_fg, theme.border_bg),
+        );
+    }
+    grid.put_styled(
+        left,
+        bottom_row,
+        &bottom_border(cols, options.status.as_deref(), options.frame_style),
+        border,
+    );
+
+    let lines: Vec<_> = wrap_logical_lines(buf, content_width).collect();
+    let total = lines.len() + options.popup.len();
+    let visible = visible_rows(total, rows, required_lines, options.scroll_offset);
+    let viewport_row = |i: usize| i.checked_sub(visible.0).filter(|&i| i < visible.1);
+    for (i, &(source, _, line)) in lines.iter().enumerate() {
+        let Some(v) = viewport_row(i) else { continue };
+        let row = frame_start + 1 + v;
+        let prefix = if i == 0 { "> " } else { "  " };
+        let bg = line_bg(source, options);
+        let (text, prompt) = (style(theme.text_fg, bg), style(theme.prompt_fg, bg));
+        let mut col = grid.put_styled(left, row, "│", border);
+        col = grid.put_styled(col, row, &format!("{pad} "), text);
+        col = grid.put_styled(col, row, prefix, prompt);
+
+        let line_start = line.as_ptr() as usize - buf.as_ptr() as usize;
+        for (offset, ch) in line.char_indices() {
+            let style = text_style_at(line_start + offset, bg, options);
+            col = grid.put_styled(col, row, ch.encode_utf8(&mut [0; 4]), style);
+        }
+        let padding = " ".repeat(content_width.saturating_sub(display_width(line)));
+        col = grid.put_styled(col, row, &padding, text);
+        let indicator = wrap_indicator_cell(&lines, i, options.wrap_indicator);
+        col = grid.put_styled(col, row, &indicator, prompt);
+        col = grid.put_styled(col, row, &pad, text);
+        grid.put_styled(col, row, right_border(v, total, visible), border);
+    }
+
+    for (i, candidate) in options.popup.iter().enumerate() {
+        let Some(v) = viewport_row(lines.len() + i) else {
+            continue;
+        };
+        let row = frame_start + 1 + v;
+        let shown: String = candidate.chars().take(popup_width).collect();
+        let padding = " ".repeat(popup_width.saturating_sub(shown.chars().count()));
+        let mut col = grid.put_styled(left, row, "│", border);
+        col = grid.put_styled(col, row, &format!("{pad}   {shown}{padding}{pad}"), text);
+        grid.put_styled(col, row, right_border(v, total, visible), border);
+    }
+
+    grid
+}
+
+/// Draws the prompt like [`draw_prompt_to_buffer`], writing only the cells
+/// that differ from `prev`, the grid of the previous draw. Without a previous
+/// grid of the same size the whole frame is redrawn.
+///
+/// # Returns
+///
+/// The grid now on screen, to pass as `prev` next time.
+pub fn draw_prompt_diff_to_buffer<W: Write>(
+    out: &mut W,
+    buf: &str,
+    dimensions: (usize, usize),
+    required_lines: usize,
+    options: &PromptOptions,
+    prev: Option<&grid::TerminalGrid>,
+) -> Result<grid::TerminalGrid, TermboxError> {
+    check_terminal_width(
+        options.layout.frame_cols(dimensions.0),
+        options.frame_padding,
+    )?;
+    let new = build_prompt_grid(buf, dimensions, required_lines, options);
+    match prev {
+        Some(prev) if prev.cols == new.cols && prev.rows == new.rows => {
+            grid::diff_and_draw(out, prev, &new)?;
+            let (cursor_col, cursor_row) =
+                prompt_cursor_position(buf, dimensions, required_lines, options);
+            queue!(out, MoveTo(cursor_col as u16, cursor_row as u16))?;
+            out.flush()?;
+        }
+        _ => draw_prompt_to_buffer(out, buf, dimensions, required_lines, options)?,
+    }
+    Ok(new)
+}
+
+/// Background of the rows of logical line `line`
+fn line_bg(line: usize, options: &PromptOptions) -> Color {
+    if options.current_line == Some(line) {
+        options.theme.current_line_bg
+    } else {
+        options.theme.text_bg
+    }
+}
+
+/// Style of the text byte at `byte`: the theme's text color on `bg`, the
+/// background of its row, overridden by any highlight span covering it and
+/// then by the selection
+fn text_style_at(byte: usize, bg: Color, options: &PromptOptions) -> grid::TerminalCell {
+    let theme = &options.theme;
+    let mut cell = grid::TerminalCell {
+        fg: theme.text_fg,
+        bg,
+        ..Default::default()
+    };
+    for (range, style) in &options.highlights {
+        if range.contains(&byte) {
+            cell.fg = style.fg.unwrap_or(cell.fg);
+            cell.bg = style.bg.unwrap_or(cell.bg);
+            cell.bold |= style.bold;
+            cell.underline |= style.underline;
+        }
+    }
+    if options
+        .selection
+        .as_ref()
+        .is_some_and(|sel| sel.contains(&byte))
+    {
+        cell.bg = theme.selection_bg;
+    }
+    cell
+}
+
+/// Prints one visual line, a slice of `text`, in runs of equal style as
+/// given by [`text_style_at`]
+fn print_text_line<W: Write>(
+    out: &mut W,
+    text: &str,
+    line: &str,
+    bg: Color,
+    options: &PromptOptions,
+) -> Result<(), TermboxError> {
+    // Byte offset of this visual line within the full text
+    let line_start = line.as_ptr() as usize - text.as_ptr() as usize;
+
+    let mut run_start = 0;
+    let mut run_style = text_style_at(line_start, bg, options);
+    for (offset, _) in line.char_indices().skip(1) {
+        let style = text_style_at(line_start + offset, bg, options);
+        if style != run_style {
+            print_styled(out, &line[run_start..offset], &run_style)?;
+            run_start = offset;
+            run_style = style;
+        }
+    }
+    print_styled(out, &line[run_start..], &run_style)
+}
+
+/// Prints `text` in the given colors and resets them afterwards. Text in
+/// `Color::Reset` on `Color::Reset` is printed without color escapes.
+fn print_colored<W: Write>(
+    out: &mut W,
+    text: &str,
+    fg: Color,
+    bg: Color,
+) -> Result<(), TermboxError> {
+    let style = grid::TerminalCell {
+        fg,
+        bg,
+        ..Default::default()
+    };
+    print_styled(out, text, &style)
+}
+
+/// Prints `text` in the colors and weight of `style`, ignoring its `ch`, and
+/// resets them afterwards. A default style prints no escape sequences.
+fn print_styled<W: Write>(
+    out: &mut W,
+    text: &str,
+    style: &grid::TerminalCell,
+) -> Result<(), TermboxError> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let plain = style.fg == Color::Reset && style.bg == Color::Reset;
+    if plain && !style.bold && !style.underline {
+        queue!(out, Print(text))?;
+        return Ok(());
+    }
+    if style.fg != Color::Reset {
+        queue!(out, SetForegroundColor(style.fg))?;
+    }
+    if style.bg != Color::Reset {
+        queue!(out, SetBackgroundColor(style.bg))?;
+    }
+    if style.bold {
+        queue!(out, SetAttribute(Attribute::Bold))?;
+    }
+    if style.underline {
+        queue!(out, SetAttribute(Attribute::Underlined))?;
+    }
+    // ResetColor also clears attributes
+    queue!(out, Print(text), ResetColor)?;
+    Ok(())
+}
+
 /// Captures terminal drawing operations as a string for testing
-pub fn capture_terminal_drawing(
+pub fn capture_terminal_drawing<E>(
     _state: &InputState,
-    draw_fn: impl FnOnce(&mut std::io::Cursor<Vec<u8>>) -> anyhow::Result<()>,
-) -> anyhow::Result<String> {
+    draw_fn: impl FnOnce(&mut std::io::Cursor<Vec<u8>>) -> Result<(), E>,
+) -> Result<String, E> {
     let mut buffer = std::io::Cursor::new(Vec::new());
     draw_fn(&mut buffer)?;
     let bytes = buffer.into_inner();
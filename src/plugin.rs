@@ -0,0 +1,21 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::{InputState, KeyAction};
+
+/// Extends key handling of an [`InputState`].
+///
+/// Plugins see every key before the built-in bindings, in the order they
+/// were registered with `InputState::register_plugin`. State a plugin needs
+/// between keys can live in `InputState::plugin_state_mut`.
+pub trait Plugin {
+    fn name(&self) -> &str;
+
+    /// Handle `key`, returning `Some(action)` to consume it: later plugins and
+    /// the built-in bindings then never see it. `None` passes the key on.
+    fn on_key(
+        &self,
+        key: KeyCode,
+        modifiers: KeyModifiers,
+        state: &mut InputState,
+    ) -> Option<KeyAction>;
+}
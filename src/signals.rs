@@ -0,0 +1,95 @@
+use tokio::sync::mpsc;
+
+/// Stops the process with SIGTSTP, as Ctrl+Z does outside raw mode, and
+/// returns once the shell continues it with SIGCONT.
+///
+/// Give the terminal back first with `TermboxGuard::suspend`. On other
+/// platforms this returns right away.
+///
+/// # Returns
+///
+/// Returns an error if the signal cannot be raised.
+pub fn stop_process() -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        // SAFETY: raising a signal has no memory safety requirements
+        if unsafe { libc::raise(libc::SIGTSTP) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+/// Creates a channel that receives a message whenever the terminal window
+/// changes size.
+///
+/// On Unix this listens for SIGWINCH, which some terminal emulators send
+/// without crossterm reporting an `Event::Resize`. On other platforms the
+/// channel never receives anything and resizes rely on `Event::Resize` alone.
+///
+/// # Returns
+///
+/// Returns the receiving end of the channel, or an error if the signal
+/// handler cannot be installed.
+pub fn window_change_channel() -> anyhow::Result<mpsc::Receiver<()>> {
+    let (tx, rx) = mpsc::channel(1);
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut window_change = signal(SignalKind::window_change())?;
+        tokio::spawn(async move {
+            while window_change.recv().await.is_some() {
+                // A full channel already has a resize pending
+                if let Err(mpsc::error::TrySendError::Closed(_)) = tx.try_send(()) {
+                    break;
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    {
+        // Keep the sender alive so the channel stays open
+        tokio::spawn(async move {
+            let _tx = tx;
+            std::future::pending::<()>().await
+        });
+    }
+
+    Ok(rx)
+}
+
+/// Waits for a window change that actually alters the terminal size.
+///
+/// Each message on `resize_rx` queries `size`, normally
+/// `crossterm::terminal::size`. Notifications that leave the size at
+/// `current` are skipped, so a resize already handled through
+/// `Event::Resize` is not handled twice.
+///
+/// # Arguments
+///
+/// * `resize_rx` - Receiver from `window_change_channel`
+/// * `current` - The terminal size as `(cols, rows)` the UI is drawn for
+/// * `size` - Queries the terminal size as `(cols, rows)`
+///
+/// # Returns
+///
+/// Returns the new size, or `None` once the channel is closed.
+pub async fn next_window_size(
+    resize_rx: &mut mpsc::Receiver<()>,
+    current: (usize, usize),
+    size: impl Fn() -> std::io::Result<(u16, u16)>,
+) -> Option<(usize, usize)> {
+    while resize_rx.recv().await.is_some() {
+        let Ok((cols, rows)) = size() else {
+            continue;
+        };
+        let new_size = (cols as usize, rows as usize);
+        if new_size != current {
+            return Some(new_size);
+        }
+    }
+    None
+}
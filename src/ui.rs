@@ -1,22 +1,37 @@
-use crate::{FRAME_CHARS, calculate_cursor_position};
-use crossterm::{cursor::MoveTo, queue, style::Print};
+use crate::error::TermboxError;
+use crate::{FrameStyle, InputState, Layout, Theme};
+use crossterm::{
+    cursor::{MoveTo, MoveToColumn},
+    queue,
+    style::Print,
+};
+use std::borrow::Cow;
 use std::io::Write;
 
 /// Pushes existing terminal content up by inserting newlines to make space for the input frame.
 ///
 /// This function ensures that any existing content in the terminal is scrolled up
 /// by the required number of lines before the input box is drawn, preventing the
-/// frame from overwriting existing content.
+/// frame from overwriting existing content. When the frame fills the whole
+/// terminal nothing is pushed.
 ///
 /// # Arguments
 ///
-/// * `out` - Mutable reference to stdout for writing output
+/// * `out` - Writer for terminal output, normally stdout
+/// * `rows` - The total terminal height in rows
 /// * `required_lines` - The number of lines to push the content up by
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` on success or an error if the operation fails.
-pub fn push_content_up(out: &mut std::io::Stdout, required_lines: usize) -> anyhow::Result<()> {
+pub fn push_content_up<W: Write>(
+    out: &mut W,
+    rows: usize,
+    required_lines: usize,
+) -> Result<(), TermboxError> {
+    if required_lines >= rows {
+        return Ok(());
+    }
     // Insert newlines to push existing content up
     for _ in 0..required_lines {
         queue!(out, Print("\n"))?;
@@ -34,79 +49,111 @@ pub fn push_content_up(out: &mut std::io::Stdout, required_lines: usize) -> anyh
 ///
 /// # Arguments
 ///
+/// * `out` - Writer for terminal output, normally stdout
 /// * `rows` - The total terminal height in rows
 /// * `required_lines` - The number of lines to reserve at the bottom for the input box
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` on success or an error if the operation fails.
-pub fn set_scroll_region(rows: usize, required_lines: usize) -> anyhow::Result<()> {
-    let scroll_bottom = rows - required_lines; // keep bottom lines fixed for frame
+pub fn set_scroll_region<W: Write>(
+    out: &mut W,
+    rows: usize,
+    required_lines: usize,
+) -> Result<(), TermboxError> {
+    queue!(out, Print(scroll_region_sequence(rows, required_lines)))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Sets the scroll region to `state.scroll_region()`, which also accounts
+/// for `state.frame_position`
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if the operation fails.
+pub fn set_scroll_region_for<W: Write>(
+    out: &mut W,
+    state: &InputState,
+) -> Result<(), TermboxError> {
+    state.content_area().apply(out)
+}
+
+/// The DECSTBM escape sequence written by [`set_scroll_region`]
+pub fn scroll_region_sequence(rows: usize, required_lines: usize) -> String {
+    let scroll_bottom = rows.saturating_sub(required_lines).max(1); // keep bottom lines fixed for frame
     // DECSTBM is 1-based & inclusive:  ESC[{top};{bottom}r
-    print!("\x1B[1;{}r", scroll_bottom);
+    format!("\x1B[1;{}r", scroll_bottom)
+}
+
+/// Gives the whole terminal back to scrolling, dropping any scroll region
+pub fn reset_scroll_region<W: Write>(out: &mut W) -> Result<(), TermboxError> {
+    queue!(out, Print("\x1B[r"))?;
+    Ok(())
+}
+
+/// Overwrites every row of the scroll region, the rows above the frame, with
+/// spaces.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `rows` - The total terminal height in rows
+/// * `required_lines` - The number of lines the input box occupies
+/// * `cols` - The terminal width in columns
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if output operations fail.
+pub fn clear_scroll_region<W: Write>(
+    out: &mut W,
+    rows: usize,
+    required_lines: usize,
+    cols: usize,
+) -> Result<(), TermboxError> {
+    let clear_line = " ".repeat(cols);
+    for row in 0..rows.saturating_sub(required_lines) {
+        queue!(out, MoveTo(0, row as u16), Print(&clear_line))?;
+    }
+    out.flush()?;
     Ok(())
 }
 
 /// Draws the border frame around the input box.
 ///
 /// Creates a box using Unicode drawing characters (╭─╮│╰─╯) that surrounds
-/// the input area. The frame is drawn at the bottom of the terminal and
-/// adjusts its height based on the content requirements.
+/// the input area, or only its `│` sides in `FrameStyle::Minimal`. The frame
+/// is drawn at the bottom of the terminal and adjusts its height based on the
+/// content requirements.
 ///
 /// # Arguments
 ///
 /// * `out` - Mutable reference to stdout for writing output
 /// * `(cols, rows)` - Terminal dimensions as a tuple (width, height)
 /// * `required_lines` - The number of lines the complete input box needs
+/// * `theme` - Colors of the border
+/// * `layout` - Columns the frame is drawn in; `Layout::SideBySide` also
+///   draws the separator
+/// * `frame_style` - Whether the top and bottom borders are drawn
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` on successful drawing or an error if output operations fail.
 pub fn draw_frame(
     out: &mut std::io::Stdout,
-    (cols, rows): (usize, usize),
+    dimensions: (usize, usize),
     required_lines: usize,
-) -> anyhow::Result<()> {
-    let horiz = "─".repeat(cols - 2);
-    let clear_line = " ".repeat(cols);
-    let frame_start = rows - required_lines;
-
-    // Clear only lines that won't interfere with scroll region content
-    let scroll_region_bottom = rows - required_lines - 1;
-    for i in 1..=2 {
-        let clear_row = frame_start - i;
-        if clear_row > scroll_region_bottom {
-            queue!(out, MoveTo(0, clear_row as u16), Print(&clear_line))?;
-        }
-    }
-
-    queue!(
+    theme: &Theme,
+    layout: Layout,
+    frame_style: FrameStyle,
+) -> Result<(), TermboxError> {
+    crate::draw_styled_frame_to_buffer(
         out,
-        // draw top border
-        MoveTo(0, frame_start as u16),
-        Print(format!("╭{}╮", horiz))
-    )?;
-
-    // draw middle lines (input area) - only clear and draw the borders, not the content
-    for i in 1..required_lines - 1 {
-        queue!(
-            out,
-            MoveTo(0, (frame_start + i) as u16),
-            Print("│"),
-            MoveTo((cols - 1) as u16, (frame_start + i) as u16),
-            Print("│")
-        )?;
-    }
-
-    // draw bottom border
-    queue!(
-        out,
-        MoveTo(0, (rows - 1) as u16),
-        Print(format!("╰{}╯", horiz))
-    )?;
-
-    out.flush()?;
-    Ok(())
+        dimensions,
+        required_lines,
+        theme,
+        (layout, frame_style),
+    )
 }
 
 /// Draws the input prompt and text content within the frame.
@@ -126,6 +173,7 @@ pub fn draw_frame(
 /// * `buf` - The current input text buffer
 /// * `(cols, rows)` - Terminal dimensions as a tuple (width, height)
 /// * `required_lines` - The number of lines the input box occupies
+/// * `layout` - Columns the frame is drawn in
 ///
 /// # Returns
 ///
@@ -133,62 +181,346 @@ pub fn draw_frame(
 pub fn draw_prompt_line(
     out: &mut std::io::Stdout,
     buf: &str,
-    (cols, rows): (usize, usize),
+    dimensions: (usize, usize),
     required_lines: usize,
-) -> anyhow::Result<()> {
-    let content_width = cols - FRAME_CHARS; // "│ > " + content + "│"
-    let frame_start = rows - required_lines;
-    let clear_line = " ".repeat(cols);
+    layout: Layout,
+) -> Result<(), TermboxError> {
+    let options = crate::PromptOptions {
+        layout,
+        ..Default::default()
+    };
+    crate::draw_prompt_to_buffer(out, buf, dimensions, required_lines, &options)
+}
 
-    // Clear and redraw the entire frame area to ensure no artifacts
-    for row in frame_start..rows {
-        queue!(out, MoveTo(0, row as u16), Print(&clear_line))?;
+/// Draws the input prompt for the current state.
+///
+/// Unlike `draw_prompt_line`, this draws the display text (masked in password
+/// mode) together with the decorations returned by `InputState::prompt_options`,
+/// such as the completion popup.
+///
+/// Only the cells that changed since the previous call are written, using
+/// `state.prev_grid`. Set it to `None` after drawing the frame by other means.
+///
+/// # Arguments
+///
+/// * `out` - Mutable reference to stdout for writing output
+/// * `state` - The input state to draw
+///
+/// # Returns
+///
+/// Returns `Ok(())` on successful rendering or an error if output operations fail.
+pub fn draw_input(out: &mut std::io::Stdout, state: &mut InputState) -> Result<(), TermboxError> {
+    let grid = crate::draw_prompt_diff_to_buffer(
+        out,
+        &state.display_text(),
+        (state.cols, state.frame_end_row()),
+        state.required_lines,
+        &state.prompt_options(),
+        state.prev_grid.as_ref(),
+    )?;
+    state.prev_grid = Some(grid);
+    Ok(())
+}
+
+/// Prints text to the bottom of the scroll region and redraws the input box.
+///
+/// The text is first passed through `state.output_formatter`, unless it is
+/// only newlines: those reserve rows for animations and stay blank. It is
+/// printed with `ContentArea::print` at the bottom of `state.content_area()`,
+/// and the content above scrolls up to make room. In `Layout::SideBySide` lines are
+/// broken at the separator so they stay left of it. The lines are also kept in
+/// `state.content` for [`redraw_scroll_region`]. Running animations are told how far
+/// it moved through a `ScrollEvent`.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `text` - The text to print, with `\n` line separators
+/// * `state` - The input state, used for the terminal size and frame height
+///
+/// # Returns
+///
+/// Returns the number of rows the content scrolled up by, or an error if
+/// output operations fail.
+pub fn print_to_scroll_region<W: Write>(
+    out: &mut W,
+    text: &str,
+    state: &mut InputState,
+) -> Result<usize, TermboxError> {
+    let layout = state.effective_layout();
+    let cols = layout.output_cols(state.cols);
+    let text = match &state.output_formatter {
+        Some(formatter) if text.contains(|c| c != '\n') => Cow::Owned(formatter.format(text, cols)),
+        _ => Cow::Borrowed(text),
+    };
+    state.content.push_text(&text);
+    // The terminal would wrap long lines across the box
+    let text = match layout {
+        Layout::SideBySide { .. } => Cow::Owned(
+            text.split('\n')
+                .flat_map(|line| wrap_columns(line, cols))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Layout::Single => text,
+    };
+
+    let line_count = state.content_area().print(out, &text)?;
+
+    // Nothing fits in a terminal narrower than the frame; wait for a resize
+    if !state.too_narrow() {
+        redraw_prompt(out, state)?;
     }
 
-    // Draw frame borders
-    let horiz = "─".repeat(cols - 2);
+    Ok(line_count)
+}
+
+/// Prints `text` at row `scroll_region_bottom`, the last row of the scroll
+/// region, so the rows above it scroll up.
+///
+/// Newlines are converted to `\r\n` so every line starts at column 0. Needs
+/// no `InputState`, so command handlers can print with just the row and
+/// width; unlike [`print_to_scroll_region`] it neither keeps the text for
+/// redrawing nor tells animations about the scrolling.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `text` - The text to print, with `\n` line separators
+/// * `scroll_region_bottom` - Row of the bottom of the scroll region
+/// * `cols` - Width the terminal wraps the text at
+///
+/// # Returns
+///
+/// Returns the number of rows the content scrolled up by, counting wrapped
+/// rows, or an error if output operations fail.
+pub fn write_str_to_scroll_region<W: Write>(
+    out: &mut W,
+    text: &str,
+    scroll_region_bottom: usize,
+    cols: usize,
+) -> Result<usize, TermboxError> {
     queue!(
         out,
-        MoveTo(0, frame_start as u16),
-        Print(format!("╭{}╮", horiz)),
-        MoveTo(0, (rows - 1) as u16),
-        Print(format!("╰{}╯", horiz))
+        MoveTo(0, scroll_region_bottom as u16),
+        Print(text.replace('\n', "\r\n")),
+        Print("\r\n") // Final newline to scroll properly
+    )?;
+    out.flush()?;
+    Ok(crate::output_line_count(text, cols))
+}
+
+/// Repaints the frame and prompt in full after output scrolled the terminal
+fn redraw_prompt<W: Write>(out: &mut W, state: &mut InputState) -> Result<(), TermboxError> {
+    crate::draw_styled_frame_to_buffer(
+        out,
+        (state.cols, state.frame_end_row()),
+        state.required_lines,
+        &state.theme,
+        (state.effective_layout(), state.frame_style),
     )?;
+    // The frame was repainted in full, so start diffing from what is drawn now
+    let grid = crate::draw_prompt_diff_to_buffer(
+        out,
+        &state.display_text(),
+        (state.cols, state.frame_end_row()),
+        state.required_lines,
+        &state.prompt_options(),
+        None,
+    )?;
+    state.prev_grid = Some(grid);
+    Ok(())
+}
+
+/// Repaints the scroll region from the lines kept in `state.content`.
+///
+/// Used after a resize, when the terminal may have reflowed or dropped what
+/// was printed. The region is cleared and the most recent lines are drawn
+/// against its bottom, wrapping at the new width like the terminal would. In
+/// `Layout::SideBySide` only the columns left of the separator are used.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `state` - The input state, used for the terminal size, frame height and content
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if output operations fail.
+pub fn redraw_scroll_region<W: Write>(out: &mut W, state: &InputState) -> Result<(), TermboxError> {
+    let region = state.scroll_region();
+    let height = region.len();
+    let cols = state.effective_layout().output_cols(state.cols);
 
-    // Split text into lines, handling both newlines and wrapping
-    let mut lines = Vec::new();
-
-    for text_line in buf.split('\n') {
-        if text_line.is_empty() {
-            lines.push(""); // Empty lines from newlines
-        } else {
-            // Handle wrapping for this line segment
-            let mut current_pos = 0;
-            while current_pos < text_line.len() {
-                let end_pos = (current_pos + content_width).min(text_line.len());
-                lines.push(&text_line[current_pos..end_pos]);
-                current_pos = end_pos;
-            }
+    // Every line takes at least one row, so the last `height` lines suffice
+    let rows: Vec<String> = state
+        .content
+        .last_n(height)
+        .into_iter()
+        .flat_map(|line| wrap_columns(line, cols))
+        .collect();
+    let rows = &rows[rows.len().saturating_sub(height)..];
+
+    let clear_line = " ".repeat(cols);
+    let first_row = height - rows.len();
+    for row in 0..height {
+        let terminal_row = (region.start + row) as u16;
+        queue!(out, MoveTo(0, terminal_row), Print(&clear_line))?;
+        if let Some(text) = row.checked_sub(first_row).map(|i| &rows[i]) {
+            queue!(out, MoveTo(0, terminal_row), Print(text))?;
         }
     }
+    out.flush()?;
+    Ok(())
+}
 
-    // Draw each line with content
-    for (i, line) in lines.iter().enumerate() {
-        let row = frame_start + 1 + i;
-        let prefix = if i == 0 { "> " } else { "  " }; // prompt on first line only
-        let padding = " ".repeat(content_width.saturating_sub(line.len()));
-
-        queue!(
-            out,
-            MoveTo(0, row as u16),
-            Print(format!("│ {}{}{}│", prefix, line, padding))
-        )?;
+/// `line` broken into rows of at most `cols` columns, as the terminal
+/// wraps it, measured by display width like the input text; one empty row
+/// for an empty line
+fn wrap_columns(line: &str, cols: usize) -> Vec<String> {
+    crate::wrap_prompt_lines(line, cols.max(1))
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Drawn in the right border for the rows of the scrollbar thumb
+pub const SCROLLBAR_THUMB: &str = "▐";
+
+/// Position of the scrollbar thumb for a view of `viewport_height` lines
+/// starting `scroll_offset` lines into `total_lines`.
+///
+/// The thumb is at least one row tall, at the top for offset 0 and at the
+/// bottom for the largest offset. Without overflow it fills the viewport.
+///
+/// # Returns
+///
+/// Returns `(thumb_start, thumb_height)` in rows relative to the viewport.
+pub fn compute_scrollbar(
+    total_lines: usize,
+    viewport_height: usize,
+    scroll_offset: usize,
+) -> (usize, usize) {
+    if total_lines <= viewport_height || viewport_height == 0 {
+        return (0, viewport_height);
     }
+    let thumb_height = ((viewport_height * viewport_height + total_lines / 2) / total_lines).max(1);
+    let max_offset = total_lines - viewport_height;
+    let max_start = viewport_height - thumb_height;
+    let offset = scroll_offset.min(max_offset);
+    let thumb_start = (offset * max_start + max_offset / 2) / max_offset;
+    (thumb_start, thumb_height)
+}
+
+/// Line drawn by [`draw_horizontal_rule`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuleStyle {
+    /// `─`
+    #[default]
+    Solid,
+    /// `·`
+    Dotted,
+    /// `═`
+    Double,
+}
 
-    // Position cursor at the end of the text
-    let (cursor_col, cursor_row) = calculate_cursor_position(buf, cols, rows, required_lines);
-    queue!(out, MoveTo(cursor_col as u16, cursor_row as u16))?;
+impl RuleStyle {
+    /// The rule as text `cols` columns wide
+    pub fn line(self, cols: usize) -> String {
+        let ch = match self {
+            RuleStyle::Solid => "─",
+            RuleStyle::Dotted => "·",
+            RuleStyle::Double => "═",
+        };
+        ch.repeat(cols)
+    }
+}
 
+/// Draws a rule across the full width of the cursor's row, to separate
+/// sections of output.
+///
+/// Commands that print through the dispatcher return
+/// `CommandResult::PrintRule` instead, which also scrolls the rule into the
+/// scroll region.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `cols` - The terminal width in columns
+/// * `style` - The line to draw
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if output operations fail.
+pub fn draw_horizontal_rule<W: Write>(
+    out: &mut W,
+    cols: usize,
+    style: RuleStyle,
+) -> Result<(), TermboxError> {
+    queue!(out, MoveToColumn(0), Print(style.line(cols)))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Look of a banner from [`format_banner`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BannerStyle {
+    /// The text centered on a bar of `█`
+    Solid,
+    /// The text centered in a three row box of `┌─┐│└─┘`
+    Boxed,
+    /// The text centered between rules, `──── Title ────`
+    #[default]
+    Centered,
+}
+
+/// Formats `text` as a banner across `cols` columns.
+///
+/// Text longer than `cols - 4` characters is truncated so there is always
+/// some decoration on either side. `BannerStyle::Boxed` gives three lines
+/// separated by `\n`, the other styles one. Command handlers can return the
+/// result as `CommandResult::PrintOutput`.
+pub fn format_banner(text: &str, cols: usize, style: BannerStyle) -> String {
+    let text: String = text.chars().take(cols.saturating_sub(4)).collect();
+    let len = text.chars().count();
+    // Columns left around " text ", more of them on the right if odd
+    let fill = cols.saturating_sub(len + 2);
+    let (left, right) = (fill / 2, fill - fill / 2);
+    match style {
+        BannerStyle::Solid => format!("{} {} {}", "█".repeat(left), text, "█".repeat(right)),
+        BannerStyle::Centered => format!("{} {} {}", "─".repeat(left), text, "─".repeat(right)),
+        BannerStyle::Boxed => {
+            let inner = "─".repeat(cols.saturating_sub(2));
+            format!(
+                "┌{inner}┐\n│{}{text}{}│\n└{inner}┘",
+                " ".repeat(left),
+                " ".repeat(right)
+            )
+        }
+    }
+}
+
+/// Draws a banner from [`format_banner`] starting on the cursor's row, to
+/// head a section of output.
+///
+/// # Arguments
+///
+/// * `out` - Writer for terminal output, normally stdout
+/// * `text` - The banner text, truncated to fit
+/// * `cols` - The terminal width in columns
+/// * `style` - How the banner is decorated
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success or an error if output operations fail.
+pub fn draw_banner<W: Write>(
+    out: &mut W,
+    text: &str,
+    cols: usize,
+    style: BannerStyle,
+) -> Result<(), TermboxError> {
+    let banner = format_banner(text, cols, style);
+    queue!(out, MoveToColumn(0), Print(banner.replace('\n', "\r\n")))?;
     out.flush()?;
     Ok(())
 }
@@ -0,0 +1,340 @@
+use std::sync::{Arc, Mutex};
+use termbox::animation::{
+    AnimationHandle, AnimationRegistry, ScrollTracker, box_top_after_scroll, drain_scroll_events,
+    run_animation, run_line_animation,
+};
+use termbox::commands::spinner::{SPINNER_FRAMES, run_spinner, spinner_frames};
+use termbox::commands::tiktok::{
+    TIKTOK_INTERVAL, TikTokConfig, run_tiktok_progress, run_tiktok_progress_with_config,
+};
+use termbox::{InputState, ScrollEvent};
+use tokio::sync::broadcast;
+use tokio::time::{Duration, sleep};
+
+#[test]
+fn test_drain_scroll_events_up_and_down() {
+    let (tx, mut rx) = broadcast::channel(16);
+    tx.send(ScrollEvent::ScrolledUp(3)).unwrap();
+    tx.send(ScrollEvent::ScrolledDown(1)).unwrap();
+    tx.send(ScrollEvent::ScrolledUp(2)).unwrap();
+
+    let mut lines_scrolled = 0;
+    assert!(drain_scroll_events(&mut rx, &mut lines_scrolled));
+    assert_eq!(lines_scrolled, 4);
+
+    // Scrolling down never takes the total below zero
+    tx.send(ScrollEvent::ScrolledDown(10)).unwrap();
+    assert!(drain_scroll_events(&mut rx, &mut lines_scrolled));
+    assert_eq!(lines_scrolled, 0);
+}
+
+#[test]
+fn test_drain_scroll_events_lagged_receiver() {
+    let (tx, mut rx) = broadcast::channel(2);
+    for _ in 0..5 {
+        tx.send(ScrollEvent::ScrolledUp(4)).unwrap();
+    }
+
+    // 3 events were overwritten and count as one line each; 2 are still queued
+    let mut lines_scrolled = 0;
+    assert!(drain_scroll_events(&mut rx, &mut lines_scrolled));
+    assert_eq!(lines_scrolled, 3 + 4 + 4);
+
+    // The box is clamped to the top row instead of wrapping around
+    assert_eq!(box_top_after_scroll(6, lines_scrolled), 0);
+    assert_eq!(box_top_after_scroll(20, lines_scrolled), 9);
+}
+
+#[test]
+fn test_drain_scroll_events_closed_channel() {
+    let (tx, mut rx) = broadcast::channel(4);
+    tx.send(ScrollEvent::ScrolledUp(1)).unwrap();
+    drop(tx);
+
+    let mut lines_scrolled = 0;
+    assert!(!drain_scroll_events(&mut rx, &mut lines_scrolled));
+    assert_eq!(lines_scrolled, 1);
+}
+
+#[test]
+fn test_scroll_tracker_adjusts_rows() {
+    let (tx, rx) = broadcast::channel(16);
+    let mut tracker = ScrollTracker::new(rx);
+    for _ in 0..3 {
+        tx.send(ScrollEvent::ScrolledUp(2)).unwrap();
+    }
+
+    assert!(tracker.drain());
+    assert_eq!(tracker.adjust_row(10), 4);
+    assert_eq!(tracker.adjust_row(3), 0);
+
+    tx.send(ScrollEvent::ScrolledDown(8)).unwrap();
+    assert!(tracker.drain());
+    assert_eq!(tracker.total_scrolled_up, 6);
+    assert_eq!(tracker.total_scrolled_down, 8);
+    // Like drain_scroll_events, the net never goes below zero
+    assert_eq!(tracker.adjust_row(10), 10);
+
+    drop(tx);
+    assert!(!tracker.drain());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_cancel_all_aborts_registered_animations() {
+    let registry = AnimationRegistry::new();
+    let finished = Arc::new(Mutex::new(0));
+    for _ in 0..2 {
+        let finished = finished.clone();
+        registry.spawn(async move {
+            sleep(Duration::from_secs(1)).await;
+            *finished.lock().unwrap() += 1;
+        });
+    }
+    assert_eq!(registry.active_count(), 2);
+
+    registry.cancel_all();
+    assert_eq!(registry.active_count(), 0);
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(*finished.lock().unwrap(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_finished_animations_are_not_counted() {
+    let registry = AnimationRegistry::new();
+    registry.spawn(sleep(Duration::from_millis(10)));
+    let clone = registry.clone();
+    assert_eq!(clone.active_count(), 1);
+
+    sleep(Duration::from_millis(20)).await;
+    assert_eq!(registry.active_count(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tiktok_progress_runs_all_steps() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let (_handle, cancel_rx) = AnimationHandle::new();
+
+    run_tiktok_progress(out.clone(), 40, 20, rx, 10, cancel_rx)
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("10/10"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_finished_animation_handle_is_cleared() {
+    let mut state = InputState::new(40, 20);
+    let (handle, cancel_rx) = AnimationHandle::new();
+    let (spinner, _spinner_rx) = AnimationHandle::new();
+    state.active_animation = Some(handle);
+    state.spinner = Some(spinner);
+    let (_tx, rx) = broadcast::channel(16);
+
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let task = tokio::spawn(run_tiktok_progress(out, 40, 20, rx, 10, cancel_rx));
+    state.clear_finished_animations();
+    assert!(state.active_animation.is_some());
+
+    task.await.unwrap().unwrap();
+    assert!(state.active_animation.as_ref().unwrap().is_finished());
+    state.clear_finished_animations();
+    assert!(state.active_animation.is_none());
+    // The spinner's receiver is still alive, so it is still running
+    assert!(state.spinner.is_some());
+}
+
+#[tokio::test]
+async fn test_animation_error_is_kept_on_its_handle() {
+    let mut state = InputState::new(40, 20);
+    let (handle, cancel_rx) = AnimationHandle::new();
+    let report = handle.error_reporter();
+    state.spinner = Some(handle);
+
+    let task = tokio::spawn(async move {
+        drop(cancel_rx);
+        report(Err(anyhow::anyhow!("broken pipe")));
+    });
+    task.await.unwrap();
+
+    let errors = state.clear_finished_animations();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "broken pipe");
+    assert!(state.spinner.is_none());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_cancel_stops_tiktok_progress_early() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let (handle, cancel_rx) = AnimationHandle::new();
+
+    let task = tokio::spawn(run_tiktok_progress(out.clone(), 40, 20, rx, 10, cancel_rx));
+    // Let two steps render, then cancel midway through the third
+    sleep(Duration::from_millis(1200)).await;
+    handle.cancel();
+    task.await.unwrap().unwrap();
+
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("3/10"));
+    assert!(!output.contains("4/10"));
+    assert!(!output.contains("10/10"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_run_animation_renders_frames_at_scrolled_positions() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (tx, rx) = broadcast::channel(16);
+    let frames = vec!["one".to_string(), "two".to_string(), "three".to_string()];
+
+    let task = tokio::spawn(run_animation(
+        out.clone(),
+        12,
+        20,
+        10,
+        frames,
+        (Duration::from_millis(100), Duration::ZERO),
+        rx,
+    ));
+    // Content scrolls up by two lines before the second frame
+    sleep(Duration::from_millis(50)).await;
+    tx.send(ScrollEvent::ScrolledUp(2)).unwrap();
+    task.await.unwrap().unwrap();
+
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    let expected = concat!(
+        "\x1B[11;1H╭──────────╮",
+        "\x1B[12;1H│ one      │",
+        "\x1B[13;1H╰──────────╯",
+        "\x1B[10;1H│ two      │",
+        "\x1B[10;1H│ three    │",
+    );
+    assert_eq!(output, expected);
+}
+
+/// Frames written on the middle line of the box by an animation of ten
+/// numbered frames 10ms apart, with writes limited to one per `rate_limit`
+async fn rate_limited_frames(rate_limit: Duration) -> Vec<String> {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let frames = (1..=10).map(|i| format!("frame {i}"));
+    let interval = Duration::from_millis(10);
+    run_animation(out.clone(), 14, 20, 10, frames, (interval, rate_limit), rx)
+        .await
+        .unwrap();
+
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    output
+        .split("\x1B[12;1H")
+        .skip(1)
+        .map(|line| line.split('\x1B').next().unwrap())
+        .map(|line| line.trim_matches(['│', ' ']).to_string())
+        .collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_run_animation_rate_limit() {
+    let all: Vec<_> = (1..=10).map(|i| format!("frame {i}")).collect();
+    assert_eq!(rate_limited_frames(Duration::ZERO).await, all);
+
+    // Only the first frame and the last are written
+    assert_eq!(
+        rate_limited_frames(Duration::from_secs(100)).await,
+        vec!["frame 1", "frame 10"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_run_line_animation_rate_limit() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let frames = (1..=10).map(|i| format!("frame {i}"));
+    let timing = (Duration::from_millis(10), Duration::from_secs(100));
+    run_line_animation(out.clone(), 10, 20, 5, frames, timing, rx)
+        .await
+        .unwrap();
+
+    // Only the first frame and the last are written
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    assert_eq!(output, "\x1B[6;1Hframe 1   \x1B[6;1Hframe 10  ");
+}
+
+#[test]
+fn test_spinner_frames_with_label() {
+    let frames = spinner_frames(Some("Loading..."));
+    assert_eq!(frames.len(), SPINNER_FRAMES.len());
+    assert_eq!(frames[0], "⠋ Loading...");
+    assert_eq!(frames[9], "⠏ Loading...");
+    assert_eq!(spinner_frames(None)[1], "⠙");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_spinner_renders_frame_sequence_on_one_line() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let (handle, cancel_rx) = AnimationHandle::new();
+
+    let task = tokio::spawn(run_spinner(
+        out.clone(),
+        12,
+        20,
+        rx,
+        5,
+        Some("Load".to_string()),
+        cancel_rx,
+    ));
+    // Frames are drawn at 0, 80, 160 and 240ms
+    sleep(Duration::from_millis(250)).await;
+    handle.cancel();
+    task.await.unwrap().unwrap();
+
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    let expected = concat!(
+        "\x1B[6;1H⠋ Load      ",
+        "\x1B[6;1H⠙ Load      ",
+        "\x1B[6;1H⠹ Load      ",
+        "\x1B[6;1H⠸ Load      ",
+    );
+    assert_eq!(output, expected);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_spinner_wraps_around_to_first_frame() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let (handle, cancel_rx) = AnimationHandle::new();
+
+    let task = tokio::spawn(run_spinner(out.clone(), 4, 20, rx, 5, None, cancel_rx));
+    // Eleven frames: one full rotation plus the first frame again
+    sleep(Duration::from_millis(810)).await;
+    handle.cancel();
+    task.await.unwrap().unwrap();
+
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    assert_eq!(output.matches("⠋").count(), 2);
+    assert_eq!(output.matches("⠏").count(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tiktok_progress_timeout_ends_at_final_frame() {
+    let out = Arc::new(Mutex::new(Vec::new()));
+    let (_tx, rx) = broadcast::channel(16);
+    let (_handle, cancel_rx) = AnimationHandle::new();
+    let config = TikTokConfig {
+        timeout: Duration::from_millis(10),
+        ..Default::default()
+    };
+
+    let start = tokio::time::Instant::now();
+    run_tiktok_progress_with_config(out.clone(), (40, 20), rx, 10, cancel_rx, config)
+        .await
+        .unwrap();
+
+    // The first step was never reached; the bar jumped straight to 10/10
+    assert!(start.elapsed() < TIKTOK_INTERVAL);
+    let output = String::from_utf8(out.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("1/10"));
+    assert!(!output.contains("2/10"));
+    assert!(output.contains("10/10"));
+}
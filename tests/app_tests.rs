@@ -0,0 +1,32 @@
+use std::io::Cursor;
+use termbox::app::run_non_tty;
+use termbox::{Config, InputMode, InputState};
+
+#[tokio::test]
+async fn test_non_tty_copies_lines() {
+    let mut out = Vec::new();
+    run_non_tty(Cursor::new("first\r\nsecond\nlast"), &mut out)
+        .await
+        .unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap(), "first\nsecond\nlast\n");
+}
+
+#[tokio::test]
+async fn test_non_tty_empty_input_writes_nothing() {
+    let mut out = Vec::new();
+    run_non_tty(Cursor::new(""), &mut out).await.unwrap();
+
+    assert!(out.is_empty());
+}
+
+#[test]
+fn test_with_config_applies_settings() {
+    let mut config = Config::default();
+    config.input_mode = InputMode::Password;
+    config.mask_char = '#';
+    let state = InputState::with_config(80, 24, config);
+
+    assert_eq!(state.input_mode, InputMode::Password);
+    assert_eq!(state.mask_char, '#');
+}
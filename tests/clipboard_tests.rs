@@ -0,0 +1,122 @@
+#![cfg(feature = "clipboard")]
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::sync::{Arc, Mutex};
+use termbox::clipboard::ClipboardProvider;
+use termbox::{InputMode, InputState, KeyAction, calculate_required_lines};
+
+/// In-memory clipboard standing in for the system one
+#[derive(Default)]
+struct MockClipboard {
+    contents: Mutex<Option<String>>,
+}
+
+impl MockClipboard {
+    fn with_text(text: &str) -> Arc<Self> {
+        Arc::new(Self {
+            contents: Mutex::new(Some(text.to_string())),
+        })
+    }
+}
+
+impl ClipboardProvider for MockClipboard {
+    fn get_text(&self) -> anyhow::Result<String> {
+        self.contents
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("clipboard is empty"))
+    }
+
+    fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        *self.contents.lock().unwrap() = Some(text.to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_ctrl_v_pastes_clipboard_text() {
+    let mut state = InputState::new(80, 24);
+    state.set_clipboard(MockClipboard::with_text("world"));
+    state.handle_key(KeyCode::Char('>'), KeyModifiers::NONE);
+
+    assert_eq!(
+        state.handle_key(KeyCode::Char('v'), KeyModifiers::CONTROL),
+        KeyAction::Continue
+    );
+    assert_eq!(state.buffer(), ">world");
+    assert_eq!(state.required_lines, 3);
+}
+
+#[test]
+fn test_ctrl_c_copies_selection_to_clipboard() {
+    let mut state = InputState::new(80, 24);
+    let clipboard = Arc::new(MockClipboard::default());
+    state.set_clipboard(clipboard.clone());
+    for c in "copy me".chars() {
+        state.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+    }
+    state.handle_key(KeyCode::Home, KeyModifiers::SHIFT);
+
+    assert_eq!(
+        state.handle_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        KeyAction::Continue
+    );
+    assert_eq!(clipboard.get_text().unwrap(), "copy me");
+    assert_eq!(state.kill_ring, vec!["copy me"]);
+    assert_eq!(state.selection_start, None);
+}
+
+#[test]
+fn test_ctrl_c_does_not_copy_password() {
+    let mut state = InputState::new(80, 24);
+    let clipboard = Arc::new(MockClipboard::default());
+    state.set_clipboard(clipboard.clone());
+    state.set_mode(InputMode::Password);
+    for c in "hunter2".chars() {
+        state.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+    }
+    state.handle_key(KeyCode::Home, KeyModifiers::SHIFT);
+
+    assert_eq!(
+        state.handle_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        KeyAction::Continue
+    );
+    assert!(clipboard.get_text().is_err());
+    assert!(state.kill_ring.is_empty());
+    assert_eq!(state.selection_start, None);
+    assert_eq!(state.buffer(), "hunter2");
+}
+
+#[test]
+fn test_ctrl_v_multiline_clipboard() {
+    let mut state = InputState::new(80, 24);
+    state.set_clipboard(MockClipboard::with_text("a\r\nb\nc"));
+
+    state.handle_key(KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "a\nb\nc");
+    assert_eq!(state.required_lines, 5);
+    assert_eq!(
+        state.required_lines,
+        calculate_required_lines(state.buffer(), state.cols)
+    );
+}
+
+#[test]
+fn test_ctrl_v_single_line_mode_drops_newlines() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::SingleLine);
+    state.set_clipboard(MockClipboard::with_text("a\nb"));
+
+    state.handle_key(KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "ab");
+}
+
+#[test]
+fn test_ctrl_v_with_unavailable_clipboard() {
+    let mut state = InputState::new(80, 24);
+    state.set_clipboard(Arc::new(MockClipboard::default()));
+
+    state.handle_key(KeyCode::Char('v'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "");
+}
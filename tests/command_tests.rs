@@ -0,0 +1,389 @@
+use std::io::Stdout;
+use std::sync::{Arc, Mutex};
+use termbox::commands::clear::{ClearCommand, clear_scroll_region};
+use termbox::commands::echo::EchoCommand;
+use termbox::commands::help::help_lines;
+use termbox::commands::progress::{ProgressCommand, parse_progress_args, progress_frames};
+use termbox::commands::table::{TableCommand, format_table};
+use termbox::commands::{CommandDispatcher, CommandHandler, CommandResult, parse_command_args};
+use termbox::ui::{self, BannerStyle, RuleStyle, draw_banner, draw_horizontal_rule, format_banner};
+use termbox::{InputState, capture_terminal_drawing};
+
+/// Records the arguments of every call
+struct MockCommand {
+    calls: Mutex<Vec<Vec<String>>>,
+}
+
+impl MockCommand {
+    fn new() -> Self {
+        Self {
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CommandHandler for MockCommand {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn description(&self) -> &str {
+        "A command used in tests"
+    }
+
+    async fn handle(
+        &self,
+        args: &[&str],
+        _state: &mut InputState,
+        _out: Arc<Mutex<Stdout>>,
+    ) -> anyhow::Result<CommandResult> {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        self.calls.lock().unwrap().push(args);
+        Ok(CommandResult::Handled { output_height: 2 })
+    }
+}
+
+fn stdout() -> Arc<Mutex<Stdout>> {
+    Arc::new(Mutex::new(std::io::stdout()))
+}
+
+#[tokio::test]
+async fn test_registered_command_is_dispatched_with_args() {
+    let mock = Arc::new(MockCommand::new());
+    let mut dispatcher = CommandDispatcher::new();
+    dispatcher.register(mock.clone());
+    let mut state = InputState::new(80, 24);
+
+    let result = dispatcher
+        .handle_command("mock one two", &mut state, stdout())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        result,
+        CommandResult::Handled { output_height: 2 }
+    ));
+    assert_eq!(*mock.calls.lock().unwrap(), vec![vec!["one", "two"]]);
+}
+
+#[tokio::test]
+async fn test_unknown_command_is_not_dispatched() {
+    let mock = Arc::new(MockCommand::new());
+    let mut dispatcher = CommandDispatcher::new();
+    dispatcher.register(mock.clone());
+    let mut state = InputState::new(80, 24);
+
+    let result = dispatcher
+        .handle_command("mockery", &mut state, stdout())
+        .await
+        .unwrap();
+
+    assert!(matches!(result, CommandResult::NotRecognized));
+    assert!(mock.calls.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_list_commands_includes_registered_commands() {
+    let mut dispatcher = CommandDispatcher::new();
+    dispatcher.register(Arc::new(MockCommand::new()));
+
+    let commands = dispatcher.list_commands();
+    assert!(commands.contains(&"mock"));
+    assert!(commands.contains(&"tiktok"));
+    assert!(commands.contains(&"spinner"));
+}
+
+fn parsed(input: &str) -> (String, Vec<String>) {
+    parse_command_args(input).unwrap()
+}
+
+#[test]
+fn test_parse_command_without_args() {
+    assert_eq!(parsed("  tiktok  "), ("tiktok".to_string(), vec![]));
+}
+
+#[test]
+fn test_parse_multiple_unquoted_args() {
+    let (name, args) = parsed("echo hello   big world");
+    assert_eq!(name, "echo");
+    assert_eq!(args, vec!["hello", "big", "world"]);
+}
+
+#[test]
+fn test_parse_quoted_args_with_spaces() {
+    let (_, args) = parsed("echo 'hello world' \"two  words\" ''");
+    assert_eq!(args, vec!["hello world", "two  words", ""]);
+}
+
+#[test]
+fn test_parse_escaped_quotes_inside_quotes() {
+    let (_, args) = parsed(r#"echo "say \"hi\"" 'it\'s' "back\\slash""#);
+    assert_eq!(args, vec![r#"say "hi""#, "it's", r"back\slash"]);
+}
+
+#[test]
+fn test_parse_unterminated_quote_is_an_error() {
+    assert!(parse_command_args("echo \"hello").is_err());
+    assert!(parse_command_args("echo 'hello").is_err());
+}
+
+#[tokio::test]
+async fn test_quoted_args_are_passed_to_handler() {
+    let mock = Arc::new(MockCommand::new());
+    let mut dispatcher = CommandDispatcher::new();
+    dispatcher.register(mock.clone());
+    let mut state = InputState::new(80, 24);
+
+    dispatcher
+        .handle_command("mock 'one two' three", &mut state, stdout())
+        .await
+        .unwrap();
+
+    assert_eq!(*mock.calls.lock().unwrap(), vec![vec!["one two", "three"]]);
+}
+
+#[test]
+fn test_help_lists_every_command() {
+    let mut dispatcher = CommandDispatcher::new();
+    dispatcher.register(Arc::new(MockCommand::new()));
+
+    let help = help_lines(&dispatcher, 80).join("\n");
+    for name in dispatcher.list_commands() {
+        assert!(help.contains(name), "help is missing {}", name);
+    }
+    // Descriptions line up after the longest name, "progress"
+    assert!(help.contains("  mock      A command used in tests"));
+    assert!(help.contains("Ctrl+R"));
+}
+
+#[test]
+fn test_help_wraps_to_terminal_width() {
+    let dispatcher = CommandDispatcher::new();
+
+    let lines = help_lines(&dispatcher, 30);
+    assert!(lines.iter().all(|line| line.chars().count() <= 30));
+    // Wrapped descriptions continue under the description column
+    assert!(lines.iter().any(|line| line.starts_with("           ")));
+}
+
+#[tokio::test]
+async fn test_echo_prints_joined_args() {
+    let mut state = InputState::new(80, 24);
+
+    let result = EchoCommand
+        .handle(&["hello", "world"], &mut state, stdout())
+        .await
+        .unwrap();
+
+    match result {
+        CommandResult::PrintOutput { text } => assert_eq!(text, "hello world"),
+        other => panic!("expected PrintOutput, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_clear_blanks_rows_above_frame() {
+    let state = InputState::new(4, 6);
+
+    let drawing =
+        capture_terminal_drawing(&state, |buffer| clear_scroll_region(buffer, &state)).unwrap();
+
+    // Rows 1-3 of 6 form the scroll region above the 3-line frame
+    assert_eq!(drawing, "\x1B[1;1H    \x1B[2;1H    \x1B[3;1H    ");
+}
+
+#[test]
+fn test_echo_and_clear_are_registered() {
+    let dispatcher = CommandDispatcher::new();
+    let commands = dispatcher.list_commands();
+    assert!(commands.contains(&EchoCommand.name()));
+    assert!(commands.contains(&ClearCommand.name()));
+}
+
+#[test]
+fn test_draw_horizontal_rule_styles() {
+    let mut buffer = Vec::new();
+    draw_horizontal_rule(&mut buffer, 4, RuleStyle::Double).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "\x1B[1G════");
+
+    assert_eq!(RuleStyle::Solid.line(3), "───");
+    assert_eq!(RuleStyle::Dotted.line(3), "···");
+}
+
+#[test]
+fn test_banner_centers_text() {
+    // 20 columns less " Title " leaves 13, one more on the right
+    assert_eq!(
+        format_banner("Title", 20, BannerStyle::Centered),
+        "────── Title ───────"
+    );
+    assert_eq!(
+        format_banner("Title", 20, BannerStyle::Solid),
+        "██████ Title ███████"
+    );
+    assert_eq!(
+        format_banner("Title", 11, BannerStyle::Boxed),
+        "┌─────────┐\n│  Title  │\n└─────────┘"
+    );
+    for style in [
+        BannerStyle::Solid,
+        BannerStyle::Boxed,
+        BannerStyle::Centered,
+    ] {
+        for line in format_banner("Title", 20, style).lines() {
+            assert_eq!(line.chars().count(), 20, "{style:?}");
+        }
+    }
+}
+
+#[test]
+fn test_banner_truncates_long_text() {
+    assert_eq!(
+        format_banner("a long banner title", 10, BannerStyle::Centered),
+        "─ a long ─"
+    );
+    assert_eq!(
+        format_banner("a long banner title", 10, BannerStyle::Boxed),
+        "┌────────┐\n│ a long │\n└────────┘"
+    );
+    // Too narrow for any text
+    assert_eq!(format_banner("title", 3, BannerStyle::Solid), "  █");
+}
+
+#[test]
+fn test_draw_banner_prints_at_cursor_row() {
+    let mut buffer = Vec::new();
+    draw_banner(&mut buffer, "Hi", 8, BannerStyle::Boxed).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "\x1B[1G┌──────┐\r\n│  Hi  │\r\n└──────┘"
+    );
+}
+
+#[test]
+fn test_rule_after_help_is_printed_above_frame() {
+    let dispatcher = CommandDispatcher::new();
+    let mut state = InputState::new(40, 12);
+    let mut buffer = Vec::new();
+
+    // What the dispatcher does with `PrintOutput` from help and a `PrintRule`
+    let help = help_lines(&dispatcher, state.cols).join("\n");
+    ui::print_to_scroll_region(&mut buffer, &help, &mut state).unwrap();
+    let printed = buffer.len();
+    ui::print_to_scroll_region(&mut buffer, &RuleStyle::Solid.line(state.cols), &mut state)
+        .unwrap();
+
+    // Row 9 of 12 is the last one above the 3-line frame
+    let rule = String::from_utf8(buffer[printed..].to_vec()).unwrap();
+    assert!(rule.starts_with(&format!("\x1B[9;1H{}\r\n", "─".repeat(40))));
+    assert_eq!(state.content.last_n(1), vec!["─".repeat(40)]);
+}
+
+#[test]
+fn test_progress_args_are_parsed() {
+    assert_eq!(
+        parse_progress_args(&["Downloading", "50"]),
+        Ok(("Downloading", 50))
+    );
+    assert!(parse_progress_args(&["Downloading", "many"]).is_err());
+    assert!(parse_progress_args(&["Downloading", "-1"]).is_err());
+    assert!(parse_progress_args(&["Downloading", "0"]).is_err());
+    assert!(parse_progress_args(&["Downloading"]).is_err());
+    assert!(parse_progress_args(&["a", "1", "extra"]).is_err());
+}
+
+#[test]
+fn test_progress_frames() {
+    let frames = progress_frames("Copy", 4);
+    assert_eq!(
+        frames,
+        vec![
+            "Copy [██░░░░░░░░] 1/4",
+            "Copy [█████░░░░░] 2/4",
+            "Copy [███████░░░] 3/4",
+            "Copy [██████████] 4/4",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_progress_with_non_numeric_total_is_an_error() {
+    let mut state = InputState::new(80, 24);
+
+    let result = ProgressCommand::default()
+        .handle(&["Downloading", "fifty"], &mut state, stdout())
+        .await
+        .unwrap();
+
+    match result {
+        CommandResult::Error(message) => assert!(message.contains("\"fifty\"")),
+        other => panic!("expected Error, got {:?}", other),
+    }
+    assert!(state.active_animation.is_none());
+    assert!(
+        CommandDispatcher::new()
+            .list_commands()
+            .contains(&"progress")
+    );
+}
+
+#[test]
+fn test_format_table_aligns_columns() {
+    let rows = vec![vec!["a.txt", "12"], vec!["long-name.rs", "3"]];
+    assert_eq!(
+        format_table(&["name", "size"], &rows, 80),
+        vec![
+            " name         │ size",
+            "──────────────┼──────",
+            " a.txt        │ 12",
+            " long-name.rs │ 3",
+        ]
+    );
+}
+
+#[test]
+fn test_format_table_truncates_wide_cells() {
+    // 20 columns over 2 columns leaves 7 for each cell
+    let rows = vec![vec!["abcdefghij", "x"]];
+    assert_eq!(
+        format_table(&["k", "v"], &rows, 20),
+        vec![" k       │ v", "─────────┼───", " abcdef… │ x"]
+    );
+    for line in format_table(&["k", "v"], &rows, 20) {
+        assert!(line.chars().count() <= 20);
+    }
+}
+
+#[test]
+fn test_format_table_with_missing_and_empty_rows() {
+    assert_eq!(
+        format_table(&["a", "b"], &[], 80),
+        vec![" a │ b", "───┼───"]
+    );
+    assert_eq!(
+        format_table(&["a", "b"], &[vec![], vec!["1"], vec!["1", "2", "3"]], 80),
+        vec![" a │ b", "───┼───", "   │", " 1 │", " 1 │ 2"]
+    );
+    assert!(format_table(&[], &[vec!["x"]], 80).is_empty());
+}
+
+#[tokio::test]
+async fn test_table_command_prints_table() {
+    let mut state = InputState::new(80, 24);
+
+    let result = TableCommand
+        .handle(&["n,v", "x,1"], &mut state, stdout())
+        .await
+        .unwrap();
+    match result {
+        CommandResult::PrintOutput { text } => assert_eq!(text, " n │ v\n───┼───\n x │ 1"),
+        other => panic!("expected PrintOutput, got {:?}", other),
+    }
+
+    let result = TableCommand
+        .handle(&[], &mut state, stdout())
+        .await
+        .unwrap();
+    assert!(matches!(result, CommandResult::Error(_)));
+}
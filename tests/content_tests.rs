@@ -0,0 +1,96 @@
+use termbox::content::{ContentArea, ContentBuffer, DEFAULT_MAX_CONTENT_LINES};
+use termbox::{FramePosition, InputState, ScrollEvent};
+use tokio::sync::broadcast;
+
+#[test]
+fn test_last_n_returns_most_recent_lines_oldest_first() {
+    let mut content = ContentBuffer::default();
+    content.push_text("one\ntwo\nthree");
+
+    assert_eq!(content.last_n(2), vec!["two", "three"]);
+    assert_eq!(content.last_n(10), vec!["one", "two", "three"]);
+    assert!(content.last_n(0).is_empty());
+}
+
+#[test]
+fn test_oldest_lines_dropped_past_max_lines() {
+    let mut content = ContentBuffer::new(3);
+    for i in 0..5 {
+        content.push_line(i.to_string());
+    }
+
+    assert_eq!(content.len(), 3);
+    assert_eq!(content.lines().collect::<Vec<_>>(), vec!["2", "3", "4"]);
+}
+
+#[test]
+fn test_iter_numbers_lines_in_submission_order() {
+    let mut content = ContentBuffer::new(2);
+    content.push_text("one\ntwo");
+    content.push_line("three");
+
+    let lines: Vec<_> = content
+        .iter()
+        .map(|line| (line.line_number, line.text.as_str()))
+        .collect();
+    // Numbering carries on past the dropped first line
+    assert_eq!(lines, vec![(2, "two"), (3, "three")]);
+}
+
+#[test]
+fn test_clear_and_default() {
+    let mut content = ContentBuffer::default();
+    assert_eq!(content.max_lines(), DEFAULT_MAX_CONTENT_LINES);
+
+    content.push_text("a\n\nb");
+    assert_eq!(content.len(), 3); // the empty line is kept
+    content.clear();
+    assert!(content.is_empty());
+}
+
+#[test]
+fn test_content_area_print_scrolls_and_reports() {
+    let (tx, mut rx) = broadcast::channel(4);
+    let mut area = ContentArea::new(10, 8, 3);
+    area.scroll_broadcast = Some(tx);
+    let mut out = Vec::new();
+
+    // "0123456789ab" wraps onto a second row
+    assert_eq!(area.print(&mut out, "0123456789ab\nx").unwrap(), 3);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "\x1B[5;1H0123456789ab\r\nx\r\n"
+    );
+    assert!(matches!(rx.try_recv(), Ok(ScrollEvent::ScrolledUp(3))));
+}
+
+#[test]
+fn test_content_area_clear_and_resize() {
+    let mut area = ContentArea::new(4, 6, 2);
+    area.top_reserved = 1;
+    assert_eq!(area.region(), 1..4);
+
+    let mut out = Vec::new();
+    area.clear(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "\x1B[2;1H    \x1B[3;1H    \x1B[4;1H    "
+    );
+
+    // The reserved rows stay reserved at the new size
+    let mut out = Vec::new();
+    area.resize(&mut out, 10, 20).unwrap();
+    assert_eq!(area.region(), 1..8);
+    assert_eq!(String::from_utf8(out).unwrap(), "\x1B[2;8r");
+}
+
+#[test]
+fn test_content_area_follows_frame() {
+    let mut state = InputState::new(20, 10);
+    assert_eq!(state.content_area().region(), 0..7);
+
+    state.frame_position = FramePosition::Top;
+    let area = state.content_area();
+    assert_eq!((area.top_reserved, area.bottom_reserved), (3, 0));
+    assert_eq!(area.region(), state.scroll_region());
+}
@@ -0,0 +1,82 @@
+use termbox::ffi::*;
+
+/// Reads a string through one of the copy-out functions, sizing the buffer
+/// with a first call
+fn read(
+    copy: unsafe extern "C" fn(*const TermboxState, *mut u8, usize) -> usize,
+    state: *const TermboxState,
+) -> String {
+    unsafe {
+        let len = copy(state, std::ptr::null_mut(), 0);
+        let mut bytes = vec![0u8; len];
+        assert_eq!(copy(state, bytes.as_mut_ptr(), bytes.len()), len);
+        String::from_utf8(bytes).unwrap()
+    }
+}
+
+#[test]
+fn test_ffi_round_trip() {
+    let state = termbox_new(40, 10);
+    unsafe {
+        for c in "hé世".chars() {
+            assert_eq!(
+                termbox_handle_key(state, c as u32, 0),
+                TERMBOX_ACTION_CONTINUE
+            );
+        }
+        termbox_handle_key(state, TERMBOX_KEY_LEFT, 0);
+        termbox_handle_key(state, TERMBOX_KEY_BACKSPACE, 0);
+        assert_eq!(read(termbox_get_buffer, state), "h世");
+
+        assert_eq!(
+            termbox_handle_key(state, TERMBOX_KEY_ENTER, 0),
+            TERMBOX_ACTION_SUBMIT
+        );
+        assert_eq!(read(termbox_get_buffer, state), "");
+        assert_eq!(read(termbox_get_submitted, state), "h世");
+
+        // The next submission replaces it
+        termbox_handle_key(state, 'x' as u32, 0);
+        termbox_handle_key(state, TERMBOX_KEY_ENTER, 0);
+        assert_eq!(read(termbox_get_submitted, state), "x");
+
+        assert_eq!(
+            termbox_handle_key(state, 'l' as u32, TERMBOX_MOD_CONTROL),
+            TERMBOX_ACTION_CLEAR_SCREEN
+        );
+        assert_eq!(
+            termbox_handle_key(state, TERMBOX_KEY_ESC, 0),
+            TERMBOX_ACTION_EXIT
+        );
+        termbox_free(state);
+    }
+}
+
+#[test]
+fn test_ffi_truncates_copies_and_ignores_null() {
+    let state = termbox_new(40, 10);
+    unsafe {
+        for c in "hello".chars() {
+            termbox_handle_key(state, c as u32, 0);
+        }
+        let mut bytes = [0u8; 3];
+        assert_eq!(termbox_get_buffer(state, bytes.as_mut_ptr(), 3), 5);
+        assert_eq!(&bytes, b"hel");
+        assert_eq!(read(termbox_get_submitted, state), "");
+
+        // Surrogates are not characters and are ignored
+        termbox_handle_key(state, 0xD800, 0);
+        assert_eq!(read(termbox_get_buffer, state), "hello");
+
+        assert_eq!(
+            termbox_handle_key(std::ptr::null_mut(), 'a' as u32, 0),
+            TERMBOX_ACTION_CONTINUE
+        );
+        assert_eq!(
+            termbox_get_buffer(std::ptr::null(), bytes.as_mut_ptr(), 3),
+            0
+        );
+        termbox_free(std::ptr::null_mut());
+        termbox_free(state);
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use termbox::format::{OutputFormatter, PrefixFormatter};
+use termbox::prelude::*;
+use termbox::ui;
+
+fn quote() -> PrefixFormatter {
+    PrefixFormatter {
+        prefix: ">> ".to_string(),
+    }
+}
+
+#[test]
+fn test_prefix_formatter_prefixes_every_line() {
+    assert_eq!(quote().format("one\n\ntwo", 80), ">> one\n>> \n>> two");
+    // Quoting already quoted text doubles the `>`
+    assert_eq!(quote().format(">> a\n>> b", 80), ">> >> a\n>> >> b");
+}
+
+#[test]
+fn test_print_to_scroll_region_applies_formatter() {
+    let mut state = InputState::new(10, 8);
+    state.set_output_formatter(Arc::new(quote()));
+    let mut buffer = Vec::new();
+
+    // "> 0123456" wraps once the prefix is added
+    let lines = ui::print_to_scroll_region(&mut buffer, "hi\n> 0123456", &mut state).unwrap();
+    // Blank rows reserved for an animation are left alone
+    ui::print_to_scroll_region(&mut buffer, "\n", &mut state).unwrap();
+
+    assert_eq!(lines, 3);
+    assert_eq!(
+        state.content.last_n(5),
+        vec![">> hi", ">> > 0123456", "", ""]
+    );
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains(">> hi\r\n>> > 0123456\r\n"));
+}
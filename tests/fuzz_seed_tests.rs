@@ -0,0 +1,45 @@
+//! Runs the checks of the `layout` fuzz target over the seeds in
+//! `tests/fuzz_seeds`, so known crashers stay fixed without cargo-fuzz.
+
+use std::fs;
+use termbox::{
+    FRAME_CHARS, calculate_cursor_position, calculate_required_lines, render_text_lines,
+};
+
+fn seeds() -> Vec<(String, String)> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fuzz_seeds");
+    let mut seeds: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect();
+    seeds.sort();
+    seeds
+}
+
+#[test]
+fn test_fuzz_seeds_layout_invariants() {
+    let seeds = seeds();
+    assert!(seeds.len() >= 3);
+
+    for (name, text) in &seeds {
+        for cols in (0..=12).chain([20, 80, u16::MAX as usize]) {
+            let lines = calculate_required_lines(text, cols);
+            assert!(lines >= 3, "{name}: {lines} lines at {cols} columns");
+            if cols > FRAME_CHARS {
+                assert_eq!(
+                    lines,
+                    render_text_lines(text, cols).len(),
+                    "{name} at {cols} columns"
+                );
+            }
+
+            for (rows, required_lines) in [(0, 0), (3, lines), (24, 3), (u16::MAX as usize, 0)] {
+                calculate_cursor_position(text, cols, rows, required_lines);
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::style::Color;
+use termbox::grid::{TerminalCell, TerminalGrid, WIDE_CONTINUATION, diff_and_draw};
+use termbox::{
+    Config, InputMode, InputState, PromptOptions, build_prompt_grid, draw_prompt_diff_to_buffer,
+    render_padded_text_lines, render_text_lines, to_strings,
+};
+
+fn diff(old: &TerminalGrid, new: &TerminalGrid) -> String {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    diff_and_draw(&mut buffer, old, new).unwrap();
+    String::from_utf8(buffer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_single_changed_cell_emits_one_move() {
+    let old = build_prompt_grid("hello", (20, 10), 3, &PromptOptions::default());
+    let new = build_prompt_grid("hellp", (20, 10), 3, &PromptOptions::default());
+
+    let drawing = diff(&old, &new);
+
+    assert_eq!(drawing, "\x1B[9;9Hp");
+    assert_eq!(drawing.matches("\x1B[").count(), 1);
+}
+
+#[test]
+fn test_identical_grids_draw_nothing() {
+    let grid = build_prompt_grid("hello", (20, 10), 3, &PromptOptions::default());
+    assert_eq!(diff(&grid, &grid.clone()), "");
+}
+
+#[test]
+fn test_adjacent_changes_share_one_move_and_colors_are_reset() {
+    let old = TerminalGrid::new(5, 1);
+    let mut new = TerminalGrid::new(5, 1);
+    let red = TerminalCell {
+        fg: Color::Red,
+        ..Default::default()
+    };
+    new.put_styled(1, 0, "ab", red);
+
+    assert_eq!(diff(&old, &new), "\x1B[1;2H\x1B[38;5;9mab\x1B[0m");
+}
+
+#[test]
+fn test_wide_character_takes_two_cells() {
+    let old = TerminalGrid::new(4, 1);
+    let mut new = TerminalGrid::new(4, 1);
+    assert_eq!(new.put_str(0, 0, "世a"), 3);
+    assert_eq!(new.cells[0][1].ch, WIDE_CONTINUATION);
+    assert_eq!(new.cells[0][2].ch, 'a');
+
+    // The continuation cell is covered by the character, not printed
+    assert_eq!(diff(&old, &new), "\x1B[1;1H世a");
+}
+
+#[test]
+fn test_grids_of_different_size_are_drawn_in_full() {
+    let old = TerminalGrid::new(3, 1);
+    let new = TerminalGrid::new(2, 1);
+    assert_eq!(diff(&old, &new), "\x1B[1;1H  ");
+}
+
+#[test]
+fn test_prompt_grid_taller_than_terminal_is_cut_off() {
+    let mut state = InputState::new(20, 5);
+    state.set_mode(InputMode::MultiLine);
+    state.handle_paste("1\n2\n3\n4\n5\n6\n7\n8");
+    let dimensions = (state.cols, state.rows);
+    let options = state.prompt_options();
+
+    let grid = build_prompt_grid(
+        &state.display_text(),
+        dimensions,
+        state.required_lines,
+        &options,
+    );
+    let frame: Vec<String> = grid
+        .cells
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.ch).collect())
+        .collect();
+    let rendered = to_strings(&render_text_lines(&state.display_text(), 20));
+    // Scrolled to the cursor on the last line: 3 of 8 lines fit, with the
+    // scrollbar thumb over the right border of the last row
+    assert_eq!(frame[0], rendered[0]);
+    assert_eq!(frame[1..3], rendered[6..8]);
+    assert_eq!(frame[3], "│   8              ▐");
+    assert_eq!(frame[4], rendered[9]);
+
+    // The diff renderer builds the same grid
+    let mut buffer = Vec::new();
+    let drawn = draw_prompt_diff_to_buffer(
+        &mut buffer,
+        &state.display_text(),
+        dimensions,
+        state.required_lines,
+        &options,
+        Some(&grid),
+    )
+    .unwrap();
+    assert_eq!(drawn, grid);
+}
+
+#[test]
+fn test_prompt_grid_matches_rendered_frame() {
+    let grid = build_prompt_grid("hi", (20, 10), 3, &PromptOptions::default());
+    let frame: Vec<String> = grid.cells[7..]
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.ch).collect())
+        .collect();
+    assert_eq!(frame, to_strings(&render_text_lines("hi", 20)));
+}
+
+#[test]
+fn test_diff_draw_repositions_cursor_after_changes() {
+    let options = PromptOptions::default();
+    let prev = build_prompt_grid("hi", (20, 10), 3, &options);
+
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let grid =
+        draw_prompt_diff_to_buffer(&mut buffer, "hi!", (20, 10), 3, &options, Some(&prev)).unwrap();
+    let drawing = String::from_utf8(buffer.into_inner()).unwrap();
+
+    assert_eq!(drawing, "\x1B[9;7H!\x1B[9;8H");
+    assert_eq!(grid, build_prompt_grid("hi!", (20, 10), 3, &options));
+}
+
+#[test]
+fn test_wrap_indicator_marks_wrapped_rows() {
+    let options = PromptOptions {
+        wrap_indicator: Some('↩'),
+        ..Default::default()
+    };
+    // 6 columns of text in 12, the 7th holds the indicator
+    let grid = build_prompt_grid("abcdefghijklmnop\nxy", (12, 10), 6, &options);
+    let frame: Vec<String> = grid.cells[4..]
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.ch).collect())
+        .collect();
+    assert_eq!(
+        frame,
+        vec![
+            "╭──────────╮",
+            "│ > abcdef↩│",
+            "│   ghijkl↩│",
+            "│   mnop   │",
+            "│   xy     │",
+            "╰──────────╯",
+        ]
+    );
+}
+
+#[test]
+fn test_wrap_indicator_narrows_input_layout() {
+    let mut config = Config::default();
+    config.wrap_indicator = Some('↩');
+    let mut state = InputState::with_config(12, 10, config);
+    for c in "abcdefghijklmnop".chars() {
+        state.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+    }
+
+    // Rows of 6 columns instead of 7
+    assert_eq!(state.visual_lines(), vec!["abcdef", "ghijkl", "mnop"]);
+    assert_eq!(state.required_lines, 5);
+    assert_eq!(state.cursor_position(), (4 + 4, 8));
+}
+
+#[test]
+fn test_frame_padding_insets_text() {
+    let options = PromptOptions {
+        frame_padding: 1,
+        ..Default::default()
+    };
+    // 5 columns of text in 12, with a blank column inside each border
+    let grid = build_prompt_grid("abcdefg", (12, 10), 4, &options);
+    let frame: Vec<String> = grid.cells[6..]
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.ch).collect())
+        .collect();
+    assert_eq!(
+        frame,
+        vec![
+            "╭──────────╮",
+            "│  > abcde │",
+            "│    fg    │",
+            "╰──────────╯",
+        ]
+    );
+    assert_eq!(
+        to_strings(&render_padded_text_lines("abcdefg", 12, 1)),
+        frame
+    );
+}
+
+#[test]
+fn test_frame_padding_narrows_input_layout() {
+    let mut config = Config::default();
+    config.frame_padding = 2;
+    let mut state = InputState::with_config(20, 10, config);
+    // 20 columns less the frame and two columns of padding on each side
+    assert_eq!(state.content_width(), 20 - 5 - 4);
+
+    for c in "abcdefghijklmnop".chars() {
+        state.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+    }
+    assert_eq!(state.visual_lines(), vec!["abcdefghijk", "lmnop"]);
+    assert_eq!(state.required_lines, 4);
+    assert_eq!(state.cursor_position(), (2 + 4 + 5, 8));
+    assert_eq!(state.visual_cursor_pos(), (1, 5));
+
+    // Clicking the "c" drawn after the padding
+    assert!(state.handle_click(2 + 4 + 2, 7));
+    assert_eq!(state.cursor_byte_offset, 2);
+
+    // Nothing is drawn once the padding leaves no room for text
+    state.handle_resize(5 + 4, 10);
+    assert!(state.too_narrow());
+}
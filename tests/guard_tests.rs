@@ -0,0 +1,128 @@
+use std::io::Write;
+use std::panic;
+use std::sync::{Arc, Mutex};
+use termbox::animation::AnimationRegistry;
+use termbox::guard::{TermboxGuard, set_panic_hook_with_writer};
+
+/// Writer that stays readable after the guard owning it is dropped
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[test]
+fn test_guard_restores_terminal_on_drop() {
+    let buffer = SharedBuffer::default();
+    drop(TermboxGuard::with_writer(80, 24, buffer.clone()));
+
+    // Scroll region reset, bracketed paste off, cursor shown and on the last row
+    assert_eq!(
+        buffer.contents(),
+        "\x1B[r\x1B[?2004l\x1B[?1006l\x1B[?1015l\x1B[?1003l\x1B[?1002l\x1B[?1000l\x1B[?25h\x1B[24;1H"
+    );
+}
+
+#[test]
+fn test_guard_restores_terminal_after_panic() {
+    let buffer = SharedBuffer::default();
+
+    let result = panic::catch_unwind({
+        let buffer = buffer.clone();
+        move || {
+            let _guard = TermboxGuard::with_writer(80, 24, buffer);
+            panic!("crash while the terminal is in raw mode");
+        }
+    });
+
+    assert!(result.is_err());
+    assert!(buffer.contents().contains("\x1B[r"));
+    assert!(buffer.contents().contains("\x1B[?25h"));
+    assert!(!crossterm::terminal::is_raw_mode_enabled().unwrap());
+}
+
+#[test]
+fn test_panic_hook_restores_terminal() {
+    let buffer = SharedBuffer::default();
+    set_panic_hook_with_writer(buffer.clone());
+
+    // No guard: only the hook can restore the terminal
+    let result = panic::catch_unwind(|| panic!("crash without a guard"));
+
+    assert!(result.is_err());
+    assert!(
+        buffer
+            .contents()
+            .starts_with("\x1B[r\x1B[?2004l\x1B[?1006l")
+    );
+    assert!(buffer.contents().ends_with("\x1B[?25h"));
+    assert!(!crossterm::terminal::is_raw_mode_enabled().unwrap());
+}
+
+#[test]
+fn test_guard_uses_updated_size() {
+    let buffer = SharedBuffer::default();
+    let mut guard = TermboxGuard::with_writer(80, 24, buffer.clone());
+    guard.set_size(100, 40);
+    drop(guard);
+
+    assert!(buffer.contents().ends_with("\x1B[40;1H"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_guard_cancels_animations_on_drop() {
+    let animations = AnimationRegistry::new();
+    animations.spawn(tokio::time::sleep(std::time::Duration::from_secs(60)));
+    let mut guard = TermboxGuard::with_writer(80, 24, SharedBuffer::default());
+    guard.cancel_animations_on_drop(animations.clone());
+    assert_eq!(animations.active_count(), 1);
+
+    drop(guard);
+    assert_eq!(animations.active_count(), 0);
+}
+
+/// Raw mode transitions made by `record_raw_mode`
+static RAW_MODE_CALLS: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+
+fn record_raw_mode(enabled: bool) -> std::io::Result<()> {
+    RAW_MODE_CALLS.lock().unwrap().push(enabled);
+    Ok(())
+}
+
+#[test]
+fn test_guard_suspend_and_resume() {
+    let buffer = SharedBuffer::default();
+    let mut guard = TermboxGuard::with_writer(80, 24, buffer.clone());
+    guard.set_raw_mode_switch(record_raw_mode);
+
+    guard.suspend().unwrap();
+    assert!(guard.is_suspended());
+    assert!(buffer.contents().starts_with("\x1B[r"));
+    assert!(buffer.contents().ends_with("\x1B[?25h\x1B[24;1H"));
+    assert_eq!(*RAW_MODE_CALLS.lock().unwrap(), vec![false]);
+
+    let suspended_len = buffer.contents().len();
+    guard.resume().unwrap();
+    assert!(!guard.is_suspended());
+    // Bracketed paste and mouse capture are turned back on
+    let resumed = buffer.contents()[suspended_len..].to_string();
+    assert!(resumed.starts_with("\x1B[?2004h"));
+    assert!(resumed.contains("\x1B[?1000h"));
+    assert_eq!(*RAW_MODE_CALLS.lock().unwrap(), vec![false, true]);
+
+    drop(guard);
+    assert_eq!(*RAW_MODE_CALLS.lock().unwrap(), vec![false, true, false]);
+}
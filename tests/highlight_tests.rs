@@ -0,0 +1,87 @@
+use crossterm::style::Color;
+use std::sync::Arc;
+use termbox::highlight::{RegexHighlighter, Style, SyntaxHighlighter};
+use termbox::{
+    InputMode, InputState, build_prompt_grid, capture_terminal_drawing, draw_prompt_to_buffer,
+};
+
+fn red() -> Style {
+    Style {
+        fg: Some(Color::Red),
+        ..Default::default()
+    }
+}
+
+fn state_with(text: &str) -> InputState {
+    let mut state = InputState::new(20, 10);
+    *state.buffer_mut() = text.to_string();
+    state.cursor_byte_offset = text.len();
+    state.set_highlighter(Arc::new(
+        RegexHighlighter::new().with_rule(r"\d+", red()).unwrap(),
+    ));
+    state
+}
+
+fn draw(state: &InputState) -> String {
+    capture_terminal_drawing(state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_regex_highlighter_returns_match_ranges() {
+    let highlighter = RegexHighlighter::new().with_rule(r"\d+", red()).unwrap();
+    assert_eq!(
+        highlighter.highlight("a 12 b 345"),
+        vec![(2..4, red()), (7..10, red())]
+    );
+}
+
+#[test]
+fn test_invalid_pattern_is_an_error() {
+    assert!(RegexHighlighter::new().with_rule("(", red()).is_err());
+}
+
+#[test]
+fn test_matching_text_is_drawn_in_color() {
+    let drawing = draw(&state_with("go 42 now"));
+    assert!(
+        drawing.contains("│ > go \x1B[38;5;9m42\x1B[0m now"),
+        "{:?}",
+        drawing
+    );
+}
+
+#[test]
+fn test_text_without_matches_has_no_color_codes() {
+    let drawing = draw(&state_with("no digits"));
+    assert!(!drawing.contains("\x1B[38;"), "{:?}", drawing);
+}
+
+#[test]
+fn test_password_mode_is_not_highlighted() {
+    let mut state = state_with("1234");
+    state.set_mode(InputMode::Password);
+    assert!(state.prompt_options().highlights.is_empty());
+}
+
+#[test]
+fn test_highlight_styles_reach_the_grid() {
+    let state = state_with("x 7");
+    let grid = build_prompt_grid(
+        state.buffer(),
+        (state.cols, state.rows),
+        state.required_lines,
+        &state.prompt_options(),
+    );
+    // "│ > " then "x 7": the digit is the seventh cell of the text row
+    assert_eq!(grid.cells[8][6].fg, Color::Red);
+    assert_eq!(grid.cells[8][4].fg, Color::Reset);
+}
@@ -1,8 +1,30 @@
-use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::Color;
+use std::sync::Arc;
+use std::time::Duration;
+use termbox::prelude::*;
 use termbox::{
-    InputState, KeyAction, calculate_cursor_position, calculate_required_lines, render_text_lines,
+    BufferEdit, DOUBLE_CLICK_INTERVAL, MAX_LINES_INDICATOR_DURATION, RTL_WARNING, SubmitAction,
+    TabCompletion, TextMetrics, WrapCache, draw_prompt_to_buffer, expand_tabs, find_line_end,
+    find_line_start, find_word_at_visual_pos, find_word_boundary_left, find_word_boundary_right,
+    leading_whitespace, visual_pos_to_byte_offset, zeroize,
 };
 
+/// Completer that ignores the prefix and returns a fixed list of candidates
+struct MockCompleter(Vec<&'static str>);
+
+impl TabCompletion for MockCompleter {
+    fn complete(&self, _prefix: &str) -> Vec<String> {
+        self.0.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+fn type_text(state: &mut InputState, text: &str) {
+    for ch in text.chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+}
+
 #[test]
 fn test_simple_short_input() {
     let mut state = InputState::new(80, 24);
@@ -30,19 +52,19 @@ fn test_simple_short_input() {
     );
 
     // Check the buffer contains expected text
-    assert_eq!(state.buffer, "hello");
+    assert_eq!(state.buffer(), "hello");
     assert_eq!(state.required_lines, 3); // minimum frame size
 
     // Check rendering
-    let lines = render_text_lines(&state.buffer, state.cols);
+    let lines = render_text_lines(state.buffer(), state.cols);
     assert_eq!(lines.len(), 3);
-    assert!(lines[0].starts_with("╭"));
-    assert!(lines[1].contains("> hello"));
-    assert!(lines[2].starts_with("╰"));
+    assert!(lines[0].content.starts_with("╭"));
+    assert!(lines[1].content.contains("> hello"));
+    assert!(lines[2].content.starts_with("╰"));
 
     // Check cursor position
     let (cursor_col, cursor_row) =
-        calculate_cursor_position(&state.buffer, state.cols, state.rows, state.required_lines);
+        calculate_cursor_position(state.buffer(), state.cols, state.rows, state.required_lines);
     assert_eq!(cursor_col, 9); // "│ > hello" = 4 + 5 = 9
     assert_eq!(cursor_row, state.rows - state.required_lines + 1); // First content row
 }
@@ -130,21 +152,24 @@ fn test_multiline_input() {
     );
 
     // Check the buffer contains multiline text
-    assert_eq!(state.buffer, "line1\nline2\nline3");
+    assert_eq!(state.buffer(), "line1\nline2\nline3");
     assert_eq!(state.required_lines, 5); // 3 text lines + 2 borders
 
     // Check rendering
-    let lines = render_text_lines(&state.buffer, state.cols);
+    let lines = render_text_lines(state.buffer(), state.cols);
     assert_eq!(lines.len(), 5);
-    assert!(lines[0].starts_with("╭"));
-    assert!(lines[1].contains("> line1"));
-    assert!(lines[2].contains("  line2")); // continuation line
-    assert!(lines[3].contains("  line3")); // continuation line
-    assert!(lines[4].starts_with("╰"));
+    assert!(lines[0].content.starts_with("╭"));
+    assert!(lines[1].content.contains("> line1"));
+    assert!(lines[2].content.contains("  line2")); // continuation line
+    assert!(lines[3].content.contains("  line3")); // continuation line
+    assert!(lines[4].content.starts_with("╰"));
+    // Each row starts a logical line of its own
+    assert_eq!(lines[3].source_logical_line, 2);
+    assert!(lines.iter().all(|line| !line.is_continuation));
 
     // Check cursor position (should be at end of last line)
     let (cursor_col, cursor_row) =
-        calculate_cursor_position(&state.buffer, state.cols, state.rows, state.required_lines);
+        calculate_cursor_position(state.buffer(), state.cols, state.rows, state.required_lines);
     assert_eq!(cursor_col, 9); // "│   line3" = 4 + 5 = 9
     assert_eq!(cursor_row, state.rows - state.required_lines + 3); // Third content row
 }
@@ -162,20 +187,24 @@ fn test_long_line_wrapping() {
         );
     }
 
-    assert_eq!(state.buffer, long_text);
+    assert_eq!(state.buffer(), long_text);
 
     // Calculate expected lines - based on debug output, it's actually 6 lines total
     let expected_lines = 6; // 4 content lines + 2 borders
     assert_eq!(state.required_lines, expected_lines);
 
-    let lines = render_text_lines(&state.buffer, state.cols);
+    let lines = render_text_lines(state.buffer(), state.cols);
     assert_eq!(lines.len(), expected_lines);
 
     // Check the actual content from debug output
-    assert!(lines[1].contains("> This is a very"));
-    assert!(lines[2].contains("   long line that"));
-    assert!(lines[3].contains("   should wrap aro"));
-    assert!(lines[4].contains("   und"));
+    assert!(lines[1].content.contains("> This is a very"));
+    assert!(lines[2].content.contains("   long line that"));
+    assert!(lines[3].content.contains("   should wrap aro"));
+    assert!(lines[4].content.contains("   und"));
+    assert!(!lines[1].is_continuation);
+    assert!(lines[2..5].iter().all(|line| line.is_continuation));
+    assert_eq!(lines[4].source_logical_line, 0);
+    assert_eq!(lines[4].visual_line_in_source, 3);
 }
 
 #[test]
@@ -203,14 +232,14 @@ fn test_backspace_functionality() {
         state.handle_key(KeyCode::Char('o'), KeyModifiers::NONE),
         KeyAction::Continue
     );
-    assert_eq!(state.buffer, "hello");
+    assert_eq!(state.buffer(), "hello");
 
     // Backspace once
     assert_eq!(
         state.handle_key(KeyCode::Backspace, KeyModifiers::NONE),
         KeyAction::Continue
     );
-    assert_eq!(state.buffer, "hell");
+    assert_eq!(state.buffer(), "hell");
 
     // Backspace all characters
     assert_eq!(
@@ -229,7 +258,7 @@ fn test_backspace_functionality() {
         state.handle_key(KeyCode::Backspace, KeyModifiers::NONE),
         KeyAction::Continue
     );
-    assert_eq!(state.buffer, "");
+    assert_eq!(state.buffer(), "");
     assert_eq!(state.required_lines, 3); // Back to minimum
 
     // Backspace on empty buffer should not crash
@@ -237,7 +266,7 @@ fn test_backspace_functionality() {
         state.handle_key(KeyCode::Backspace, KeyModifiers::NONE),
         KeyAction::Continue
     );
-    assert_eq!(state.buffer, "");
+    assert_eq!(state.buffer(), "");
 }
 
 #[test]
@@ -271,7 +300,7 @@ fn test_exit_scenarios() {
         state.handle_key(KeyCode::Char('d'), KeyModifiers::NONE),
         KeyAction::Continue
     );
-    assert_eq!(state.buffer, "cd");
+    assert_eq!(state.buffer(), "cd");
 }
 
 #[test]
@@ -304,6 +333,24 @@ fn test_terminal_resize() {
     assert!(state.required_lines <= narrow_required_lines);
 }
 
+#[test]
+fn test_handle_resize_reports_needed_redraw() {
+    let mut state = InputState::with_initial_text(20, 10, "a line that wraps once");
+    state.prev_grid = Some(termbox::grid::TerminalGrid::new(20, 10));
+
+    assert_eq!(state.handle_resize(20, 10), ResizeAction::NoChange);
+    assert!(state.prev_grid.is_some());
+
+    assert_eq!(state.handle_resize(20, 14), ResizeAction::RegionOnly);
+    assert_eq!(state.rows, 14);
+    assert!(state.prev_grid.is_none());
+
+    // The text no longer wraps, so the frame shrinks
+    assert_eq!(state.handle_resize(40, 14), ResizeAction::FullRedraw);
+    // Same frame height, but the width changed
+    assert_eq!(state.handle_resize(41, 14), ResizeAction::FullRedraw);
+}
+
 #[test]
 fn test_empty_lines_in_multiline() {
     let mut state = InputState::new(80, 24);
@@ -320,14 +367,14 @@ fn test_empty_lines_in_multiline() {
     // Type third line
     state.handle_key(KeyCode::Char('b'), KeyModifiers::NONE);
 
-    assert_eq!(state.buffer, "a\n\nb");
+    assert_eq!(state.buffer(), "a\n\nb");
     assert_eq!(state.required_lines, 5); // 3 content lines + 2 borders
 
-    let lines = render_text_lines(&state.buffer, state.cols);
+    let lines = render_text_lines(state.buffer(), state.cols);
     assert_eq!(lines.len(), 5);
-    assert!(lines[1].contains("> a"));
-    assert!(lines[2].contains("  ")); // empty line
-    assert!(lines[3].contains("  b"));
+    assert!(lines[1].content.contains("> a"));
+    assert!(lines[2].content.contains("  ")); // empty line
+    assert!(lines[3].content.contains("  b"));
 }
 
 #[test]
@@ -369,9 +416,51 @@ fn test_cursor_position_calculations() {
     assert_eq!(col, 13); // Based on debug output: "long line" = 9 chars + 4 prefix = 13
 }
 
+#[test]
+fn test_cursor_position_counts_wide_characters_as_two_columns() {
+    let mut state = InputState::new(20, 24);
+    type_text(&mut state, "Hi 世界");
+
+    let (col, _row) =
+        calculate_cursor_position(state.buffer(), state.cols, state.rows, state.required_lines);
+    // "│ > " prefix, "Hi " and two characters two columns wide
+    assert_eq!(col, 4 + 3 + 2 + 2);
+}
+
+#[test]
+fn test_wide_characters_wrap_by_columns() {
+    let mut state = InputState::new(20, 24);
+    // Eight two-column characters in 15 columns: seven fit on the first row
+    type_text(&mut state, &"世".repeat(8));
+
+    assert_eq!(
+        state.visual_lines_for(state.cols),
+        ["世".repeat(7), "世".to_string()]
+    );
+    assert_eq!(state.required_lines, 4);
+    let (col, row) =
+        calculate_cursor_position(state.buffer(), state.cols, state.rows, state.required_lines);
+    assert_eq!((col, row), (4 + 2, 22));
+    assert_eq!(state.cursor_position(), (col, row));
+}
+
+#[test]
+fn test_cursor_position_side_by_side() {
+    let mut config = Config::default();
+    config.layout = Layout::SideBySide { split_col: 40 };
+    let mut state = InputState::with_config(80, 10, config);
+    type_text(&mut state, "abc");
+
+    // Right of the separator at 40: "│ > " from column 41, then "abc"
+    assert_eq!(state.cursor_position(), (48, 8));
+    assert_eq!(state.visual_cursor_pos(), (0, 3));
+}
+
 #[test]
 fn test_special_characters() {
     let mut state = InputState::new(80, 24);
+    // Check each character is inserted as typed, without closing pairs
+    state.auto_pairs.clear();
 
     // Test various special characters
     let special_chars = "!@#$%^&*()_+-=[]{}|;':\",./<>?`~";
@@ -382,14 +471,1907 @@ fn test_special_characters() {
         );
     }
 
-    assert_eq!(state.buffer, special_chars);
+    assert_eq!(state.buffer(), special_chars);
 
     // Test Unicode characters
-    state.buffer.clear();
+    state.buffer_mut().clear();
     let unicode_text = "Hello 世界 🌍 Здравствуй";
     for ch in unicode_text.chars() {
         state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
     }
 
-    assert_eq!(state.buffer, unicode_text);
+    assert_eq!(state.buffer(), unicode_text);
+}
+
+#[test]
+fn test_single_line_mode_ignores_newline_keys() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::SingleLine);
+
+    state.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Char('j'), KeyModifiers::CONTROL);
+    state.handle_key(KeyCode::Char('b'), KeyModifiers::NONE);
+
+    assert_eq!(state.buffer(), "ab");
+    assert_eq!(state.required_lines, 3);
+
+    // Enter submits regardless of modifiers
+    assert!(state.is_submit_key(KeyModifiers::NONE));
+    assert!(state.is_submit_key(KeyModifiers::ALT));
+}
+
+#[test]
+fn test_multi_line_mode_submit_key() {
+    let state = InputState::new(80, 24);
+    assert_eq!(state.input_mode, InputMode::MultiLine);
+    assert!(state.is_submit_key(KeyModifiers::NONE));
+    assert!(!state.is_submit_key(KeyModifiers::ALT));
+}
+
+#[test]
+fn test_password_mode_masks_display_text() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::Password);
+
+    for ch in "secret".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+
+    assert_eq!(state.buffer(), "secret");
+    assert_eq!(state.display_text(), "******");
+
+    state.mask_char = '#';
+    assert_eq!(state.display_text(), "######");
+}
+
+#[test]
+fn test_password_mode_cursor_uses_masked_length() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::Password);
+
+    // Multi-byte characters must still count as one masked column each
+    for ch in "世界".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+
+    let (cursor_col, _) = calculate_cursor_position(
+        &state.display_text(),
+        state.cols,
+        state.rows,
+        state.required_lines,
+    );
+    assert_eq!(cursor_col, 6); // "│ > **" = 4 + 2
+}
+
+#[test]
+fn test_password_mode_submit_clears_buffer() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::Password);
+
+    for ch in "hunter2".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+
+    assert_eq!(state.get_submitted_text(), Some("hunter2".to_string()));
+    assert!(state.buffer().is_empty());
+    assert_eq!(state.display_text(), "");
+}
+
+#[test]
+fn test_wipe_password_zeroes_the_buffer() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["ls"]);
+    state.set_mode(InputMode::Password);
+    type_text(&mut state, "hunter2");
+
+    // History browsing would keep a copy of the password as its draft
+    state.handle_key(KeyCode::Up, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "hunter2");
+
+    let ptr = state.buffer().as_ptr();
+    let capacity = state.buffer_mut().capacity();
+    state.wipe_password();
+    assert!(state.buffer().is_empty());
+    assert_eq!(state.cursor_byte_offset, 0);
+    // SAFETY: the buffer still owns the allocation and every byte was written
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+    assert!(bytes.iter().all(|&b| b == 0));
+
+    // Other modes keep their text
+    state.set_mode(InputMode::MultiLine);
+    type_text(&mut state, "kept");
+    state.wipe_password();
+    assert_eq!(state.buffer(), "kept");
+}
+
+#[test]
+fn test_password_mode_title_is_hidden() {
+    let mut state = InputState::new(80, 24);
+    assert_eq!(state.title(), None);
+
+    state.set_mode(InputMode::Password);
+    assert_eq!(state.title(), Some("[hidden]".to_string()));
+}
+
+#[test]
+fn test_zeroize_wipes_the_whole_allocation() {
+    let mut text = String::with_capacity(16);
+    text.push_str("hunter2");
+    let ptr = text.as_ptr();
+    let capacity = text.capacity();
+
+    zeroize(&mut text);
+
+    assert!(text.is_empty());
+    assert_eq!(text.capacity(), capacity);
+    // SAFETY: `text` still owns the allocation and every byte was written
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+    assert!(bytes.iter().all(|&b| b == 0));
+}
+
+#[test]
+fn test_overwrite_mode_replaces_character_at_cursor() {
+    let mut state = InputState::new(80, 24);
+    *state.buffer_mut() = "hello".to_string();
+    state.cursor_byte_offset = 0;
+
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+    assert!(state.overwrite_mode);
+    state.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+
+    assert_eq!(state.buffer(), "xello");
+    assert_eq!(state.buffer().len(), 5);
+    assert_eq!(state.cursor_byte_offset, 1);
+}
+
+#[test]
+fn test_overwrite_mode_appends_at_end_and_keeps_newlines() {
+    let mut state = InputState::new(80, 24);
+    *state.buffer_mut() = "ab\ncd".to_string();
+    state.cursor_byte_offset = 1;
+    state.overwrite_mode = true;
+
+    for ch in "xyz".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+    assert_eq!(state.buffer(), "axyz\ncd");
+
+    state.cursor_byte_offset = state.buffer().len();
+    state.handle_key(KeyCode::Char('!'), KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "axyz\ncd!");
+
+    // Multi-byte characters are replaced whole
+    *state.buffer_mut() = "é".to_string();
+    state.cursor_byte_offset = 0;
+    state.handle_key(KeyCode::Char('e'), KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "e");
+}
+
+#[test]
+fn test_insert_toggles_overwrite_status() {
+    let mut state = InputState::new(80, 24);
+    assert_eq!(state.status(), None);
+
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+    assert_eq!(state.status(), Some("OVR".to_string()));
+
+    state.handle_key(KeyCode::Insert, KeyModifiers::CONTROL);
+    assert!(!state.overwrite_mode);
+}
+
+#[test]
+fn test_word_count() {
+    let mut state = InputState::new(80, 24);
+    assert_eq!(state.word_count(), 0);
+
+    for (text, words) in [
+        ("hello world", 2),
+        ("one\ntwo\nthree", 3),
+        ("  spaced   out  ", 2),
+        // Punctuation is never a word of its own, apostrophes stay inside one
+        ("Hello, world!", 2),
+        ("don't stop - go", 3),
+        ("x=1+2", 3),
+    ] {
+        state.set_buffer(text);
+        assert_eq!(state.word_count(), words, "{text:?}");
+        assert_eq!(state.text_metrics().word_count, words, "{text:?}");
+    }
+}
+
+#[test]
+fn test_status_mode_counts() {
+    let mut config = Config::default();
+    config.status_mode = StatusMode::WordCount;
+    let mut state = InputState::with_config(80, 24, config);
+    assert_eq!(state.status(), Some("0 words".to_string()));
+
+    state.set_buffer("one");
+    assert_eq!(state.status(), Some("1 word".to_string()));
+
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+    assert_eq!(state.status(), Some("OVR 1 word".to_string()));
+
+    state.status_mode = StatusMode::CharCount;
+    assert_eq!(state.status(), Some("OVR 3 chars".to_string()));
+
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+    state.status_mode = StatusMode::LineCount;
+    state.set_buffer("a\nb");
+    assert_eq!(state.status(), Some("2 lines".to_string()));
+
+    state.status_mode = StatusMode::LineInfo;
+    assert_eq!(state.status(), Some("2:2".to_string()));
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(state.status_string(), "2:1");
+
+    state.status_mode = StatusMode::Custom(Arc::new(|state| state.buffer().to_uppercase()));
+    assert_eq!(state.status(), Some("A\nB".to_string()));
+    state.status_mode = StatusMode::Custom(Arc::new(|_| String::new()));
+    assert_eq!(state.status(), None);
+
+    state.status_mode = StatusMode::None;
+    assert_eq!(state.status_string(), "");
+    assert_eq!(state.status(), None);
+
+    // Never hints at what a password looks like
+    state.set_mode(InputMode::Password);
+    assert_eq!(state.status(), None);
+}
+
+#[test]
+fn test_rtl_text_is_flagged() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "hi ");
+    assert!(!state.has_rtl);
+    assert_eq!(state.title(), None);
+
+    type_text(&mut state, "שלום");
+    assert!(state.has_rtl);
+    assert_eq!(state.title(), Some("⚠".to_string()));
+    assert_eq!(state.status(), Some(RTL_WARNING.to_string()));
+
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+    assert_eq!(state.status(), Some(format!("{RTL_WARNING} OVR")));
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+
+    // Deleting other text keeps the flag, deleting the RTL text clears it
+    state.delete_range(0..3).unwrap();
+    assert!(state.has_rtl);
+    while !state.buffer().is_empty() {
+        state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+    }
+    assert!(!state.has_rtl);
+    assert_eq!(state.status(), None);
+
+    state.set_buffer("مرحبا");
+    assert!(state.has_rtl);
+    state.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+    assert!(!state.has_rtl);
+}
+
+#[test]
+fn test_leading_whitespace() {
+    assert_eq!(leading_whitespace("    code"), "    ");
+    assert_eq!(leading_whitespace("\t x"), "\t ");
+    assert_eq!(leading_whitespace("none"), "");
+    assert_eq!(leading_whitespace("   "), "   ");
+}
+
+#[test]
+fn test_auto_indent_carries_leading_whitespace() {
+    let mut config = Config::default();
+    config.auto_indent = true;
+    let mut state = InputState::with_config(80, 24, config);
+    *state.buffer_mut() = "if x:\n    body".to_string();
+    state.cursor_byte_offset = state.buffer().len();
+
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    assert_eq!(state.buffer(), "if x:\n    body\n    ");
+
+    state.handle_key(KeyCode::Char('j'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "if x:\n    body\n    \n    ");
+    assert_eq!(state.cursor_byte_offset, state.buffer().len());
+}
+
+#[test]
+fn test_newline_is_bare_without_auto_indent() {
+    let mut state = InputState::with_config(80, 24, Config::default());
+    *state.buffer_mut() = "    body".to_string();
+    state.cursor_byte_offset = state.buffer().len();
+
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    assert_eq!(state.buffer(), "    body\n");
+}
+
+#[test]
+fn test_auto_pairs_insert_closer_after_cursor() {
+    let mut state = InputState::new(80, 24);
+
+    type_text(&mut state, "f(");
+    assert_eq!(state.buffer(), "f()");
+    assert_eq!(state.cursor_byte_offset, 2);
+
+    type_text(&mut state, "[\"");
+    assert_eq!(state.buffer(), "f([\"\"])");
+    assert_eq!(state.cursor_byte_offset, 4);
+}
+
+#[test]
+fn test_auto_pairs_skip_over_existing_closer() {
+    let mut state = InputState::new(80, 24);
+
+    type_text(&mut state, "f(x)");
+    assert_eq!(state.buffer(), "f(x)");
+    assert_eq!(state.cursor_byte_offset, 4);
+
+    type_text(&mut state, "'a'");
+    assert_eq!(state.buffer(), "f(x)'a'");
+}
+
+#[test]
+fn test_closer_without_pair_is_inserted() {
+    let mut state = InputState::new(80, 24);
+
+    // Nothing to step over
+    type_text(&mut state, "x)]");
+    assert_eq!(state.buffer(), "x)]");
+    assert_eq!(state.cursor_byte_offset, 3);
+
+    // A closer other than the one typed is not stepped over
+    state.cursor_byte_offset = 1;
+    type_text(&mut state, "]");
+    assert_eq!(state.buffer(), "x])]");
+    assert_eq!(state.cursor_byte_offset, 2);
+}
+
+#[test]
+fn test_backspace_between_pair_deletes_both() {
+    let mut state = InputState::new(80, 24);
+
+    type_text(&mut state, "x{");
+    state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+
+    assert_eq!(state.buffer(), "x");
+    assert_eq!(state.cursor_byte_offset, 1);
+}
+
+#[test]
+fn test_quote_after_word_is_not_paired() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "don't");
+    assert_eq!(state.buffer(), "don't");
+}
+
+#[test]
+fn test_empty_auto_pairs_inserts_plain_characters() {
+    let mut config = Config::default();
+    config.auto_pairs = Vec::new();
+    let mut state = InputState::with_config(80, 24, config);
+
+    type_text(&mut state, "(\"");
+    assert_eq!(state.buffer(), "(\"");
+    state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "(");
+}
+
+#[test]
+fn test_multiline_paste() {
+    let mut state = InputState::new(20, 24);
+
+    state.handle_paste("first line\r\nsecond line that wraps\rthird");
+
+    assert_eq!(state.buffer(), "first line\nsecond line that wraps\nthird");
+    assert!(!state.paste_mode);
+    // "first line" (1) + "second line that wraps" (2) + "third" (1) + 2 borders
+    assert_eq!(state.required_lines, 6);
+    assert_eq!(
+        state.required_lines,
+        calculate_required_lines(state.buffer(), state.cols)
+    );
+}
+
+#[test]
+fn test_paste_defers_frame_recalculation() {
+    let mut state = InputState::new(80, 24);
+
+    state.begin_paste();
+    state.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    state.handle_key(KeyCode::Char('b'), KeyModifiers::NONE);
+
+    // Frame size is untouched while the paste is in progress
+    assert_eq!(state.required_lines, 3);
+
+    state.end_paste();
+    assert_eq!(state.buffer(), "a\nb");
+    assert_eq!(state.required_lines, 4);
+}
+
+#[test]
+fn test_single_line_paste_drops_newlines() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::SingleLine);
+
+    state.handle_paste("one\ntwo\r\nthree");
+
+    assert_eq!(state.buffer(), "onetwothree");
+    assert_eq!(state.required_lines, 3);
+}
+
+#[test]
+fn test_clone_leaves_animations_with_the_original() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "draft");
+    let (handle, _cancel_rx) = termbox::animation::AnimationHandle::new();
+    state.active_animation = Some(handle);
+    *state.plugin_state_mut::<u32>() = 7;
+
+    let copy = state.clone();
+    assert_eq!(copy.buffer(), "draft");
+    assert_eq!(copy.cursor_byte_offset, 5);
+    assert_eq!(copy.required_lines, state.required_lines);
+    assert!(copy.active_animation.is_none());
+    assert!(copy.plugin_state::<u32>().is_none());
+    assert!(state.active_animation.is_some());
+}
+
+#[test]
+fn test_password_paste_drops_line_breaks() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::Password);
+
+    state.handle_paste("hun\r\nter\r2");
+
+    assert_eq!(state.buffer(), "hunter2");
+    assert_eq!(state.display_text(), "*******");
+}
+
+#[test]
+fn test_tab_completion_no_results() {
+    let mut state = InputState::new(80, 24);
+    state.set_tab_completer(Arc::new(MockCompleter(vec![])));
+    type_text(&mut state, "tik");
+
+    assert_eq!(
+        state.handle_key(KeyCode::Tab, KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert_eq!(state.buffer(), "tik");
+    assert!(state.completion_popup.is_empty());
+    assert_eq!(state.required_lines, 3);
+}
+
+#[test]
+fn test_tab_completion_single_result_replaces_word() {
+    let mut state = InputState::new(80, 24);
+    state.set_tab_completer(Arc::new(MockCompleter(vec!["tiktok"])));
+    type_text(&mut state, "run tik");
+
+    state.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "run tiktok");
+    assert!(state.completion_popup.is_empty());
+    assert_eq!(state.required_lines, 3);
+}
+
+#[test]
+fn test_tab_completion_multiple_results_open_popup() {
+    let mut state = InputState::new(80, 24);
+    state.set_tab_completer(Arc::new(MockCompleter(vec!["help", "hello", "hex"])));
+    type_text(&mut state, "he");
+
+    state.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "he");
+    assert_eq!(state.completion_popup, vec!["help", "hello", "hex"]);
+    assert_eq!(state.required_lines, 6); // 1 text line + 3 candidates + 2 borders
+
+    // Escape dismisses the popup without exiting
+    assert_eq!(
+        state.handle_key(KeyCode::Esc, KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert!(state.completion_popup.is_empty());
+    assert_eq!(state.required_lines, 3);
+
+    // With no popup open, Escape exits again
+    assert_eq!(
+        state.handle_key(KeyCode::Esc, KeyModifiers::NONE),
+        KeyAction::Exit
+    );
+}
+
+#[test]
+fn test_tab_without_completer_inserts_spaces() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "abc");
+
+    state.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "abc    ");
+    assert_eq!(state.cursor_byte_offset, 7);
+    assert!(state.completion_popup.is_empty());
+}
+
+#[test]
+fn test_literal_tab_expands_to_next_tab_stop() {
+    let mut config = Config::default();
+    config.tab_mode = TabMode::Literal;
+    let mut state = InputState::with_config(80, 24, config);
+
+    state.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "\t");
+
+    // Tab stops every 4 columns, from columns 0, 2 and 4
+    assert_eq!(expand_tabs("\tx", 4), "    x");
+    assert_eq!(expand_tabs("ab\tx", 4), "ab  x");
+    assert_eq!(expand_tabs("abcd\tx", 4), "abcd    x");
+    assert_eq!(expand_tabs("abcd\n\tx", 4), "abcd\n    x");
+
+    *state.buffer_mut() = "ab\tx".to_string();
+    state.cursor_byte_offset = 3;
+    assert_eq!(state.display_text(), "ab  x");
+    assert_eq!(state.cursor_position().0, 4 + 4); // after the expanded tab
+}
+
+#[test]
+fn test_literal_tabs_count_toward_wrapping() {
+    // 15 content columns: three tabs fill 12, so "abcd" wraps
+    assert_eq!(calculate_required_lines("\t\t\tabc", 20), 3);
+    assert_eq!(calculate_required_lines("\t\t\tabcd", 20), 4);
+    assert_eq!(
+        render_text_lines("\tx", 20)[1].content,
+        "│ >     x          │"
+    );
+}
+
+#[test]
+fn test_shift_tab_dedents_current_line() {
+    let mut state = InputState::new(80, 24);
+    *state.buffer_mut() = "top\n      body".to_string();
+    state.cursor_byte_offset = state.buffer().len();
+
+    state.handle_key(KeyCode::BackTab, KeyModifiers::SHIFT);
+    assert_eq!(state.buffer(), "top\n  body");
+    assert_eq!(state.cursor_byte_offset, state.buffer().len());
+
+    state.handle_key(KeyCode::BackTab, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::BackTab, KeyModifiers::SHIFT);
+    assert_eq!(state.buffer(), "top\nbody");
+}
+
+fn submit_history(state: &mut InputState, entries: &[&str]) {
+    for entry in entries {
+        type_text(state, entry);
+        state.get_submitted_text();
+    }
+}
+
+#[test]
+fn test_submitted_text_is_recorded_in_history() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["one", "two", "two"]);
+    assert_eq!(state.history, vec!["one", "two"]); // consecutive duplicates collapse
+
+    state.set_mode(InputMode::Password);
+    submit_history(&mut state, &["secret"]);
+    assert_eq!(state.history, vec!["one", "two"]); // passwords are never recorded
+}
+
+#[test]
+fn test_reverse_search_cycles_through_matches() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["git status", "cargo build", "git commit"]);
+
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(state.title().as_deref(), Some("(reverse-i-search): "));
+
+    type_text(&mut state, "git");
+    assert_eq!(state.buffer(), "git commit");
+    assert_eq!(state.title().as_deref(), Some("(reverse-i-search): git"));
+
+    // Ctrl+R moves to the next older match
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "git status");
+    assert_eq!(state.search.as_ref().unwrap().match_index, 0);
+
+    // No older match: stay on the current one
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "git status");
+
+    // Enter accepts the match instead of submitting
+    assert!(!state.is_submit_key(KeyModifiers::NONE));
+    state.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+    assert!(state.search.is_none());
+    assert_eq!(state.buffer(), "git status");
+    assert_eq!(state.title(), None);
+}
+
+#[test]
+fn test_reverse_search_failed_query() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["ls"]);
+
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    type_text(&mut state, "xyz");
+    assert_eq!(state.buffer(), "");
+    assert_eq!(
+        state.title().as_deref(),
+        Some("(failed reverse-i-search): xyz")
+    );
+
+    // Backspacing to a matching query finds the entry again
+    for _ in 0..3 {
+        state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+    }
+    type_text(&mut state, "l");
+    assert_eq!(state.buffer(), "ls");
+}
+
+#[test]
+fn test_reverse_search_cancel_restores_draft() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["cargo build", "cargo test"]);
+    type_text(&mut state, "draft");
+
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    type_text(&mut state, "car");
+    assert_eq!(state.buffer(), "cargo test");
+
+    assert_eq!(
+        state.handle_key(KeyCode::Esc, KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert!(state.search.is_none());
+    assert_eq!(state.buffer(), "draft");
+
+    // A key search mode does not handle also cancels, then acts normally
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    type_text(&mut state, "build");
+    assert_eq!(state.buffer(), "cargo build");
+    state.handle_key(KeyCode::Char('j'), KeyModifiers::CONTROL);
+    assert!(state.search.is_none());
+    assert_eq!(state.buffer(), "draft\n");
+}
+
+#[test]
+fn test_reverse_search_match_can_be_undone() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["cargo build", "cargo test"]);
+    type_text(&mut state, "draft");
+
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    type_text(&mut state, "c");
+    state.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "cargo test");
+
+    assert!(state.undo());
+    assert_eq!(state.buffer(), "draft");
+}
+
+#[test]
+fn test_reverse_search_disabled_in_password_mode() {
+    let mut state = InputState::new(80, 24);
+    submit_history(&mut state, &["git status"]);
+    state.set_mode(InputMode::Password);
+    type_text(&mut state, "hunter2");
+
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    assert!(state.search.is_none());
+    type_text(&mut state, "git");
+    assert_eq!(state.buffer(), "hunter2git");
+    assert_eq!(state.title().as_deref(), Some("[hidden]"));
+}
+
+#[test]
+fn test_cursor_movement_and_mid_buffer_editing() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "hllo");
+    assert_eq!(state.cursor_byte_offset, 4);
+
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Right, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Char('e'), KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "hello");
+    assert_eq!(state.cursor_byte_offset, 2);
+
+    state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "hllo");
+    assert_eq!(state.cursor_byte_offset, 1);
+
+    state.handle_key(KeyCode::End, KeyModifiers::NONE);
+    assert_eq!(state.cursor_byte_offset, 4);
+
+    // Left steps over whole multi-byte characters
+    type_text(&mut state, "世");
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    assert_eq!(state.cursor_byte_offset, 4);
+}
+
+#[test]
+fn test_home_end_stay_on_logical_line() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "abc");
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    type_text(&mut state, "de");
+
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(state.cursor_byte_offset, 4);
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(state.cursor_byte_offset, 0);
+    state.handle_key(KeyCode::End, KeyModifiers::NONE);
+    assert_eq!(state.cursor_byte_offset, 3);
+}
+
+#[test]
+fn test_shift_arrows_extend_selection() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "hello world");
+
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    assert_eq!(state.selection_start, Some(11));
+    assert_eq!(state.selection_range(), Some(9..11));
+    assert_eq!(state.selected_text(), Some("ld"));
+
+    state.handle_key(KeyCode::Home, KeyModifiers::SHIFT);
+    assert_eq!(state.selected_text(), Some("hello world"));
+
+    // Moving back past the anchor selects in the other direction
+    state.handle_key(KeyCode::End, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Right, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::End, KeyModifiers::SHIFT);
+    assert_eq!(state.selection_range(), Some(0..11));
+}
+
+#[test]
+fn test_selection_collapses() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "abc");
+
+    // Moving back to the anchor leaves an empty selection
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Right, KeyModifiers::SHIFT);
+    assert_eq!(state.selection_range(), None);
+
+    // A motion without Shift clears the selection
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    assert_eq!(state.selected_text(), Some("c"));
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    assert_eq!(state.selection_start, None);
+    assert_eq!(state.buffer(), "abc");
+}
+
+#[test]
+fn test_typing_over_selection() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "hello world");
+    for _ in 0..5 {
+        state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    }
+
+    // Shifted characters still replace the selection
+    state.handle_key(KeyCode::Char('T'), KeyModifiers::SHIFT);
+    assert_eq!(state.buffer(), "hello T");
+    assert_eq!(state.selection_start, None);
+    assert_eq!(state.cursor_byte_offset, 7);
+
+    // Backspace deletes a selection as a whole
+    state.handle_key(KeyCode::Home, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "");
+    assert_eq!(state.required_lines, 3);
+}
+
+#[test]
+fn test_ctrl_c_copies_selection_to_kill_ring() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "copy me");
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+
+    assert_eq!(
+        state.handle_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        KeyAction::Continue
+    );
+    assert_eq!(state.kill_ring, vec!["me"]);
+    assert_eq!(state.selection_start, None);
+    assert_eq!(state.buffer(), "copy me");
+
+    // Without a selection Ctrl+C exits as before
+    assert_eq!(
+        state.handle_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        KeyAction::Exit
+    );
+}
+
+#[test]
+fn test_ctrl_c_with_empty_selection_exits() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "text");
+    // Selecting one character and back again leaves nothing selected
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Right, KeyModifiers::SHIFT);
+    assert_eq!(state.selection_start, Some(state.cursor_byte_offset));
+
+    assert_eq!(
+        state.handle_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        KeyAction::Exit
+    );
+    assert!(state.kill_ring.is_empty());
+}
+
+#[test]
+fn test_enter_returns_submitted_text() {
+    let mut state = InputState::new(80, 24);
+    type_text(&mut state, "hello");
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    type_text(&mut state, "world");
+
+    assert_eq!(
+        state.handle_key(KeyCode::Enter, KeyModifiers::NONE),
+        KeyAction::Submit("hello\nworld".to_string())
+    );
+    assert_eq!(state.buffer(), "");
+    assert_eq!(state.cursor_byte_offset, 0);
+    assert_eq!(state.required_lines, 3);
+
+    // Submitting an empty buffer still reports a submission
+    assert_eq!(
+        state.handle_key(KeyCode::Enter, KeyModifiers::NONE),
+        KeyAction::Submit(String::new())
+    );
+}
+
+#[test]
+fn test_single_line_alt_enter_submits() {
+    let mut state = InputState::new(80, 24);
+    state.set_mode(InputMode::SingleLine);
+    type_text(&mut state, "one");
+
+    assert_eq!(
+        state.handle_key(KeyCode::Enter, KeyModifiers::ALT),
+        KeyAction::Submit("one".to_string())
+    );
+}
+
+#[test]
+fn test_ctrl_l_returns_clear_screen() {
+    let mut state = InputState::new(20, 8);
+    state.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+
+    let action = state.handle_key(KeyCode::Char('l'), KeyModifiers::CONTROL);
+
+    assert_eq!(action, KeyAction::ClearScreen);
+    assert_eq!(state.buffer(), "a");
+}
+
+#[test]
+fn test_click_moves_cursor_to_clicked_character() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "hello world and more");
+    // Lines "hello world and" and " more" on rows 7 and 8, text from column 4
+    assert_eq!(state.required_lines, 4);
+
+    assert!(state.handle_click(4 + 6, 7));
+    assert_eq!(state.cursor_byte_offset, 6); // the "w"
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+    assert!(drawing.ends_with("\x1B[8;11H"));
+
+    assert!(state.handle_click(4 + 2, 8));
+    assert_eq!(state.cursor_byte_offset, 17); // the "o" of " more"
+
+    assert!(state.handle_click(18, 8)); // past the end of the line
+    assert_eq!(state.cursor_byte_offset, 20);
+
+    assert!(state.handle_click(0, 7)); // on the left border
+    assert_eq!(state.cursor_byte_offset, 0);
+
+    assert!(!state.handle_click(5, 2)); // above the frame
+    assert!(!state.handle_click(5, 9)); // on the bottom border
+    assert_eq!(state.cursor_byte_offset, 0);
+}
+
+#[test]
+fn test_click_on_wide_characters_and_tabs() {
+    assert_eq!(visual_pos_to_byte_offset("a世b", 20, 0, 1), 1);
+    assert_eq!(visual_pos_to_byte_offset("a世b", 20, 0, 2), 1);
+    assert_eq!(visual_pos_to_byte_offset("a世b", 20, 0, 3), 4);
+    assert_eq!(visual_pos_to_byte_offset("a世b", 20, 5, 0), 5);
+
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "\tx");
+    assert!(state.handle_click(4 + 4, 8));
+    assert_eq!(state.cursor_byte_offset, 1); // the "x" after the expanded tab
+    assert!(state.handle_click(4 + 2, 8));
+    assert_eq!(state.cursor_byte_offset, 0); // inside the tab
+}
+
+#[test]
+fn test_replace_range_edits_buffer() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "hello world");
+
+    // Insert in the middle
+    state.replace_range(5..5, ",").unwrap();
+    assert_eq!(state.buffer(), "hello, world");
+
+    // Replace several characters, including a multi-byte one
+    state.replace_range(0..5, "hé").unwrap();
+    assert_eq!(state.buffer(), "hé, world");
+
+    // Replace with a longer string that wraps onto a second line
+    state.replace_range(5..10, "wonderful world").unwrap();
+    assert_eq!(state.buffer(), "hé, wonderful world");
+    assert_eq!(
+        state.required_lines,
+        calculate_required_lines(state.buffer(), state.cols)
+    );
+    assert_eq!(state.required_lines, 4);
+}
+
+#[test]
+fn test_replace_range_clamps_cursor() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "hello world");
+    assert_eq!(state.cursor_byte_offset, 11);
+
+    state.replace_range(5..11, "").unwrap();
+    assert_eq!(state.buffer(), "hello");
+    assert_eq!(state.cursor_byte_offset, 5);
+}
+
+#[test]
+fn test_delete_range_removes_whole_characters() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "añb€c");
+
+    // "ñ" is bytes 1..3 and "€" bytes 4..7
+    state.delete_range(1..3).unwrap();
+    assert_eq!(state.buffer(), "ab€c");
+    state.delete_range(2..5).unwrap();
+    assert_eq!(state.buffer(), "abc");
+
+    // Inside "é", and past the end
+    type_text(&mut state, "é");
+    for range in [4..5, 3..9] {
+        assert!(matches!(
+            state.delete_range(range),
+            Err(TermboxError::InvalidBoundary)
+        ));
+    }
+    assert_eq!(state.buffer(), "abcé");
+}
+
+#[test]
+fn test_insert_at_places_text_without_moving_cursor() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "hé world");
+    state.cursor_byte_offset = 0;
+
+    state.insert_at(3, "llo").unwrap();
+    assert_eq!(state.buffer(), "héllo world");
+    state.insert_at(state.buffer().len(), "\n!").unwrap();
+    assert_eq!(state.buffer(), "héllo world\n!");
+    assert_eq!(state.cursor_byte_offset, 0);
+    assert_eq!(state.required_lines, 4);
+
+    // Inside "é"
+    assert!(state.insert_at(2, "x").is_err());
+    assert_eq!(state.buffer(), "héllo world\n!");
+}
+
+#[test]
+fn test_delete_range_and_insert_at_can_be_undone() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "añb");
+    state.delete_range(1..3).unwrap();
+    state.insert_at(0, "x").unwrap();
+    assert_eq!(state.buffer(), "xab");
+
+    assert!(state.undo());
+    assert_eq!(state.buffer(), "ab");
+    assert!(state.undo());
+    assert_eq!(state.buffer(), "añb");
+    assert_eq!(state.cursor_byte_offset, 4);
+
+    // Typing is undone a key at a time, also with Ctrl+_
+    state.handle_key(KeyCode::Char('_'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "añ");
+    assert_eq!(state.cursor_byte_offset, 3);
+    assert!(state.undo());
+    assert!(state.undo());
+    assert_eq!(state.buffer(), "");
+    assert!(!state.undo());
+}
+
+#[test]
+fn test_undo_skips_submissions_and_passwords() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "sent");
+    state.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+    assert!(state.undo_stack.is_empty());
+    assert!(!state.undo());
+    assert_eq!(state.buffer(), "");
+
+    state.set_mode(InputMode::Password);
+    type_text(&mut state, "hunter2");
+    state.handle_paste("!");
+    assert!(state.undo_stack.is_empty());
+}
+
+#[test]
+fn test_undo_records_only_what_edits_replace() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "hello");
+    assert_eq!(state.undo_stack.len(), 5);
+
+    // Moving the cursor edits nothing
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(state.undo_stack.len(), 5);
+    assert_eq!(
+        state.undo_stack[4].edits,
+        vec![BufferEdit {
+            start: 4,
+            removed: String::new(),
+            inserted: 1,
+        }]
+    );
+
+    // Typing over a selection deletes it and inserts, undone as one
+    state.handle_key(KeyCode::End, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "x");
+    let edits = &state.undo_stack.last().unwrap().edits;
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0].removed, "hello");
+    assert!(state.undo());
+    assert_eq!(state.buffer(), "hello");
+    // The cursor from before the key, at the end of the selection
+    assert_eq!(state.cursor_byte_offset, 5);
+}
+
+#[test]
+fn test_replace_range_rejects_invalid_ranges() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "héllo");
+
+    // Inside "é", past the end, and reversed
+    let reversed = std::ops::Range { start: 3, end: 1 };
+    for range in [2..3, 0..9, reversed] {
+        let err = state.replace_range(range, "x").unwrap_err();
+        assert!(matches!(err, TermboxError::InvalidBoundary));
+    }
+    assert_eq!(state.buffer(), "héllo");
+}
+
+#[test]
+fn test_visual_lines_match_rendered_lines() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "a long line that wraps around\nsecond\n\nlast");
+
+    let lines = state.visual_lines();
+    assert_eq!(
+        lines,
+        vec!["a long line tha", "t wraps around", "second", "", "last"]
+    );
+
+    // Each rendered row is one of the lines between the prompt and padding
+    let rendered = render_text_lines(state.buffer(), state.cols);
+    assert_eq!(rendered.len(), lines.len() + 2);
+    for (i, (line, row)) in lines.iter().zip(&rendered[1..]).enumerate() {
+        let prefix = if i == 0 { "> " } else { "  " };
+        assert_eq!(row.content, format!("│ {}{:<15}│", prefix, line));
+        assert_eq!(row.is_continuation, i == 1);
+    }
+
+    // Borrowed from the buffer, not copied
+    let buffer = state.buffer().as_bytes().as_ptr_range();
+    for line in &lines {
+        assert!(buffer.contains(&line.as_ptr()) || line.is_empty());
+    }
+    assert_eq!(state.visual_lines_for(80).len(), 4);
+}
+
+#[test]
+fn test_text_metrics() {
+    let mut state = InputState::new(20, 10);
+    assert_eq!(
+        state.text_metrics(),
+        TextMetrics {
+            logical_lines: 1,
+            visual_lines: 1,
+            char_count: 0,
+            byte_count: 0,
+            grapheme_count: 0,
+            word_count: 0,
+        }
+    );
+
+    type_text(&mut state, "hello\nworld, this wraps");
+    assert_eq!(
+        state.text_metrics(),
+        TextMetrics {
+            logical_lines: 2,
+            visual_lines: 3,
+            char_count: 23,
+            byte_count: 23,
+            grapheme_count: 23,
+            word_count: 4,
+        }
+    );
+
+    // "é" as e + combining accent, a two-wide ideograph and a flag
+    *state.buffer_mut() = "e\u{301}世🇫🇷".to_string();
+    assert_eq!(
+        state.text_metrics(),
+        TextMetrics {
+            logical_lines: 1,
+            visual_lines: 1,
+            char_count: 5,
+            byte_count: 14,
+            grapheme_count: 3,
+            word_count: 2,
+        }
+    );
+    assert_eq!(
+        calculate_required_lines(state.buffer(), state.cols),
+        state.text_metrics().visual_lines + 2
+    );
+}
+
+#[test]
+fn test_cursor_line_col() {
+    let mut state = InputState::new(20, 10);
+    assert_eq!(state.cursor_line_col(), (0, 0));
+    assert_eq!(state.visual_cursor_pos(), (0, 0));
+
+    state.handle_paste("abc\nde");
+    assert_eq!(state.cursor_line_col(), (1, 2));
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    assert_eq!(state.cursor_line_col(), (1, 1)); // on the "e"
+    assert_eq!(state.visual_cursor_pos(), (1, 1));
+
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(state.cursor_line_col(), (1, 0));
+}
+
+#[test]
+fn test_visual_cursor_pos_follows_wrapping() {
+    let mut state = InputState::new(20, 10);
+    // The frame holds 15 columns of text per row
+    type_text(&mut state, &"x".repeat(15));
+    assert_eq!(state.visual_cursor_pos(), (0, 15));
+
+    type_text(&mut state, "yz");
+    assert_eq!(state.visual_cursor_pos(), (1, 2));
+    assert_eq!(state.cursor_line_col(), (0, 17));
+}
+
+#[test]
+fn test_visual_cursor_row_index() {
+    // Three visual lines: "x" * 15, "yz" wrapped from it, then "last"
+    let text = format!("{}yz\nlast", "x".repeat(15));
+    let mut state = InputState::with_initial_text(20, 10, &text);
+    assert_eq!(state.visual_cursor_row_index(), 2);
+
+    // On the wrapped continuation of the first logical line
+    state.cursor_byte_offset = 16;
+    assert_eq!(state.visual_cursor_row_index(), 1);
+
+    state.cursor_byte_offset = 0;
+    assert_eq!(state.visual_cursor_row_index(), 0);
+
+    // Matches the row the cursor is drawn on, wherever the frame is
+    state.frame_position = FramePosition::Top;
+    state.cursor_byte_offset = 16;
+    assert_eq!(state.visual_cursor_pos().0, 1);
+}
+
+#[test]
+fn test_scroll_offset_follows_cursor() {
+    let press = |state: &mut InputState, key: KeyCode, times: usize| {
+        for _ in 0..times {
+            state.handle_key(key, KeyModifiers::NONE);
+        }
+    };
+    // 8 lines in a 5 row terminal: 3 rows between the borders
+    let mut state = InputState::new(20, 5);
+    state.set_mode(InputMode::MultiLine);
+    state.set_buffer("1\n2\n3\n4\n5\n6\n7\n8");
+    assert_eq!(state.scroll_offset, 5);
+    assert_eq!(state.cursor_position(), (5, 3));
+
+    // Moving within the rows shown does not scroll, to the end of "6"
+    press(&mut state, KeyCode::Left, 4);
+    assert_eq!(state.scroll_offset, 5);
+    assert_eq!(state.cursor_position(), (5, 1));
+
+    // Moving past them scrolls just far enough, to the end of "5"
+    press(&mut state, KeyCode::Left, 2);
+    assert_eq!(state.scroll_offset, 4);
+    assert_eq!(state.cursor_position(), (5, 1));
+    press(&mut state, KeyCode::Left, 8);
+    assert_eq!(state.scroll_offset, 0);
+    press(&mut state, KeyCode::Right, 4);
+    assert_eq!(state.scroll_offset, 0);
+    assert_eq!(state.cursor_position(), (5, 3));
+
+    // Everything fits again in a taller terminal
+    press(&mut state, KeyCode::Right, 10);
+    assert_eq!(state.scroll_offset, 5);
+    state.handle_resize(20, 12);
+    assert_eq!(state.scroll_offset, 0);
+}
+
+#[test]
+fn test_click_on_scrolled_frame() {
+    let mut state = InputState::new(20, 5);
+    state.set_mode(InputMode::MultiLine);
+    state.set_buffer("1\n2\n3\n4\n5\n6\n7\n8");
+
+    // The first row between the borders shows "6", scrolled past "1" to "5"
+    assert!(state.handle_click(4, 1));
+    assert_eq!(state.cursor_byte_offset, 10);
+    // The borders are not text
+    assert!(!state.handle_click(4, 4));
+}
+
+#[test]
+fn test_lines_and_counts() {
+    let mut state = InputState::new(20, 10);
+    assert!(state.is_empty());
+    assert_eq!(state.lines().count(), 0);
+    assert_eq!(state.line_count(), 1);
+    assert_eq!(state.char_count(), 0);
+
+    type_text(&mut state, "héllo");
+    assert!(!state.is_empty());
+    assert_eq!(state.lines().collect::<Vec<_>>(), vec!["héllo"]);
+    assert_eq!(state.line_count(), 1);
+    assert_eq!(state.char_count(), 5);
+
+    state.handle_paste("\nworld\n");
+    assert_eq!(state.lines().collect::<Vec<_>>(), vec!["héllo", "world"]);
+    assert_eq!(state.line_count(), 2); // the trailing newline adds no line
+    assert_eq!(state.char_count(), 12);
+
+    state.handle_paste("\n");
+    assert_eq!(
+        state.lines().collect::<Vec<_>>(),
+        vec!["héllo", "world", ""]
+    );
+    assert_eq!(state.line_count(), 3);
+}
+
+#[test]
+fn test_set_buffer_recalculates_frame_and_moves_cursor_to_end() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "old");
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+
+    state.set_buffer("a line long enough to wrap twice in the frame");
+    assert_eq!(state.required_lines, 5); // three rows of 15 columns
+    assert_eq!(
+        state.required_lines,
+        calculate_required_lines(state.buffer(), state.cols)
+    );
+    assert_eq!(state.cursor_byte_offset, state.buffer().len());
+
+    state.set_buffer("");
+    assert_eq!(state.required_lines, 3);
+    assert_eq!(state.cursor_byte_offset, 0);
+}
+
+#[test]
+fn test_clear_empties_buffer_without_submitting() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut state = InputState::with_initial_text(20, 10, "a line long enough to wrap");
+    let sink = seen.clone();
+    state.set_on_change(move |text| sink.lock().unwrap().push(text.to_string()));
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    assert_eq!(state.buffer(), state.buffer());
+
+    state.clear();
+    assert_eq!(state.buffer(), "");
+    assert_eq!(state.cursor_byte_offset, 0);
+    assert_eq!(state.selection_start, None);
+    assert_eq!(state.required_lines, 3);
+    assert!(state.history.is_empty());
+    assert_eq!(*seen.lock().unwrap(), vec![""]);
+
+    // Clearing an empty buffer changes nothing
+    state.clear();
+    assert_eq!(seen.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_clear_can_be_undone() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "first\nsecond");
+    state.cursor_byte_offset = 3;
+
+    state.clear();
+    assert_eq!(state.buffer(), "");
+    assert!(state.undo());
+    assert_eq!(state.buffer(), "first\nsecond");
+    assert_eq!(state.buffer(), state.buffer());
+    assert_eq!(state.cursor_byte_offset, 3);
+    assert_eq!(state.required_lines, 4);
+}
+
+#[test]
+fn test_with_initial_text() {
+    let state = InputState::with_initial_text(20, 10, "first\nsecond");
+    assert_eq!(state.buffer(), "first\nsecond");
+    assert_eq!(state.required_lines, 4);
+    assert_eq!(state.cursor_byte_offset, 12);
+}
+
+#[test]
+fn test_with_initial_text_and_cursor() {
+    let state = InputState::with_initial_text_and_cursor(
+        20,
+        10,
+        "né
+wrapped past twenty",
+        3,
+    )
+    .unwrap();
+    assert_eq!(state.cursor_byte_offset, 3);
+    assert_eq!(state.required_lines, 5);
+    assert_eq!(
+        state.required_lines,
+        calculate_required_lines(state.buffer(), state.cols)
+    );
+
+    // Inside "é", and past the end
+    for cursor in [2, 25] {
+        let result = InputState::with_initial_text_and_cursor(20, 10, "né\nwrapped", cursor);
+        assert!(matches!(result, Err(TermboxError::InvalidBoundary)));
+    }
+}
+
+#[test]
+fn test_on_change_sees_every_buffer_state() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut state = InputState::new(20, 10);
+    let sink = seen.clone();
+    state.set_on_change(move |text| sink.lock().unwrap().push(text.to_string()));
+
+    type_text(&mut state, "ab");
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE); // no change
+    state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+    state.handle_paste("xy");
+    state.replace_range(0..0, ">").unwrap();
+    state.set_buffer(">xyb"); // same text, no change
+    state.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec!["a", "ab", "b", "xyb", ">xyb", ""]
+    );
+}
+
+#[test]
+fn test_on_change_not_called_in_password_mode() {
+    let calls = Arc::new(std::sync::Mutex::new(0));
+    let mut state = InputState::new(20, 10);
+    state.set_mode(InputMode::Password);
+    let sink = calls.clone();
+    state.set_on_change(move |_| *sink.lock().unwrap() += 1);
+
+    type_text(&mut state, "secret");
+    assert_eq!(*calls.lock().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_rejected_submit_keeps_buffer_and_shows_error() {
+    let mut state = InputState::new(40, 10);
+    state.on_submit_async = Some(Arc::new(|text: String| {
+        Box::pin(async move {
+            if text.contains("bad") {
+                SubmitAction::Reject(format!("{text:?} was rejected"))
+            } else {
+                SubmitAction::Accept
+            }
+        })
+    }));
+
+    type_text(&mut state, "bad input");
+    assert_eq!(
+        state
+            .handle_key_async(KeyCode::Enter, KeyModifiers::NONE)
+            .await,
+        KeyAction::Continue
+    );
+    assert_eq!(state.buffer(), "bad input");
+    assert_eq!(
+        state.title(),
+        Some("\"bad input\" was rejected".to_string())
+    );
+    assert_eq!(state.prompt_options().title_fg, Some(Color::Red));
+
+    // The next key clears the message
+    state
+        .handle_key_async(KeyCode::Home, KeyModifiers::NONE)
+        .await;
+    assert_eq!(state.submit_error, None);
+    assert_eq!(state.title(), None);
+
+    state.set_buffer("good input");
+    assert_eq!(
+        state
+            .handle_key_async(KeyCode::Enter, KeyModifiers::NONE)
+            .await,
+        KeyAction::Submit("good input".to_string())
+    );
+    assert!(state.buffer().is_empty());
+}
+
+#[test]
+fn test_exit_can_be_remapped_from_esc_to_f10() {
+    let mut config = Config::default();
+    config.key_bindings.unbind(KeyCode::Esc, KeyModifiers::NONE);
+    config
+        .key_bindings
+        .bind(KeyCode::F(10), KeyModifiers::NONE, InputAction::Exit);
+    let mut state = InputState::with_config(80, 24, config);
+
+    assert_eq!(
+        state.handle_key(KeyCode::Esc, KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert_eq!(
+        state.handle_key(KeyCode::F(10), KeyModifiers::NONE),
+        KeyAction::Exit
+    );
+}
+
+#[test]
+fn test_submit_key_swaps_submit_and_newline() {
+    let alt = KeyModifiers::ALT;
+    let ctrl = KeyModifiers::CONTROL;
+    for (submit_key, (submit_code, submit_modifiers), newline_modifiers) in [
+        (
+            SubmitKey::AltEnter,
+            (KeyCode::Enter, KeyModifiers::NONE),
+            alt,
+        ),
+        (SubmitKey::Enter, (KeyCode::Enter, alt), KeyModifiers::NONE),
+        (
+            SubmitKey::CtrlEnter,
+            (KeyCode::Enter, ctrl),
+            KeyModifiers::NONE,
+        ),
+        (
+            SubmitKey::CtrlD,
+            (KeyCode::Char('d'), ctrl),
+            KeyModifiers::NONE,
+        ),
+    ] {
+        let mut config = Config::default();
+        config.input_mode = InputMode::MultiLine;
+        config.submit_key = submit_key;
+        let mut state = InputState::with_config(80, 24, config);
+
+        type_text(&mut state, "a");
+        assert_eq!(
+            state.handle_key(KeyCode::Enter, newline_modifiers),
+            KeyAction::Continue,
+            "{submit_key:?}"
+        );
+        type_text(&mut state, "b");
+        assert_eq!(
+            state.handle_key(submit_code, submit_modifiers),
+            KeyAction::Submit("a\nb".to_string()),
+            "{submit_key:?}"
+        );
+    }
+
+    // Enter still submits in single-line mode
+    let mut config = Config::default();
+    config.input_mode = InputMode::SingleLine;
+    config.submit_key = SubmitKey::CtrlD;
+    let mut state = InputState::with_config(80, 24, config);
+    type_text(&mut state, "a");
+    assert_eq!(
+        state.handle_key(KeyCode::Enter, KeyModifiers::NONE),
+        KeyAction::Submit("a".to_string())
+    );
+}
+
+#[test]
+fn test_default_bindings_apply_with_extra_modifiers() {
+    let bindings = KeyBindings::default();
+    assert_eq!(
+        bindings.get(KeyCode::Left, KeyModifiers::SHIFT),
+        Some(InputAction::MoveLeft)
+    );
+    assert_eq!(
+        bindings.get(KeyCode::Enter, KeyModifiers::CONTROL),
+        Some(InputAction::Submit)
+    );
+    // Characters are typed rather than borrowing another binding
+    assert_eq!(
+        bindings.get(KeyCode::Char('x'), KeyModifiers::CONTROL),
+        None
+    );
+}
+
+#[test]
+fn test_history_and_delete_actions_when_bound() {
+    let mut state = InputState::new(80, 24);
+    let bindings = &mut state.key_bindings;
+    bindings.bind(KeyCode::Up, KeyModifiers::NONE, InputAction::HistoryPrev);
+    bindings.bind(KeyCode::Down, KeyModifiers::NONE, InputAction::HistoryNext);
+    bindings.bind(
+        KeyCode::Delete,
+        KeyModifiers::NONE,
+        InputAction::DeleteAfter,
+    );
+    bindings.bind(
+        KeyCode::Char('u'),
+        KeyModifiers::CONTROL,
+        InputAction::ClearLine,
+    );
+    state.history = vec!["first".to_string(), "second".to_string()];
+    state.set_buffer("draft");
+
+    state.handle_key(KeyCode::Up, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Up, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "first");
+    state.handle_key(KeyCode::Down, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "second");
+    state.handle_key(KeyCode::Down, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "draft");
+
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(state.buffer(), "raft");
+
+    state.set_buffer("one\ntwo");
+    state.handle_key(KeyCode::Char('u'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "one\n");
+}
+
+#[test]
+fn test_function_keys() {
+    let mut state = InputState::with_initial_text(80, 24, "some text");
+
+    assert_eq!(
+        state.handle_key(KeyCode::F(1), KeyModifiers::NONE),
+        KeyAction::ShowHelp
+    );
+    assert_eq!(state.buffer(), "some text");
+
+    // Unmapped function keys are ignored
+    assert_eq!(
+        state.handle_key(KeyCode::F(7), KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert_eq!(state.buffer(), "some text");
+    assert_eq!(state.cursor_byte_offset, 9);
+
+    assert_eq!(
+        state.handle_key(KeyCode::F(5), KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert_eq!(state.buffer(), "");
+    assert_eq!(state.cursor_byte_offset, 0);
+}
+
+#[test]
+fn test_function_keys_can_be_remapped() {
+    let mut config = Config::default();
+    config.function_keys.clear();
+    config.function_keys.insert(2, InputAction::Exit);
+    let mut state = InputState::with_config(80, 24, config);
+
+    assert_eq!(
+        state.handle_key(KeyCode::F(1), KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    assert_eq!(
+        state.handle_key(KeyCode::F(2), KeyModifiers::NONE),
+        KeyAction::Exit
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ctrl_z_suspends() {
+    let mut state = InputState::with_initial_text(80, 24, "draft");
+    assert_eq!(
+        state.handle_key(KeyCode::Char('z'), KeyModifiers::CONTROL),
+        KeyAction::Suspend
+    );
+    assert_eq!(state.buffer(), "draft");
+}
+
+#[test]
+fn test_find_line_start_and_end() {
+    let text = "ab\ncd\nef";
+    // Start of the buffer
+    assert_eq!((find_line_start(text, 0), find_line_end(text, 0)), (0, 2));
+    // Mid-line
+    assert_eq!((find_line_start(text, 4), find_line_end(text, 4)), (3, 5));
+    // On a newline, which belongs to the line it ends
+    assert_eq!((find_line_start(text, 2), find_line_end(text, 2)), (0, 2));
+    // End of the last line
+    assert_eq!((find_line_start(text, 8), find_line_end(text, 8)), (6, 8));
+}
+
+#[test]
+fn test_current_line() {
+    let mut state = InputState::with_initial_text(80, 24, "first\nsecond\nthird");
+    assert_eq!(state.current_line(), "third");
+    assert_eq!(state.current_line_offset(), 5);
+
+    state.cursor_byte_offset = 9;
+    assert_eq!(state.current_line(), "second");
+    assert_eq!(state.current_line_offset(), 3);
+
+    state.cursor_byte_offset = 0;
+    assert_eq!(state.current_line(), "first");
+    assert_eq!(state.current_line_offset(), 0);
+
+    // Right after a newline is the start of the next line
+    state.cursor_byte_offset = 6;
+    assert_eq!(state.current_line(), "second");
+    assert_eq!(state.current_line_offset(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_max_lines_drops_extra_newlines() {
+    let mut config = Config::default();
+    config.max_lines = Some(3);
+    config.input_mode = InputMode::MultiLine;
+    let mut state = InputState::with_config(80, 24, config);
+
+    for line in ["a", "b", "c"] {
+        type_text(&mut state, line);
+        state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    }
+    assert_eq!(state.buffer(), "a\nb\nc");
+    assert_eq!(state.title().as_deref(), Some("[max]"));
+    assert_eq!(state.prompt_options().title_fg, Some(Color::Red));
+    // The frame is 3 lines plus borders
+    assert_eq!(state.required_lines, 5);
+
+    // The 4th newline is dropped again
+    state.handle_key(KeyCode::Char('j'), KeyModifiers::CONTROL);
+    assert_eq!(state.buffer(), "a\nb\nc");
+    assert_eq!(state.title().as_deref(), Some("[max]"));
+
+    // Pasted newlines past the limit are dropped too
+    state.set_buffer("");
+    state.handle_paste("1\n2\n3\n4");
+    assert_eq!(state.buffer(), "1\n2\n34");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_max_lines_indicator_clears() {
+    let mut config = Config::default();
+    config.max_lines = Some(1);
+    config.input_mode = InputMode::MultiLine;
+    let mut state = InputState::with_config(80, 24, config);
+
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    assert_eq!(state.title().as_deref(), Some("[max]"));
+
+    // After two seconds
+    tokio::time::advance(MAX_LINES_INDICATOR_DURATION).await;
+    assert_eq!(state.title(), None);
+
+    // Or on the next key
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    assert_eq!(state.title().as_deref(), Some("[max]"));
+    state.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+    assert_eq!(state.title(), None);
+    assert_eq!(state.buffer(), "x");
+}
+
+#[test]
+fn test_hard_wrap_inserts_newline() {
+    let mut config = Config::default();
+    config.wrap_mode = WrapMode::Hard(40);
+    config.input_mode = InputMode::MultiLine;
+    let mut state = InputState::with_config(80, 24, config);
+
+    type_text(&mut state, &"x".repeat(80));
+    assert_eq!(state.buffer().matches('\n').count(), 1);
+    assert_eq!(state.buffer().find('\n'), Some(40));
+    assert_eq!(state.buffer().len(), 81);
+
+    // Wrapping newlines count against max_lines
+    let mut config = Config::default();
+    config.wrap_mode = WrapMode::Hard(4);
+    config.input_mode = InputMode::MultiLine;
+    config.max_lines = Some(2);
+    let mut state = InputState::with_config(80, 24, config);
+    type_text(&mut state, "abcdefghijkl");
+    assert_eq!(state.buffer(), "abcd\nefghijkl");
+}
+
+#[test]
+fn test_no_wrap_scrolls_to_cursor() {
+    let mut config = Config::default();
+    config.wrap_mode = WrapMode::None;
+    // 5 columns for text
+    let mut state = InputState::with_config(10, 24, config);
+
+    type_text(&mut state, "abcdefgh");
+    // The cursor sits after the last visible character
+    assert_eq!(state.display_text(), "efgh");
+    assert_eq!(state.required_lines, 3);
+    assert_eq!(state.cursor_position(), (4 + 4, 22));
+
+    state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    assert_eq!(state.display_text(), "abcde");
+    assert_eq!(state.cursor_position(), (4, 22));
+}
+
+fn left_click(column: u16, row: u16) -> MouseEvent {
+    MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_double_click_selects_word() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "hello world");
+
+    // The "r" of "world", on row 8 with text from column 4
+    assert!(state.handle_mouse(left_click(4 + 8, 8)));
+    assert_eq!(state.cursor_byte_offset, 8);
+    assert_eq!(state.selection_range(), None);
+
+    tokio::time::advance(Duration::from_millis(100)).await;
+    assert!(state.handle_mouse(left_click(4 + 8, 8)));
+    assert_eq!(state.selection_range(), Some(6..11));
+    assert_eq!(state.cursor_byte_offset, 11);
+
+    // A third click is a single click again
+    assert!(state.handle_mouse(left_click(4 + 8, 8)));
+    assert_eq!(state.selection_range(), None);
+
+    // Too slow
+    tokio::time::advance(DOUBLE_CLICK_INTERVAL).await;
+    assert!(state.handle_mouse(left_click(4 + 1, 8)));
+    assert_eq!(state.selection_range(), None);
+    assert_eq!(state.cursor_byte_offset, 1);
+
+    // On a space there is no word to select
+    assert!(state.handle_mouse(left_click(4 + 5, 8)));
+    assert!(!state.handle_mouse(left_click(4 + 5, 8)));
+    assert_eq!(state.selection_range(), None);
+}
+
+#[test]
+fn test_find_word_at_visual_pos() {
+    // 7 columns wrap "foo_bar baz" into "foo_bar" and " baz"
+    let text = "foo_bar baz";
+    assert_eq!(find_word_at_visual_pos(text, 12, 3, 0), Some((0, 7)));
+    assert_eq!(find_word_at_visual_pos(text, 12, 0, 1), None);
+    assert_eq!(find_word_at_visual_pos(text, 12, 1, 1), Some((8, 11)));
+    assert_eq!(find_word_at_visual_pos(text, 12, 5, 1), None);
+    assert_eq!(find_word_at_visual_pos("a, b", 80, 1, 0), None);
+}
+
+#[test]
+fn test_find_word_boundaries() {
+    let text = "foo.bar  baz";
+    // From inside a word, and from its start to the previous word
+    assert_eq!(find_word_boundary_left(text, 6), 4);
+    assert_eq!(find_word_boundary_left(text, 4), 0);
+    // Over the spaces, then the word
+    assert_eq!(find_word_boundary_left(text, 9), 4);
+    assert_eq!(find_word_boundary_right(text, 0), 3);
+    assert_eq!(find_word_boundary_right(text, 3), 7);
+    assert_eq!(find_word_boundary_right(text, 7), 12);
+
+    // The ends of the text
+    assert_eq!(find_word_boundary_left(text, 0), 0);
+    assert_eq!(find_word_boundary_right(text, text.len()), text.len());
+    assert_eq!(find_word_boundary_left("  ", 2), 0);
+    assert_eq!(find_word_boundary_right("  ", 0), 2);
+}
+
+#[test]
+fn test_find_word_boundaries_across_lines() {
+    let text = "one\ntwo";
+    assert_eq!(find_word_boundary_right(text, 0), 3);
+    assert_eq!(find_word_boundary_right(text, 3), 7);
+    assert_eq!(find_word_boundary_left(text, 4), 0);
+    assert_eq!(find_word_boundary_left(text, 7), 4);
+}
+
+#[test]
+fn test_find_word_boundaries_with_unicode_letters() {
+    // "é", "ñ" and "ü" are two bytes each and part of their words
+    let text = "café, señor über";
+    assert_eq!(find_word_boundary_right(text, 0), 5);
+    assert_eq!(find_word_boundary_right(text, 5), 13);
+    assert_eq!(find_word_boundary_right(text, 13), text.len());
+    assert_eq!(find_word_boundary_left(text, text.len()), 14);
+    assert_eq!(find_word_boundary_left(text, 14), 7);
+    assert_eq!(find_word_boundary_left(text, 7), 0);
+    // Digits are word characters too
+    assert_eq!(find_word_boundary_right("v2 ok", 0), 2);
+}
+
+#[test]
+fn test_line_lengths_follow_direct_buffer_edits() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "abc");
+    assert_eq!(state.line_lengths, vec![3]);
+
+    *state.buffer_mut() = "first\n\tx".to_string();
+    state.cursor_byte_offset = state.buffer().len();
+    type_text(&mut state, "y");
+    // The tab is displayed as 4 columns
+    assert_eq!(state.line_lengths, vec![5, 6]);
+    assert_eq!(state.required_lines, 4);
+}
+
+#[test]
+fn test_line_lengths_follow_same_length_buffer_edits() {
+    let mut state = InputState::new(20, 10);
+    type_text(&mut state, "abc");
+
+    // Same length as before, but two lines
+    *state.buffer_mut() = "a\nb".to_string();
+    type_text(&mut state, "x");
+    assert_eq!(state.buffer(), "a\nbx");
+    assert_eq!(state.line_lengths, vec![1, 2]);
+    assert_eq!(state.required_lines, 4);
+}
+
+#[test]
+fn test_wrap_cache_hits_for_same_text_and_width() {
+    let cache = WrapCache::new("héllo wörld", 10);
+    assert_eq!(cache.result, calculate_required_lines("héllo wörld", 10));
+    assert_eq!(cache.get("héllo wörld", 10), Some(cache.result));
+    assert_eq!(cache.get("héllo wörld", 11), None);
+    assert_eq!(cache.get("héllo world", 10), None);
+}
+
+#[test]
+fn test_wide_lines_are_measured_in_columns() {
+    let mut config = Config::default();
+    config.input_mode = InputMode::MultiLine;
+    let mut state = InputState::with_config(12, 10, config);
+    type_text(&mut state, "日本語");
+    state.handle_key(KeyCode::Enter, KeyModifiers::ALT);
+    type_text(&mut state, "abc");
+    assert_eq!(state.line_lengths, vec![6, 3]);
+    assert_eq!(state.required_lines, 4);
+
+    // A wide character that does not fit the row starts the next one
+    state.insert_at(0, "x本").unwrap();
+    assert_eq!(state.line_lengths, vec![9, 3]);
+    assert_eq!(state.required_lines, 5);
+
+    // A narrower terminal wraps the wide line again
+    state.handle_resize(8, 10);
+    assert_eq!(
+        state.required_lines,
+        calculate_required_lines(&state.display_text(), state.cols)
+    );
 }
@@ -0,0 +1,78 @@
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use termbox::Config;
+use termbox::app::run_with_events;
+use termbox::commands::tiktok::get_active_animations;
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+    Event::Key(KeyEvent::new(code, modifiers))
+}
+
+/// Key events typing `text`
+fn typed(text: &str) -> Vec<Event> {
+    text.chars()
+        .map(|c| key(KeyCode::Char(c), KeyModifiers::NONE))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_typed_text_is_submitted_on_enter() {
+    let mut events = typed("hello");
+    events.push(key(KeyCode::Enter, KeyModifiers::NONE));
+    events.push(key(KeyCode::Esc, KeyModifiers::NONE));
+
+    let submitted = run_with_events(events, Config::default()).await.unwrap();
+    assert_eq!(submitted, vec!["hello"]);
+}
+
+#[tokio::test]
+async fn test_ctrl_c_exits_without_submitting() {
+    let mut events = typed("draft");
+    events.push(key(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    // Never reached
+    events.push(key(KeyCode::Enter, KeyModifiers::NONE));
+
+    let submitted = run_with_events(events, Config::default()).await.unwrap();
+    assert!(submitted.is_empty());
+}
+
+#[tokio::test]
+async fn test_alt_enter_submits_multiline_text() {
+    let mut events = typed("one");
+    events.push(key(KeyCode::Enter, KeyModifiers::ALT));
+    events.extend(typed("two"));
+    events.push(key(KeyCode::Enter, KeyModifiers::NONE));
+
+    // The loop also ends when the events run out
+    let submitted = run_with_events(events, Config::default()).await.unwrap();
+    assert_eq!(submitted, vec!["one\ntwo"]);
+}
+
+#[tokio::test]
+async fn test_tiktok_command_spawns_animation() {
+    let mut events = typed("tiktok");
+    events.push(key(KeyCode::Enter, KeyModifiers::NONE));
+
+    let submitted = run_with_events(events, Config::default()).await.unwrap();
+    assert_eq!(submitted, vec!["tiktok"]);
+    assert!(get_active_animations() >= 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_double_click_selects_word_to_replace() {
+    let mut events = typed("hello world");
+    // The "r" of "world" on the only text row of the 80x24 terminal, twice
+    let click = Event::Mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 4 + 8,
+        row: 22,
+        modifiers: KeyModifiers::NONE,
+    });
+    events.extend([click.clone(), click]);
+    events.extend(typed("x"));
+    events.push(key(KeyCode::Enter, KeyModifiers::NONE));
+
+    let submitted = run_with_events(events, Config::default()).await.unwrap();
+    assert_eq!(submitted, vec!["hello x"]);
+}
@@ -1,5 +1,12 @@
 use crossterm::event::{KeyCode, KeyModifiers};
-use termbox::{InputState, capture_terminal_drawing, draw_prompt_line_to_buffer};
+use crossterm::style::{Color, SetForegroundColor};
+use std::sync::Arc;
+use termbox::prelude::*;
+use termbox::{
+    PromptOptions, ScrollEvent, TabCompletion, build_prompt_grid, draw_layout_frame_to_buffer,
+    draw_prompt_to_buffer, draw_styled_frame_to_buffer, draw_themed_frame_to_buffer,
+    output_line_count, ui,
+};
 
 #[test]
 fn test_exact_terminal_output_simple() {
@@ -12,7 +19,7 @@ fn test_exact_terminal_output_simple() {
     let drawing = capture_terminal_drawing(&state, |buffer| {
         draw_prompt_line_to_buffer(
             buffer,
-            &state.buffer,
+            state.buffer(),
             (state.cols, state.rows),
             state.required_lines,
         )
@@ -44,7 +51,7 @@ fn test_exact_terminal_output_multiline() {
     let drawing = capture_terminal_drawing(&state, |buffer| {
         draw_prompt_line_to_buffer(
             buffer,
-            &state.buffer,
+            state.buffer(),
             (state.cols, state.rows),
             state.required_lines,
         )
@@ -72,7 +79,7 @@ fn test_exact_terminal_output_wrapped() {
     let drawing = capture_terminal_drawing(&state, |buffer| {
         draw_prompt_line_to_buffer(
             buffer,
-            &state.buffer,
+            state.buffer(),
             (state.cols, state.rows),
             state.required_lines,
         )
@@ -116,7 +123,7 @@ fn test_exact_cursor_positioning_sequences() {
     let drawing = capture_terminal_drawing(&state, |buffer| {
         draw_prompt_line_to_buffer(
             buffer,
-            &state.buffer,
+            state.buffer(),
             (state.cols, state.rows),
             state.required_lines,
         )
@@ -164,7 +171,7 @@ fn test_terminal_output_with_unicode_content() {
     let drawing = capture_terminal_drawing(&state, |buffer| {
         draw_prompt_line_to_buffer(
             buffer,
-            &state.buffer,
+            state.buffer(),
             (state.cols, state.rows),
             state.required_lines,
         )
@@ -172,7 +179,953 @@ fn test_terminal_output_with_unicode_content() {
     .unwrap();
 
     // Should properly encode Unicode in the terminal output
-    assert!(drawing.contains("│ > Hi 🌍        │")); // Actual padding from debug output 
+    // Padded by columns, the emoji taking two, so the right border lines up
+    assert!(drawing.contains("│ > Hi 🌍          │"));
     assert!(drawing.contains("\x1B[6;1H╭──────────────────╮"));
     assert!(drawing.contains("\x1B[8;1H╰──────────────────╯"));
 }
+
+#[test]
+fn test_password_mode_output_has_no_plaintext() {
+    let mut state = InputState::new(20, 8);
+    state.set_mode(InputMode::Password);
+
+    for ch in "pass".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_line_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+        )
+    })
+    .unwrap();
+
+    for ch in "pas".chars() {
+        assert!(!drawing.contains(ch), "plaintext {:?} leaked", ch);
+    }
+    assert!(drawing.contains("\x1B[7;1H│ > ****           │"));
+    assert!(drawing.contains("\x1B[7;9H")); // Cursor after the four mask chars
+}
+
+#[test]
+fn test_password_mode_frame_is_titled_hidden() {
+    let mut state = InputState::new(20, 8);
+    state.set_mode(InputMode::Password);
+    state.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    assert!(drawing.contains("\x1B[6;1H╭─ [hidden] ───────╮"));
+    assert!(!drawing.contains('x'));
+}
+
+#[test]
+fn test_submit_error_title_drawn_in_red() {
+    let mut state = InputState::new(20, 8);
+    state.submit_error = Some("Not found".to_string());
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    assert!(drawing.contains("\x1B[6;1H╭─ Not found ──────╮"));
+    assert!(drawing.contains(&format!(
+        "\x1B[6;4H{}Not found",
+        SetForegroundColor(Color::Red)
+    )));
+}
+
+struct FixedCompleter;
+
+impl TabCompletion for FixedCompleter {
+    fn complete(&self, _prefix: &str) -> Vec<String> {
+        vec!["alpha".to_string(), "alphabet".to_string()]
+    }
+}
+
+#[test]
+fn test_completion_popup_rendered_below_text() {
+    let mut state = InputState::new(16, 10);
+    state.set_tab_completer(Arc::new(FixedCompleter));
+    state.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    // 5-line frame (1 text line + 2 candidates) starting at row 6
+    assert!(drawing.contains("\x1B[6;1H╭──────────────╮"));
+    assert!(drawing.contains("\x1B[7;1H│ > a          │"));
+    assert!(drawing.contains("\x1B[8;1H│   alpha      │"));
+    assert!(drawing.contains("\x1B[9;1H│   alphabet   │"));
+    assert!(drawing.contains("\x1B[10;1H╰──────────────╯"));
+    assert!(drawing.ends_with("\x1B[7;6H")); // Cursor stays on the text line
+}
+
+#[test]
+fn test_history_search_title_in_top_border() {
+    let mut state = InputState::new(40, 8);
+    for ch in "echo hi".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+    state.get_submitted_text();
+
+    state.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL);
+    state.handle_key(KeyCode::Char('e'), KeyModifiers::NONE);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    assert!(drawing.contains("\x1B[6;1H╭─ (reverse-i-search): e ──────────────╮"));
+    assert!(drawing.contains("\x1B[7;1H│ > echo hi"));
+}
+
+#[test]
+fn test_selection_rendered_with_background_color() {
+    let mut state = InputState::new(20, 8);
+    for ch in "abcdef".chars() {
+        state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+    }
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+    state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    // "de" is wrapped in a background color change and a reset
+    assert!(drawing.contains("\x1B[7;1H│ > abc\x1B[48;5;8mde\x1B[0mf         │"));
+    // Cursor sits at the start of the selection, after "abc"
+    assert!(drawing.ends_with("\x1B[7;8H"));
+}
+
+#[test]
+fn test_output_line_count_wraps_long_lines() {
+    assert_eq!(output_line_count("", 10), 1);
+    assert_eq!(output_line_count("hello", 10), 1);
+    assert_eq!(output_line_count("0123456789", 10), 1);
+    assert_eq!(output_line_count("0123456789a", 10), 2);
+    assert_eq!(output_line_count("a\n\nb", 10), 3);
+    // 25 characters wrap onto three rows, plus one short line
+    assert_eq!(
+        output_line_count(&format!("{}\nend", "x".repeat(25)), 10),
+        4
+    );
+    // Width is measured in columns, not bytes or characters
+    assert_eq!(output_line_count(&"█".repeat(10), 10), 1);
+    assert_eq!(output_line_count(&"日".repeat(5), 10), 1);
+    assert_eq!(output_line_count(&"日".repeat(6), 10), 2);
+    // A wide character that does not fit moves to the next row
+    assert_eq!(output_line_count(&format!("{}日", "x".repeat(9)), 10), 2);
+}
+
+#[test]
+fn test_print_to_scroll_region_scrolls_by_wrapped_line_count() {
+    let mut state = InputState::new(10, 10);
+    let mut scroll_rx = state.setup_scroll_broadcast();
+
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let lines = ui::print_to_scroll_region(&mut buffer, "one\n0123456789abc", &mut state).unwrap();
+    assert_eq!(lines, 3);
+    let drawing = String::from_utf8(buffer.into_inner()).unwrap();
+
+    assert!(drawing.starts_with("\x1B[7;1Hone\r\n0123456789abc\r\n"));
+    // The input box is redrawn after the output
+    assert!(drawing.contains("\x1B[8;1H╭────────╮"));
+    assert!(matches!(
+        scroll_rx.try_recv(),
+        Ok(ScrollEvent::ScrolledUp(3))
+    ));
+}
+
+#[test]
+fn test_write_str_to_scroll_region_returns_line_count() {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    let lines = ui::write_str_to_scroll_region(&mut buffer, "0123456789abc", 4, 10).unwrap();
+    assert_eq!(lines, 2);
+    let lines = ui::write_str_to_scroll_region(&mut buffer, "one\ntwo\n", 4, 10).unwrap();
+    assert_eq!(lines, 3);
+
+    let drawing = String::from_utf8(buffer.into_inner()).unwrap();
+    assert_eq!(
+        drawing,
+        "\x1B[5;1H0123456789abc\r\n\x1B[5;1Hone\r\ntwo\r\n\r\n"
+    );
+}
+
+#[test]
+fn test_notify_scroll_reaches_every_receiver() {
+    let mut state = InputState::new(10, 10);
+    // Nothing is sent, and nothing fails, before anyone subscribes
+    state.notify_scroll(ScrollEvent::ScrolledUp(1));
+    assert!(state.scroll_broadcast.is_none());
+
+    let mut first = state.setup_scroll_broadcast();
+    let mut second = state.setup_scroll_broadcast();
+    state.notify_scroll(ScrollEvent::ScrolledUp(2));
+    state.notify_scroll(ScrollEvent::ScrolledDown(1));
+
+    for rx in [&mut first, &mut second] {
+        assert!(matches!(rx.try_recv(), Ok(ScrollEvent::ScrolledUp(2))));
+        assert!(matches!(rx.try_recv(), Ok(ScrollEvent::ScrolledDown(1))));
+        assert!(rx.try_recv().is_err());
+    }
+
+    drop((first, second));
+    state.notify_scroll(ScrollEvent::ScrolledUp(3));
+}
+
+#[test]
+fn test_minimal_frame_style_draws_no_top_or_bottom_border() {
+    let mut config = Config::default();
+    config.frame_style = FrameStyle::Minimal;
+    let mut state = InputState::with_config(20, 10, config);
+    state.set_buffer("one\ntwo");
+    // The rows of the missing borders are still taken
+    assert_eq!(state.required_lines, 4);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        let dimensions = (state.cols, state.rows);
+        draw_styled_frame_to_buffer(
+            buffer,
+            dimensions,
+            state.required_lines,
+            &state.theme,
+            (state.effective_layout(), state.frame_style),
+        )?;
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            dimensions,
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    for border in ['╭', '╮', '╰', '╯', '─'] {
+        assert!(!drawing.contains(border), "{border} in {drawing:?}");
+    }
+    assert!(drawing.contains(&format!("\x1B[7;1H{}", " ".repeat(20))));
+    assert!(drawing.contains("│ > one"));
+    assert!(drawing.contains("│   two"));
+
+    let grid = build_prompt_grid(
+        &state.display_text(),
+        (state.cols, state.rows),
+        state.required_lines,
+        &state.prompt_options(),
+    );
+    let frame: Vec<String> = grid.cells[6..]
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.ch).collect())
+        .collect();
+    assert_eq!(
+        frame,
+        [
+            " ".repeat(20),
+            format!("│ > one{}│", " ".repeat(12)),
+            format!("│   two{}│", " ".repeat(12)),
+            " ".repeat(20),
+        ]
+    );
+}
+
+fn draw_themed(state: &InputState) -> String {
+    capture_terminal_drawing(state, |buffer| {
+        let dimensions = (state.cols, state.rows);
+        draw_themed_frame_to_buffer(buffer, dimensions, state.required_lines, &state.theme)?;
+        draw_prompt_to_buffer(
+            buffer,
+            state.buffer(),
+            dimensions,
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_default_theme_emits_no_color_escapes() {
+    let mut state = InputState::new(20, 10);
+    state.handle_key(KeyCode::Char('h'), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Char('i'), KeyModifiers::NONE);
+    state.completion_popup = vec!["hint".to_string()];
+
+    let drawing = draw_themed(&state);
+
+    assert!(!drawing.contains("\x1B[38;"), "{:?}", drawing);
+    assert!(!drawing.contains("\x1B[48;"), "{:?}", drawing);
+    assert!(!drawing.contains("\x1B[0m"), "{:?}", drawing);
+}
+
+#[test]
+fn test_dark_theme_colors_border_prompt_and_text() {
+    let mut config = Config::default();
+    config.theme = Theme::dark();
+    let mut state = InputState::with_config(20, 10, config);
+    state.handle_key(KeyCode::Char('h'), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Char('i'), KeyModifiers::NONE);
+
+    let drawing = draw_themed(&state);
+
+    // Dark cyan border, cyan prompt and white text, each reset afterwards
+    assert!(drawing.contains("\x1B[38;5;6m╭──────────────────╮\x1B[0m"));
+    assert!(drawing.contains("\x1B[38;5;6m│\x1B[0m\x1B[38;5;15m \x1B[0m\x1B[38;5;14m> \x1B[0m"));
+    assert!(drawing.contains("\x1B[38;5;15mhi\x1B[0m"));
+}
+
+#[test]
+fn test_prompt_grid_carries_theme_colors() {
+    let options = PromptOptions {
+        theme: Theme::light(),
+        ..Default::default()
+    };
+
+    let grid = build_prompt_grid("hi", (20, 10), 3, &options);
+
+    assert_eq!(grid.cells[7][0].fg, Theme::light().border_fg);
+    assert_eq!(grid.cells[8][2].fg, Theme::light().prompt_fg);
+    assert_eq!(grid.cells[8][4].fg, Theme::light().text_fg);
+}
+
+#[test]
+fn test_overwrite_mode_shows_ovr_in_bottom_border() {
+    let mut state = InputState::new(20, 8);
+    state.handle_key(KeyCode::Insert, KeyModifiers::NONE);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    assert!(drawing.contains("\x1B[8;1H╰──────────── OVR ─╯"));
+}
+
+#[test]
+fn test_word_count_shown_in_bottom_border() {
+    let mut config = Config::default();
+    config.status_mode = StatusMode::WordCount;
+    let mut state = InputState::with_config(20, 8, config);
+    state.set_buffer("hello world");
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    assert!(drawing.contains("\x1B[8;1H╰──────── 2 words ─╯"));
+}
+
+#[test]
+fn test_status_modes_shown_in_bottom_border() {
+    let modes = [
+        (StatusMode::CharCount, "╰─────── 11 chars ─╯"),
+        (StatusMode::LineCount, "╰───────── 1 line ─╯"),
+        (StatusMode::LineInfo, "╰──────────── 1:6 ─╯"),
+        (
+            StatusMode::Custom(Arc::new(|state| format!("{}!", state.buffer().len()))),
+            "╰──────────── 11! ─╯",
+        ),
+        (StatusMode::None, "╰──────────────────╯"),
+    ];
+    for (mode, border) in modes {
+        let mut config = Config::default();
+        config.status_mode = mode;
+        let mut state = InputState::with_config(20, 8, config);
+        state.set_buffer("hello world");
+        state.cursor_byte_offset = 5;
+
+        let drawing = capture_terminal_drawing(&state, |buffer| {
+            draw_prompt_to_buffer(
+                buffer,
+                &state.display_text(),
+                (state.cols, state.rows),
+                state.required_lines,
+                &state.prompt_options(),
+            )
+        })
+        .unwrap();
+
+        assert!(
+            drawing.contains(&format!("\x1B[8;1H{border}")),
+            "{border} in {drawing:?}"
+        );
+    }
+}
+
+#[test]
+fn test_narrow_terminal_layout_does_not_panic() {
+    for cols in [0, 1, 4, 5] {
+        assert!(calculate_required_lines("hello world", cols) >= 3);
+        calculate_cursor_position("hello world", cols, 10, 3);
+        render_text_lines("hello world", cols);
+
+        let mut state = InputState::new(cols, 10);
+        state.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+        state.handle_resize(cols, 12);
+    }
+}
+
+#[test]
+fn test_prompt_taller_than_terminal_is_cut_off() {
+    let mut state = InputState::new(20, 5);
+    state.set_mode(InputMode::MultiLine);
+    state.handle_paste("1\n2\n3\n4\n5\n6\n7\n8");
+    assert_eq!(state.required_lines, 10);
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    assert!(drawing.contains("\x1B[1;1H╭"));
+    assert!(drawing.contains("\x1B[5;1H╰"));
+    // Only the rows between the borders are drawn, scrolled to the cursor
+    assert!(drawing.contains("\x1B[2;1H│   6"));
+    assert!(drawing.contains("\x1B[4;1H│   8"));
+    assert!(!drawing.contains("│   5"));
+    assert!(drawing.ends_with("\x1B[4;6H"));
+}
+
+#[test]
+fn test_frame_taller_than_terminal_does_not_overflow() {
+    for (rows, required_lines) in [(3, 6), (5, 1), (5, 0), (0, 3)] {
+        let mut buffer = Vec::new();
+        draw_frame_to_buffer(&mut buffer, (20, rows), required_lines).unwrap();
+    }
+
+    let mut buffer = Vec::new();
+    draw_frame_to_buffer(&mut buffer, (20, 3), 6).unwrap();
+    let drawing = String::from_utf8(buffer).unwrap();
+    assert!(drawing.contains("\x1B[1;1H╭"));
+    assert!(drawing.contains("\x1B[2;1H│"));
+    assert!(drawing.contains("\x1B[3;1H╰"));
+}
+
+#[test]
+fn test_draw_frame_on_narrow_terminal_is_too_small_error() {
+    for cols in [0, 1, 4, 5] {
+        let mut buffer = Vec::new();
+        let err = draw_frame_to_buffer(&mut buffer, (cols, 10), 3).unwrap_err();
+
+        assert!(matches!(
+            err,
+            TermboxError::TerminalTooSmall { min_cols, actual_cols }
+                if min_cols == FRAME_CHARS + 1 && actual_cols == cols
+        ));
+        assert!(buffer.is_empty());
+    }
+
+    let mut buffer = Vec::new();
+    assert!(draw_frame_to_buffer(&mut buffer, (FRAME_CHARS + 1, 10), 3).is_ok());
+}
+
+/// Writer that fails every write
+struct BrokenPipe;
+
+impl std::io::Write for BrokenPipe {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_failure_is_io_error() {
+    let err = draw_frame_to_buffer(&mut BrokenPipe, (20, 10), 3).unwrap_err();
+
+    match err {
+        TermboxError::Io(io_err) => assert_eq!(io_err.kind(), std::io::ErrorKind::BrokenPipe),
+        other => panic!("expected Io, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_frame_filling_whole_terminal_stays_in_range() {
+    let state = InputState::new(10, 3);
+    assert_eq!(state.required_lines, state.rows);
+
+    let mut buffer = Vec::new();
+    draw_frame_to_buffer(&mut buffer, (state.cols, state.rows), state.required_lines).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+
+    assert_eq!(
+        output,
+        "\x1B[1;1H╭────────╮\x1B[2;1H│\x1B[2;10H│\x1B[3;1H╰────────╯"
+    );
+}
+
+#[test]
+fn test_push_content_up_is_noop_when_frame_fills_terminal() {
+    let mut buffer = Vec::new();
+    ui::push_content_up(&mut buffer, 3, 3).unwrap();
+    assert!(buffer.is_empty());
+
+    ui::push_content_up(&mut buffer, 10, 3).unwrap();
+    assert_eq!(buffer, b"\n\n\n");
+}
+
+#[test]
+fn test_print_to_scroll_region_keeps_printed_lines() {
+    let mut state = InputState::new(10, 8);
+    let mut buffer = std::io::Cursor::new(Vec::new());
+
+    ui::print_to_scroll_region(&mut buffer, "one\ntwo", &mut state).unwrap();
+    ui::print_to_scroll_region(&mut buffer, "three", &mut state).unwrap();
+
+    assert_eq!(state.content.last_n(5), vec!["one", "two", "three"]);
+}
+
+#[test]
+fn test_resize_redraws_stored_lines_at_bottom_of_scroll_region() {
+    let mut state = InputState::new(10, 8);
+    state.content.push_text("zero\nfirst\nsecond line\nthird");
+    state.handle_resize(8, 7);
+
+    let mut buffer = Vec::new();
+    ui::redraw_scroll_region(&mut buffer, &state).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+
+    // Rows 0-3 are above the 3-line frame; "second line" now wraps and
+    // pushes "zero" out
+    assert_eq!(
+        output,
+        "\x1B[1;1H        \x1B[1;1Hfirst\
+         \x1B[2;1H        \x1B[2;1Hsecond l\
+         \x1B[3;1H        \x1B[3;1Hine\
+         \x1B[4;1H        \x1B[4;1Hthird"
+    );
+}
+
+#[test]
+fn test_clear_scroll_region_blanks_rows_above_frame() {
+    let mut buffer = Vec::new();
+    ui::clear_scroll_region(&mut buffer, 7, 4, 5).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+
+    // Rows 0-2 are above the 4-line frame starting at row 3
+    assert_eq!(output, "\x1B[1;1H     \x1B[2;1H     \x1B[3;1H     ");
+}
+
+#[test]
+fn test_scrollbar_thumb_positions() {
+    // Everything fits: the thumb fills the viewport
+    assert_eq!(ui::compute_scrollbar(3, 5, 0), (0, 5));
+
+    // 20 lines in a 5 row viewport: a 1 row thumb moving over rows 0-4
+    assert_eq!(ui::compute_scrollbar(20, 5, 0), (0, 1));
+    assert_eq!(ui::compute_scrollbar(20, 5, 7), (2, 1));
+    assert_eq!(ui::compute_scrollbar(20, 5, 15), (4, 1));
+    // Offsets past the end stay at the bottom
+    assert_eq!(ui::compute_scrollbar(20, 5, 99), (4, 1));
+
+    // Half the lines visible: a thumb half the viewport tall
+    assert_eq!(ui::compute_scrollbar(8, 4, 0), (0, 2));
+    assert_eq!(ui::compute_scrollbar(8, 4, 2), (1, 2));
+    assert_eq!(ui::compute_scrollbar(8, 4, 4), (2, 2));
+}
+
+#[test]
+fn test_scrollbar_drawn_in_right_border_only_on_overflow() {
+    let frame = |buf: &str, required_lines: usize, scroll_offset: usize| -> Vec<String> {
+        let options = PromptOptions {
+            scroll_offset,
+            ..Default::default()
+        };
+        let grid = build_prompt_grid(buf, (10, 5), required_lines, &options);
+        grid.cells
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.ch).collect())
+            .collect()
+    };
+
+    // Everything fits: plain borders
+    assert_eq!(
+        frame("a\nb", 4, 0),
+        vec![
+            "          ",
+            "╭────────╮",
+            "│ > a    │",
+            "│   b    │",
+            "╰────────╯"
+        ]
+    );
+
+    // 6 lines in a 3 row viewport: a 2 row thumb, at the top unscrolled
+    // and at the bottom scrolled past the end
+    assert_eq!(
+        frame("a\nb\nc\nd\ne\nf", 8, 0),
+        vec![
+            "╭────────╮",
+            "│ > a    ▐",
+            "│   b    ▐",
+            "│   c    │",
+            "╰────────╯"
+        ]
+    );
+    assert_eq!(
+        frame("a\nb\nc\nd\ne\nf", 8, 99),
+        vec![
+            "╭────────╮",
+            "│   d    │",
+            "│   e    ▐",
+            "│   f    ▐",
+            "╰────────╯"
+        ]
+    );
+}
+
+#[test]
+fn test_scrolled_frame_keeps_cursor_on_its_row() {
+    let options = PromptOptions {
+        scroll_offset: 2,
+        popup: &["x".to_string()],
+        ..Default::default()
+    };
+    let drawing = capture_terminal_drawing(&InputState::new(10, 4), |buffer| {
+        draw_prompt_to_buffer(buffer, "a\nb\nc", (10, 4), 6, &options)
+    })
+    .unwrap();
+
+    // "a" and "b" are scrolled past, "c" and the candidate fill the viewport
+    assert!(!drawing.contains("> a"));
+    assert!(drawing.contains("   x"));
+    assert!(drawing.contains(ui::SCROLLBAR_THUMB));
+    // The cursor after "c" is on its scrolled row
+    assert!(drawing.ends_with("\x1B[2;6H"));
+}
+
+#[test]
+fn test_set_scroll_region_sequences() {
+    for (rows, required_lines, expected) in [
+        (24, 3, "\x1B[1;21r"),
+        (24, 5, "\x1B[1;19r"),
+        (10, 3, "\x1B[1;7r"),
+        // A frame filling the terminal still leaves a valid one-row region
+        (3, 3, "\x1B[1;1r"),
+    ] {
+        let mut buffer = Vec::new();
+        ui::set_scroll_region(&mut buffer, rows, required_lines).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+        assert_eq!(ui::scroll_region_sequence(rows, required_lines), expected);
+    }
+
+    let mut buffer = Vec::new();
+    ui::reset_scroll_region(&mut buffer).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "\x1B[r");
+}
+
+#[test]
+fn test_push_content_up_writes_one_newline_per_frame_line() {
+    for required_lines in [3, 4, 7] {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        ui::push_content_up(&mut buffer, 24, required_lines).unwrap();
+        assert_eq!(
+            buffer.into_inner(),
+            "\n".repeat(required_lines).into_bytes()
+        );
+    }
+}
+
+#[test]
+fn test_scroll_region_rows_and_frame_start_row() {
+    // (rows, text, scroll_region_rows, frame_start_row)
+    for (rows, text, bottom, frame_start) in [
+        (24, "", 20, 21),
+        (10, "a\nb\nc", 4, 5),
+        // The frame fills the terminal, or all but one row of it
+        (5, "a\nb\nc", 0, 0),
+        (6, "a\nb\nc", 0, 1),
+        (0, "", 0, 0),
+    ] {
+        let mut state = InputState::new(20, rows);
+        state.set_buffer(text);
+        assert_eq!(state.scroll_region_rows(), bottom, "{rows} rows, {text:?}");
+        assert_eq!(
+            state.frame_start_row(),
+            frame_start,
+            "{rows} rows, {text:?}"
+        );
+    }
+}
+
+/// Zero-based columns of every `MoveTo` in `drawing`
+fn move_to_columns(drawing: &str) -> Vec<usize> {
+    regex::Regex::new(r"\x1B\[\d+;(\d+)H")
+        .unwrap()
+        .captures_iter(drawing)
+        .map(|caps| caps[1].parse::<usize>().unwrap() - 1)
+        .collect()
+}
+
+#[test]
+fn test_side_by_side_draws_right_of_split_col() {
+    let mut config = Config::default();
+    config.layout = Layout::SideBySide { split_col: 40 };
+    let mut state = InputState::with_config(80, 10, config);
+    state.set_buffer(&"word ".repeat(20));
+
+    let drawing = capture_terminal_drawing(&state, |buffer| {
+        draw_layout_frame_to_buffer(
+            buffer,
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.theme,
+            state.effective_layout(),
+        )?;
+        draw_prompt_to_buffer(
+            buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+        )
+    })
+    .unwrap();
+
+    let columns = move_to_columns(&drawing);
+    assert!(!columns.is_empty());
+    assert!(columns.iter().all(|&col| col >= 40), "{columns:?}");
+    // The separator, then the frame in the 39 columns right of it
+    assert!(drawing.contains("\x1B[1;41H│"));
+    assert!(drawing.contains(&format!("\x1B[10;42H╰{}╯", "─".repeat(37))));
+    // 100 characters at 34 per row
+    assert_eq!(state.required_lines, 5);
+}
+
+#[test]
+fn test_multi_column_threshold_splits_wide_terminals() {
+    let mut config = Config::default();
+    config.multi_column_threshold = Some(160);
+    let mut state = InputState::with_config(200, 24, config);
+    assert_eq!(
+        state.effective_layout(),
+        Layout::SideBySide { split_col: 100 }
+    );
+
+    state.handle_resize(160, 24);
+    assert_eq!(state.effective_layout(), Layout::Single);
+}
+
+#[test]
+fn test_side_by_side_output_is_kept_left_of_separator() {
+    let mut config = Config::default();
+    config.layout = Layout::SideBySide { split_col: 10 };
+    let mut state = InputState::with_config(30, 10, config);
+    let mut buffer = Vec::new();
+
+    let lines = ui::print_to_scroll_region(&mut buffer, "0123456789abcde\nxy", &mut state).unwrap();
+
+    assert_eq!(lines, 3);
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("0123456789\r\nabcde\r\nxy\r\n"));
+    // The whole line is kept, to replay at any width
+    assert_eq!(state.content.last_n(2), vec!["0123456789abcde", "xy"]);
+
+    // Wide characters take two of the 10 columns each
+    let mut buffer = Vec::new();
+    let lines = ui::print_to_scroll_region(&mut buffer, "日本語日本語", &mut state).unwrap();
+    assert_eq!(lines, 2);
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("日本語日本\r\n語\r\n"));
+}
+
+#[test]
+fn test_frame_start_row_for_each_frame_position() {
+    // (position, frame_start_row, frame_end_row, scroll_region)
+    for (position, start, end, region) in [
+        (FramePosition::Bottom, 21, 24, 0..21),
+        (FramePosition::Top, 0, 3, 3..24),
+        (FramePosition::Center, 11, 14, 0..11),
+    ] {
+        let mut config = Config::default();
+        config.frame_position = position;
+        let state = InputState::with_config(20, 24, config);
+        assert_eq!(state.required_lines, 3);
+
+        assert_eq!(state.frame_start_row(), start, "{position:?}");
+        assert_eq!(state.frame_end_row(), end, "{position:?}");
+        assert_eq!(state.scroll_region(), region, "{position:?}");
+        assert_eq!(state.scroll_region_rows(), region.end - 1, "{position:?}");
+    }
+}
+
+#[test]
+fn test_frame_taller_than_terminal_ends_at_its_bottom() {
+    for position in [
+        FramePosition::Bottom,
+        FramePosition::Top,
+        FramePosition::Center,
+    ] {
+        let mut config = Config::default();
+        config.frame_position = position;
+        let mut state = InputState::with_config(20, 5, config);
+        state.set_buffer("1\n2\n3\n4\n5\n6");
+        assert_eq!(state.required_lines, 8);
+
+        assert_eq!(state.frame_start_row(), 0, "{position:?}");
+        assert_eq!(state.frame_end_row(), 5, "{position:?}");
+    }
+}
+
+#[test]
+fn test_cursor_position_follows_frame_position() {
+    // (position, row of the first line of text)
+    for (position, text_row) in [
+        (FramePosition::Bottom, 22),
+        (FramePosition::Top, 1),
+        (FramePosition::Center, 12),
+    ] {
+        let mut config = Config::default();
+        config.frame_position = position;
+        let mut state = InputState::with_config(20, 24, config);
+        state.set_buffer("hi");
+        assert_eq!(state.cursor_position(), (6, text_row), "{position:?}");
+    }
+}
+
+#[test]
+fn test_frame_at_top_scrolls_output_below_it() {
+    let mut config = Config::default();
+    config.frame_position = FramePosition::Top;
+    let mut state = InputState::with_config(20, 10, config);
+    let mut buffer = Vec::new();
+
+    ui::set_scroll_region_for(&mut buffer, &state).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "\x1B[4;10r");
+
+    let mut buffer = Vec::new();
+    ui::print_to_scroll_region(&mut buffer, "hello", &mut state).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    // Printed on the last row, then the frame is redrawn on the first three
+    assert!(output.starts_with("\x1B[10;1Hhello\r\n"));
+    assert!(output.contains("\x1B[1;1H╭"));
+    assert!(output.contains("\x1B[3;1H╰"));
+    assert!(!output.contains("\x1B[8;1H╭"));
+}
+
+/// Everything `drawing` prints after a `MoveTo` to 1-based `row`, up to the
+/// next `MoveTo`
+fn row_output(drawing: &str, row: usize) -> String {
+    let move_to = regex::Regex::new(r"\x1B\[(\d+);\d+H").unwrap();
+    let moves: Vec<_> = move_to.captures_iter(drawing).collect();
+    moves
+        .iter()
+        .enumerate()
+        .filter(|(_, caps)| caps[1].parse::<usize>().unwrap() == row)
+        .map(|(i, caps)| {
+            let end = moves
+                .get(i + 1)
+                .map_or(drawing.len(), |next| next.get(0).unwrap().start());
+            &drawing[caps.get(0).unwrap().end()..end]
+        })
+        .collect()
+}
+
+#[test]
+fn test_current_line_is_highlighted() {
+    let mut config = Config::default();
+    config.highlight_current_line = true;
+    config.theme.current_line_bg = Color::Blue;
+    let mut state = InputState::with_config(20, 10, config);
+    state.set_buffer("one\ntwo long enough to wrap\nthree");
+    state.cursor_byte_offset = 5; // in "two"
+
+    let draw = |state: &InputState, prev: Option<&termbox::grid::TerminalGrid>| {
+        let mut buffer = Vec::new();
+        let grid = termbox::draw_prompt_diff_to_buffer(
+            &mut buffer,
+            &state.display_text(),
+            (state.cols, state.rows),
+            state.required_lines,
+            &state.prompt_options(),
+            prev,
+        )
+        .unwrap();
+        (String::from_utf8(buffer).unwrap(), grid)
+    };
+    let blue_bg = "\x1B[48;5;12m";
+
+    // One row each for "one" and "three", two for the wrapped line
+    assert_eq!(state.required_lines, 6);
+    let (drawing, grid) = draw(&state, None);
+    assert!(!row_output(&drawing, 6).contains(blue_bg));
+    assert!(row_output(&drawing, 7).contains(blue_bg));
+    assert!(row_output(&drawing, 8).contains(blue_bg));
+    assert!(!row_output(&drawing, 9).contains(blue_bg));
+
+    // Moving to the first line repaints both lines
+    state.cursor_byte_offset = 1;
+    let (drawing, _) = draw(&state, Some(&grid));
+    assert!(row_output(&drawing, 6).contains(blue_bg));
+    assert!(row_output(&drawing, 7).contains("\x1B[49m"));
+    assert!(!row_output(&drawing, 7).contains(blue_bg));
+    assert!(!row_output(&drawing, 8).contains(blue_bg));
+}
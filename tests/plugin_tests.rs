@@ -0,0 +1,83 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::sync::{Arc, Mutex};
+use termbox::plugin::Plugin;
+use termbox::prelude::*;
+
+/// Records every key it sees and passes it on
+struct Recorder(Arc<Mutex<Vec<KeyCode>>>);
+
+impl Plugin for Recorder {
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    fn on_key(&self, key: KeyCode, _: KeyModifiers, _: &mut InputState) -> Option<KeyAction> {
+        self.0.lock().unwrap().push(key);
+        None
+    }
+}
+
+/// Turns F1 into typing the number of times it was pressed
+struct Counter;
+
+#[derive(Default)]
+struct Presses(usize);
+
+impl Plugin for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn on_key(&self, key: KeyCode, _: KeyModifiers, state: &mut InputState) -> Option<KeyAction> {
+        if key != KeyCode::F(1) {
+            return None;
+        }
+        let presses = state.plugin_state_mut::<Presses>();
+        presses.0 += 1;
+        let text = presses.0.to_string();
+        let end = state.buffer().len();
+        state.replace_range(end..end, &text).unwrap();
+        state.cursor_byte_offset = state.buffer().len();
+        Some(KeyAction::Continue)
+    }
+}
+
+#[test]
+fn test_plugin_sees_every_key() {
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let mut state = InputState::new(20, 10);
+    state.register_plugin(Recorder(keys.clone()));
+
+    state.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+
+    assert_eq!(
+        *keys.lock().unwrap(),
+        vec![KeyCode::Char('a'), KeyCode::Left, KeyCode::Backspace]
+    );
+    // Passed on to the built-in bindings
+    assert_eq!(state.buffer(), "a");
+}
+
+#[test]
+fn test_plugin_consumes_key_and_keeps_state() {
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let mut state = InputState::new(20, 10);
+    state.register_plugin(Counter);
+    state.register_plugin(Recorder(keys.clone()));
+
+    assert_eq!(
+        state.handle_key(KeyCode::F(1), KeyModifiers::NONE),
+        KeyAction::Continue
+    );
+    state.handle_key(KeyCode::F(1), KeyModifiers::NONE);
+    state.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+
+    assert_eq!(state.buffer(), "12x");
+    assert_eq!(state.plugin_state::<Presses>().map(|p| p.0), Some(2));
+    // The recorder, registered later, only saw the key the counter passed on
+    assert_eq!(*keys.lock().unwrap(), vec![KeyCode::Char('x')]);
+    assert_eq!(state.plugins.len(), 2);
+    assert_eq!(state.plugins[0].name(), "counter");
+}
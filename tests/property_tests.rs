@@ -0,0 +1,131 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use proptest::prelude::*;
+use termbox::{
+    Config, InputMode, InputState, TAB_WIDTH, calculate_cursor_position, calculate_required_lines,
+    display_width, expand_tabs,
+};
+
+/// State with auto-pairs off, so every typed character adds exactly itself
+fn plain_state(cols: usize, rows: usize) -> InputState {
+    let mut config = Config::default();
+    config.auto_pairs.clear();
+    InputState::with_config(cols, rows, config)
+}
+
+proptest! {
+    #[test]
+    fn typed_chars_end_up_in_buffer(
+        chars in prop::collection::vec(any::<char>(), 0..200),
+        (cols, rows) in (10usize..200, 5usize..50),
+    ) {
+        let mut state = plain_state(cols, rows);
+        for &ch in &chars {
+            state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+        }
+
+        prop_assert_eq!(state.buffer().len(), chars.iter().map(|c| c.len_utf8()).sum::<usize>());
+        prop_assert_eq!(state.buffer().chars().count(), chars.len());
+    }
+
+    #[test]
+    fn required_lines_tracks_buffer(
+        chars in prop::collection::vec(any::<char>(), 0..200),
+        (cols, rows) in (10usize..200, 5usize..50),
+    ) {
+        let mut state = plain_state(cols, rows);
+        for &ch in &chars {
+            state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+            prop_assert!(state.required_lines >= 3);
+            prop_assert_eq!(
+                state.required_lines,
+                calculate_required_lines(state.buffer(), state.cols)
+            );
+        }
+    }
+
+    #[test]
+    fn cursor_column_stays_within_terminal(
+        chars in prop::collection::vec(any::<char>(), 0..200),
+        (cols, rows) in (10usize..200, 5usize..50),
+    ) {
+        let mut state = plain_state(cols, rows);
+        for &ch in &chars {
+            state.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+            let (col, _row) = calculate_cursor_position(
+                state.buffer(),
+                state.cols,
+                state.rows,
+                state.required_lines,
+            );
+            prop_assert!(col <= state.cols, "column {} of {}", col, state.cols);
+        }
+    }
+
+    #[test]
+    fn backspace_on_empty_buffer_is_noop((cols, rows) in (10usize..200, 5usize..50)) {
+        let mut state = InputState::new(cols, rows);
+        state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+
+        prop_assert!(state.buffer().is_empty());
+        prop_assert_eq!(state.cursor_byte_offset, 0);
+        prop_assert_eq!(state.required_lines, 3);
+    }
+}
+
+/// An edit made through the public API
+#[derive(Debug, Clone)]
+enum Mutation {
+    Key(KeyCode, KeyModifiers),
+    Paste(String),
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    let key = prop_oneof![
+        any::<char>().prop_map(KeyCode::Char),
+        Just(KeyCode::Char('\t')),
+        Just(KeyCode::Backspace),
+        Just(KeyCode::Left),
+        Just(KeyCode::Right),
+        Just(KeyCode::Home),
+        Just(KeyCode::End),
+        Just(KeyCode::Tab),
+        Just(KeyCode::BackTab),
+    ];
+    prop_oneof![
+        4 => key.prop_map(|code| Mutation::Key(code, KeyModifiers::NONE)),
+        1 => Just(Mutation::Key(KeyCode::Enter, KeyModifiers::ALT)),
+        1 => Just(Mutation::Key(KeyCode::Left, KeyModifiers::SHIFT)),
+        1 => "[a-z\t\n é日]{0,12}".prop_map(Mutation::Paste),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn line_lengths_track_buffer(
+        mutations in prop::collection::vec(mutation(), 100),
+        cols in 10usize..60,
+    ) {
+        let mut config = Config::default();
+        config.input_mode = InputMode::MultiLine;
+        let mut state = InputState::with_config(cols, 24, config);
+        for mutation in mutations {
+            match mutation {
+                Mutation::Key(code, modifiers) => {
+                    state.handle_key(code, modifiers);
+                }
+                Mutation::Paste(text) => state.handle_paste(&text),
+            }
+
+            let expected: Vec<usize> = state
+                .buffer()
+                .split('\n')
+                .map(|line| display_width(&expand_tabs(line, TAB_WIDTH)))
+                .collect();
+            prop_assert_eq!(&state.line_lengths, &expected);
+            prop_assert_eq!(
+                state.required_lines,
+                calculate_required_lines(&state.display_text(), state.cols)
+            );
+        }
+    }
+}
@@ -0,0 +1,150 @@
+#![cfg(feature = "serde")]
+
+use std::path::PathBuf;
+use std::time::Duration;
+use termbox::app::spawn_auto_save;
+use termbox::error::TermboxError;
+use termbox::{Config, InputMode, InputState, InputStateSnapshot, Theme};
+use tokio::sync::watch;
+
+/// A path in the temp directory unique to this test run
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("termbox-{}-{}.json", std::process::id(), name))
+}
+
+#[test]
+fn test_snapshot_round_trips_through_json() {
+    let mut state = InputState::new(40, 10);
+    state.history = vec!["first".to_string(), "second".to_string()];
+    *state.buffer_mut() = "héllo".to_string();
+    state.cursor_byte_offset = 3;
+    let snapshot = state.snapshot();
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let restored: InputStateSnapshot = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored, snapshot);
+}
+
+#[test]
+fn test_restore_snapshot_replaces_state() {
+    let mut state = InputState::new(40, 10);
+    *state.buffer_mut() = "draft".to_string();
+    let snapshot = InputStateSnapshot {
+        buffer: "restored text".to_string(),
+        cursor_byte_offset: 8,
+        history: vec!["old".to_string()],
+    };
+
+    state.restore_snapshot(snapshot.clone());
+
+    assert_eq!(state.snapshot(), snapshot);
+}
+
+#[test]
+fn test_restore_snapshot_clamps_cursor() {
+    let mut state = InputState::new(40, 10);
+    state.restore_snapshot(InputStateSnapshot {
+        buffer: "é".to_string(),
+        cursor_byte_offset: 1,
+        history: Vec::new(),
+    });
+
+    // Byte 1 is inside "é", so the cursor moves back to the char boundary
+    assert_eq!(state.cursor_byte_offset, 0);
+}
+
+#[test]
+fn test_config_round_trips_through_json() {
+    let mut config = Config::default();
+    config.input_mode = InputMode::Password;
+    config.mask_char = '•';
+    config.theme = Theme::dark();
+
+    let json = serde_json::to_string(&config).unwrap();
+    let restored: Config = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored, config);
+}
+
+#[test]
+fn test_save_and_load_restores_buffer() {
+    let path = temp_path("save-load");
+    let mut state = InputState::new(40, 10);
+    *state.buffer_mut() = "unsent draft".to_string();
+    state.cursor_byte_offset = 6;
+    state.history = vec!["echo hi".to_string()];
+
+    state.save_to_file(&path).unwrap();
+    *state.buffer_mut() = "something else".to_string();
+    state.history.push("later".to_string());
+    state.restore_snapshot(InputState::load_from_file(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(state.buffer(), "unsent draft");
+    assert_eq!(state.cursor_byte_offset, 6);
+    assert_eq!(state.history, vec!["echo hi"]);
+}
+
+#[test]
+fn test_save_leaves_no_temp_file() {
+    let path = temp_path("no-tmp");
+    InputState::new(40, 10).save_to_file(&path).unwrap();
+
+    let mut tmp_path = path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    assert!(path.exists());
+    assert!(!PathBuf::from(tmp_path).exists());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_password_buffer_is_not_snapshotted() {
+    let mut state = InputState::new(40, 10);
+    state.set_mode(InputMode::Password);
+    *state.buffer_mut() = "hunter2".to_string();
+    state.cursor_byte_offset = 7;
+
+    let snapshot = state.snapshot();
+    assert_eq!(snapshot.buffer, "");
+    assert_eq!(snapshot.cursor_byte_offset, 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_auto_save_writes_changed_snapshots() {
+    let path = temp_path("auto-save");
+    let (snapshot_tx, snapshot_rx) = watch::channel(InputStateSnapshot::default());
+    let task = spawn_auto_save(path.clone(), Duration::from_secs(5), snapshot_rx);
+
+    snapshot_tx.send_replace(InputStateSnapshot {
+        buffer: "typed so far".to_string(),
+        ..InputStateSnapshot::default()
+    });
+    tokio::time::sleep(Duration::from_secs(6)).await;
+
+    let saved = InputState::load_from_file(&path).unwrap();
+    assert_eq!(saved.buffer, "typed so far");
+
+    // Dropping the sender ends the task
+    drop(snapshot_tx);
+    tokio::time::sleep(Duration::from_secs(6)).await;
+    assert!(task.is_finished());
+    assert!(task.await.unwrap().is_ok());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_auto_save_returns_failed_save() {
+    let path = temp_path("missing-dir").join("session.json");
+    let (snapshot_tx, snapshot_rx) = watch::channel(InputStateSnapshot::default());
+    let task = spawn_auto_save(path, Duration::from_secs(5), snapshot_rx);
+
+    snapshot_tx.send_replace(InputStateSnapshot {
+        buffer: "unsaved".to_string(),
+        ..InputStateSnapshot::default()
+    });
+    tokio::time::sleep(Duration::from_secs(6)).await;
+
+    drop(snapshot_tx);
+    assert!(matches!(task.await.unwrap(), Err(TermboxError::Io(_))));
+}
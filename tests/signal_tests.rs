@@ -0,0 +1,43 @@
+use std::cell::Cell;
+use termbox::signals::next_window_size;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_window_change_reports_new_size() {
+    let (tx, mut rx) = mpsc::channel(4);
+    tx.send(()).await.unwrap();
+
+    let size = next_window_size(&mut rx, (80, 24), || Ok((100, 30))).await;
+    assert_eq!(size, Some((100, 30)));
+}
+
+#[tokio::test]
+async fn test_window_change_without_size_change_is_skipped() {
+    let (tx, mut rx) = mpsc::channel(4);
+    let queries = Cell::new(0);
+    // The first signal repeats a resize that was already handled
+    tx.send(()).await.unwrap();
+    tx.send(()).await.unwrap();
+
+    let size = next_window_size(&mut rx, (80, 24), || {
+        queries.set(queries.get() + 1);
+        Ok(if queries.get() == 1 {
+            (80, 24)
+        } else {
+            (60, 20)
+        })
+    })
+    .await;
+
+    assert_eq!(size, Some((60, 20)));
+    assert_eq!(queries.get(), 2);
+}
+
+#[tokio::test]
+async fn test_closed_window_change_channel_ends_wait() {
+    let (tx, mut rx) = mpsc::channel::<()>(4);
+    drop(tx);
+
+    let size = next_window_size(&mut rx, (80, 24), || Ok((100, 30))).await;
+    assert_eq!(size, None);
+}
@@ -0,0 +1,72 @@
+//! Snapshots of the rendered input box for a matrix of texts and widths.
+//!
+//! After an intended change to the frame or wrapping, review the new output
+//! with `cargo insta review`.
+
+use termbox::{
+    InputState, calculate_required_lines, capture_terminal_drawing, draw_frame_to_buffer,
+    draw_prompt_line_to_buffer, render_text_lines, to_strings,
+};
+
+const ROWS: usize = 12;
+const WIDTHS: [usize; 3] = [12, 20, 40];
+
+const INPUTS: [(&str, &str); 5] = [
+    ("empty", ""),
+    ("short", "hello"),
+    (
+        "long_wrapped",
+        "the quick brown fox jumps over the lazy dog and keeps on running",
+    ),
+    ("multiline", "first line\n\nthird line"),
+    ("unicode", "héllo wörld 世界 🎉"),
+];
+
+/// `render_text_lines` output at every width, one block per width
+fn rendered_lines(text: &str) -> String {
+    WIDTHS
+        .iter()
+        .map(|&cols| {
+            format!(
+                "cols = {}\n{}\n",
+                cols,
+                to_strings(&render_text_lines(text, cols)).join("\n")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape sequences drawn for the frame and prompt at every width, one
+/// sequence per line with the escape character spelled out
+fn drawn_output(text: &str) -> String {
+    WIDTHS
+        .iter()
+        .map(|&cols| {
+            let state = InputState::new(cols, ROWS);
+            let required_lines = calculate_required_lines(text, cols);
+            let drawing = capture_terminal_drawing(&state, |buffer| {
+                draw_frame_to_buffer(buffer, (cols, ROWS), required_lines)?;
+                draw_prompt_line_to_buffer(buffer, text, (cols, ROWS), required_lines)
+            })
+            .unwrap();
+            let sequences = drawing.replace('\x1B', "\n\\x1B");
+            format!("cols = {}{}\n", cols, sequences)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_render_text_lines_snapshots() {
+    for (name, text) in INPUTS {
+        insta::assert_snapshot!(format!("render_text_lines_{}", name), rendered_lines(text));
+    }
+}
+
+#[test]
+fn test_terminal_drawing_snapshots() {
+    for (name, text) in INPUTS {
+        insta::assert_snapshot!(format!("terminal_drawing_{}", name), drawn_output(text));
+    }
+}
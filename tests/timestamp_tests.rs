@@ -0,0 +1,61 @@
+#![cfg(feature = "timestamps")]
+
+use chrono::{FixedOffset, TimeZone, Utc};
+use regex::Regex;
+use termbox::content::ContentBuffer;
+use termbox::format::{OutputFormatter, TimestampFormatter, timestamp_prefix};
+use termbox::prelude::*;
+use termbox::ui;
+
+#[test]
+fn test_timestamp_formatter_prefixes_local_time() {
+    let formatted = TimestampFormatter.format("started\ndone", 80);
+    let timestamped = Regex::new(r"^\d{2}:\d{2}:\d{2} (started|done)$").unwrap();
+    assert_eq!(formatted.lines().count(), 2);
+    for line in formatted.lines() {
+        assert!(timestamped.is_match(line), "{line:?}");
+    }
+}
+
+#[test]
+fn test_timestamp_prefix_is_zero_padded_24_hour_time() {
+    let morning = Utc.with_ymd_and_hms(2024, 3, 1, 9, 5, 3).unwrap();
+    assert_eq!(timestamp_prefix(&morning), "09:05:03 ");
+
+    // Formatted in the time's own zone
+    let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+    let evening = offset.with_ymd_and_hms(2024, 3, 1, 23, 59, 0).unwrap();
+    assert_eq!(timestamp_prefix(&evening), "23:59:00 ");
+}
+
+#[test]
+fn test_content_lines_are_in_chronological_order() {
+    let mut content = ContentBuffer::default();
+    for i in 0..20 {
+        content.push_line(i.to_string());
+    }
+
+    let lines: Vec<_> = content.iter().collect();
+    assert_eq!(lines.len(), 20);
+    for pair in lines.windows(2) {
+        assert!(pair[0].submitted_at <= pair[1].submitted_at);
+        assert_eq!(pair[0].line_number + 1, pair[1].line_number);
+    }
+}
+
+#[test]
+fn test_show_timestamps_prefixes_printed_lines() {
+    let mut config = Config::default();
+    config.show_timestamps = true;
+    let mut state = InputState::with_config(40, 10, config);
+    let mut buffer = Vec::new();
+
+    ui::print_to_scroll_region(&mut buffer, "one\ntwo", &mut state).unwrap();
+
+    let timestamped = Regex::new(r"^\d{2}:\d{2}:\d{2} (one|two)$").unwrap();
+    let lines = state.content.last_n(2);
+    assert_eq!(lines.len(), 2);
+    for line in lines {
+        assert!(timestamped.is_match(line), "{line:?}");
+    }
+}